@@ -0,0 +1,131 @@
+#import bevy_pbr::mesh_view_bindings
+#import bevy_pbr::mesh_bindings
+#import bevy_pbr::mesh_functions
+
+#import bevy_pbr::pbr_types
+#import bevy_pbr::utils
+#import bevy_pbr::clustered_forward
+#import bevy_pbr::lighting
+#import bevy_pbr::shadows
+#import bevy_pbr::pbr_functions
+
+struct ColormapMaterial {
+    colormap: u32,
+    field: u32,
+    range: vec2<f32>,
+    unlit: u32,
+};
+
+@group(1) @binding(0)
+var<uniform> colormap_material: ColormapMaterial;
+
+let COLORMAP_VIRIDIS: u32 = 0u;
+let COLORMAP_MAGMA: u32 = 1u;
+let COLORMAP_DIVERGING: u32 = 2u;
+
+struct Vertex {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) fields: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_position: vec4<f32>,
+    @location(1) world_normal: vec3<f32>,
+    @location(2) fields: vec4<f32>,
+};
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    let world_position = mesh_position_local_to_world(mesh.model, vec4<f32>(vertex.position, 1.0));
+
+    var out: VertexOutput;
+    out.clip_position = mesh_position_world_to_clip(world_position);
+    out.world_position = world_position;
+    out.world_normal = mesh_normal_local_to_world(vertex.normal);
+    out.fields = vertex.fields;
+
+    return out;
+}
+
+struct Fragment {
+    @builtin(front_facing) is_front: bool,
+    @builtin(position) frag_coord: vec4<f32>,
+    @location(0) world_position: vec4<f32>,
+    @location(1) world_normal: vec3<f32>,
+    @location(2) fields: vec4<f32>,
+};
+
+// Polynomial fits of the matplotlib colormaps, in sRGB.
+fn viridis(t: f32) -> vec3<f32> {
+    let c0 = vec3<f32>(0.2777273272234177, 0.005407344544966578, 0.3340998053353061);
+    let c1 = vec3<f32>(0.1050930431085774, 1.404613529898575, 1.384590162594685);
+    let c2 = vec3<f32>(-0.3308618287255563, 0.214847559468213, 0.09509516302823659);
+    let c3 = vec3<f32>(-4.634230498983486, -5.799100973351585, -19.33244095627987);
+    let c4 = vec3<f32>(6.228269936347081, 14.17993336680509, 56.69055260068105);
+    let c5 = vec3<f32>(4.776384997670288, -13.74514537774601, -65.35303263337234);
+    let c6 = vec3<f32>(-5.435455855934631, 4.645852612178535, 26.3124352495832);
+    return c0 + t * (c1 + t * (c2 + t * (c3 + t * (c4 + t * (c5 + t * c6)))));
+}
+
+fn magma(t: f32) -> vec3<f32> {
+    let c0 = vec3<f32>(-0.002136485053939582, -0.000749655052795221, -0.005386127855323933);
+    let c1 = vec3<f32>(0.2516605407371642, 0.6775232436837668, 2.494026599312351);
+    let c2 = vec3<f32>(8.353717279216625, -3.577719514958484, 0.3144679030132573);
+    let c3 = vec3<f32>(-27.66873308576866, 14.26473078096533, -13.64921318813922);
+    let c4 = vec3<f32>(52.17613981234068, -27.94360607168351, 12.94416944238394);
+    let c5 = vec3<f32>(-50.76852536473588, 29.04658282127291, 4.23415299384598);
+    let c6 = vec3<f32>(18.65570506591883, -11.48977351997711, -5.601961508734096);
+    return c0 + t * (c1 + t * (c2 + t * (c3 + t * (c4 + t * (c5 + t * c6)))));
+}
+
+// Moreland's cool to warm endpoints around a light grey midpoint.
+fn diverging(t: f32) -> vec3<f32> {
+    let cool = vec3<f32>(0.230, 0.299, 0.754);
+    let middle = vec3<f32>(0.865, 0.865, 0.865);
+    let warm = vec3<f32>(0.706, 0.016, 0.150);
+    if (t < 0.5) {
+        return mix(cool, middle, t * 2.0);
+    }
+    return mix(middle, warm, t * 2.0 - 1.0);
+}
+
+fn srgb_to_linear(color: vec3<f32>) -> vec3<f32> {
+    return pow(clamp(color, vec3<f32>(0.0), vec3<f32>(1.0)), vec3<f32>(2.2));
+}
+
+@fragment
+fn fragment(in: Fragment) -> @location(0) vec4<f32> {
+    var fields = in.fields;
+    let value = fields[min(colormap_material.field, 3u)];
+    let range = colormap_material.range;
+    let t = clamp((value - range.x) / max(range.y - range.x, 0.0001), 0.0, 1.0);
+
+    var color: vec3<f32>;
+    if (colormap_material.colormap == COLORMAP_MAGMA) {
+        color = magma(t);
+    } else if (colormap_material.colormap == COLORMAP_DIVERGING) {
+        color = diverging(t);
+    } else {
+        color = viridis(t);
+    }
+    var output_color = vec4<f32>(srgb_to_linear(color), 1.0);
+
+    if (colormap_material.unlit == 0u) {
+        var pbr_input = pbr_input_new();
+        pbr_input.material.base_color = output_color;
+        pbr_input.frag_coord = in.frag_coord;
+        pbr_input.world_position = in.world_position;
+        pbr_input.world_normal = prepare_world_normal(in.world_normal, true, in.is_front);
+        pbr_input.is_orthographic = view.projection[3].w == 1.0;
+        pbr_input.N = normalize(pbr_input.world_normal);
+        pbr_input.V = calculate_view(in.world_position, pbr_input.is_orthographic);
+        output_color = pbr(pbr_input);
+    }
+
+#ifdef TONEMAP_IN_SHADER
+    output_color = tone_mapping(output_color);
+#endif
+    return output_color;
+}
@@ -0,0 +1,247 @@
+// The previous generation, and the texture the next one is written to. The red and green channels
+// hold each cell: Lenia's value, or Gray-Scott's `u` and `v`.
+@group(0) @binding(0)
+var input: texture_2d<f32>;
+@group(0) @binding(1)
+var output: texture_storage_2d<rgba32float, write>;
+
+struct Automaton {
+    kind: u32,
+    radius: i32,
+    mu: f32,
+    sigma: f32,
+    dt: f32,
+    feed: f32,
+    kill: f32,
+    diffusion: vec2<f32>,
+};
+@group(0) @binding(2)
+var<uniform> automaton: Automaton;
+
+let KIND_LENIA: u32 = 0u;
+let KIND_GRAY_SCOTT: u32 = 1u;
+
+// What neighbours past the edge of the grid are.
+struct Grid {
+    boundary: u32,
+};
+@group(0) @binding(3)
+var<uniform> grid: Grid;
+
+let BOUNDARY_DEAD: u32 = 0u;
+let BOUNDARY_TOROIDAL: u32 = 1u;
+let BOUNDARY_MIRRORED: u32 = 2u;
+
+// The weights of the square of cells within `automaton.radius`, a row at a time from the top left.
+struct Kernel {
+    weights: array<f32>,
+};
+@group(0) @binding(4)
+var<storage, read> convolution_kernel: Kernel;
+
+// Only bound for `init`.
+struct Seed {
+    offset: vec2<i32>,
+    mode: u32,
+    // Offsets the hash of the random initialization.
+    seed: u32,
+};
+@group(1) @binding(1)
+var<uniform> seed: Seed;
+
+let SEED_CLEAR: u32 = 3u;
+let SEED_BLOCK_SIZE: u32 = 16u;
+
+// Capsules of cells painted with the mouse, only bound for `paint`.
+struct Stroke {
+    start: vec2<f32>,
+    end: vec2<f32>,
+    radius: f32,
+    state: u32,
+};
+struct Paint {
+    min: vec2<u32>,
+    count: u32,
+    strokes: array<Stroke>,
+};
+@group(1) @binding(2)
+var<storage, read> paint_strokes: Paint;
+
+// The displayed texture, only bound for `colour`.
+@group(1) @binding(3)
+var display: texture_storage_2d<rgba8unorm, write>;
+
+fn hash(value: u32) -> u32 {
+    var state = value;
+    state = state ^ 2747636419u;
+    state = state * 2654435769u;
+    state = state ^ state >> 16u;
+    state = state * 2654435769u;
+    state = state ^ state >> 16u;
+    state = state * 2654435769u;
+    return state;
+}
+
+fn randomFloat(value: u32) -> f32 {
+    return f32(hash(value)) / 4294967295.0;
+}
+
+// A cell with nothing in it, as cleared or erased.
+fn empty() -> vec2<f32> {
+    if (automaton.kind == KIND_GRAY_SCOTT) {
+        return vec2<f32>(1.0, 0.0);
+    }
+    return vec2<f32>(0.0);
+}
+
+// A cell of `value` strength, as randomly initialized or painted.
+fn filled(value: f32) -> vec2<f32> {
+    if (automaton.kind == KIND_GRAY_SCOTT) {
+        return vec2<f32>(0.5, 0.25);
+    }
+    return vec2<f32>(value, 0.0);
+}
+
+fn store_cell(location: vec2<i32>, cell: vec2<f32>) {
+    textureStore(output, location, vec4<f32>(cell, 0.0, 0.0));
+}
+
+// Fills blocks of cells at random, each cell with a random value.
+@compute @workgroup_size(8, 8, 1)
+fn init(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
+    let location = vec2<i32>(i32(invocation_id.x), i32(invocation_id.y));
+    let size = textureDimensions(output);
+    if (any(location >= size)) {
+        return;
+    }
+
+    var cell = empty();
+    if (seed.mode != SEED_CLEAR) {
+        let offset = seed.seed * 2654435769u;
+        let block = invocation_id.xy / SEED_BLOCK_SIZE;
+        if (randomFloat(((block.y << 16u) | block.x) + offset) > 0.8) {
+            let index = invocation_id.y * u32(size.x) + invocation_id.x + offset;
+            cell = filled(randomFloat(index ^ 2654435769u));
+        }
+    }
+    store_cell(location, cell);
+}
+
+// Fills or empties the cells under this frame's strokes, dispatched over the texels they cover.
+@compute @workgroup_size(8, 8, 1)
+fn paint(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
+    let location = vec2<i32>(paint_strokes.min + invocation_id.xy);
+    if (any(location >= textureDimensions(output))) {
+        return;
+    }
+
+    let center = vec2<f32>(location) + 0.5;
+    var painted = false;
+    var state = 0u;
+    for (var i = 0u; i < paint_strokes.count; i = i + 1u) {
+        let stroke = paint_strokes.strokes[i];
+        let segment = stroke.end - stroke.start;
+        let t = clamp(dot(center - stroke.start, segment) / max(dot(segment, segment), 0.0001), 0.0, 1.0);
+        if (distance(center, stroke.start + segment * t) <= stroke.radius) {
+            painted = true;
+            state = stroke.state;
+        }
+    }
+    if (painted) {
+        if (state == 0u) {
+            store_cell(location, empty());
+        } else {
+            store_cell(location, filled(1.0));
+        }
+    }
+}
+
+// The cell standing in for `coordinate` along an axis of `size` cells, or -1 if it is dead.
+fn boundary_coordinate(coordinate: i32, size: i32) -> i32 {
+    if (coordinate >= 0 && coordinate < size) {
+        return coordinate;
+    }
+    if (grid.boundary == BOUNDARY_TOROIDAL) {
+        return (coordinate % size + size) % size;
+    }
+    if (grid.boundary == BOUNDARY_MIRRORED) {
+        if (coordinate < 0) {
+            return min(-coordinate - 1, size - 1);
+        }
+        return max(2 * size - coordinate - 1, 0);
+    }
+    return -1;
+}
+
+// Dead cells past the edge are empty.
+fn load_cell(location: vec2<i32>, offset: vec2<i32>) -> vec2<f32> {
+    let size = textureDimensions(input);
+    let neighbour = vec2<i32>(
+        boundary_coordinate(location.x + offset.x, size.x),
+        boundary_coordinate(location.y + offset.y, size.y)
+    );
+    if (any(neighbour < vec2<i32>(0))) {
+        return empty();
+    }
+    return textureLoad(input, neighbour, 0).xy;
+}
+
+fn convolve(location: vec2<i32>) -> vec2<f32> {
+    let radius = automaton.radius;
+    let side = 2 * radius + 1;
+    var convolution = vec2<f32>(0.0);
+    for (var y = -radius; y <= radius; y = y + 1) {
+        for (var x = -radius; x <= radius; x = x + 1) {
+            let weight = convolution_kernel.weights[(y + radius) * side + x + radius];
+            if (weight != 0.0) {
+                convolution = convolution + weight * load_cell(location, vec2<i32>(x, y));
+            }
+        }
+    }
+    return convolution;
+}
+
+@compute @workgroup_size(8, 8, 1)
+fn update(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
+    let location = vec2<i32>(i32(invocation_id.x), i32(invocation_id.y));
+    if (any(location >= textureDimensions(output))) {
+        return;
+    }
+
+    let cell = textureLoad(input, location, 0).xy;
+    let convolution = convolve(location);
+    var next: vec2<f32>;
+    if (automaton.kind == KIND_GRAY_SCOTT) {
+        let reaction = cell.x * cell.y * cell.y;
+        let feed = automaton.feed;
+        let change = vec2<f32>(
+            automaton.diffusion.x * convolution.x - reaction + feed * (1.0 - cell.x),
+            automaton.diffusion.y * convolution.y + reaction - (feed + automaton.kill) * cell.y
+        );
+        next = clamp(cell + automaton.dt * change, vec2<f32>(0.0), vec2<f32>(1.0));
+    } else {
+        let deviation = (convolution.x - automaton.mu) / automaton.sigma;
+        let growth = 2.0 * exp(-deviation * deviation / 2.0) - 1.0;
+        next = vec2<f32>(clamp(cell.x + automaton.dt * growth, 0.0, 1.0), 0.0);
+    }
+    store_cell(location, next);
+}
+
+// Shades Lenia's value, or Gray-Scott's `v`, from dark blue through blue to pale yellow.
+@compute @workgroup_size(8, 8, 1)
+fn colour(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
+    let location = vec2<i32>(i32(invocation_id.x), i32(invocation_id.y));
+    if (any(location >= textureDimensions(display))) {
+        return;
+    }
+
+    let cell = textureLoad(input, location, 0).xy;
+    var value = cell.x;
+    if (automaton.kind == KIND_GRAY_SCOTT) {
+        // `v` rarely rises past a half.
+        value = min(cell.y * 2.0, 1.0);
+    }
+    let low = mix(vec3<f32>(0.0, 0.0, 0.05), vec3<f32>(0.1, 0.4, 0.9), smoothstep(0.0, 0.5, value));
+    let shade = mix(low, vec3<f32>(1.0, 0.95, 0.6), smoothstep(0.5, 1.0, value));
+    textureStore(display, location, vec4<f32>(shade, 1.0));
+}
@@ -1,5 +1,82 @@
+// The previous generation, and the texture the next one is written to. The red channel holds each
+// cell's state, as `state / 255`.
 @group(0) @binding(0)
-var texture: texture_storage_2d<rgba8unorm, read_write>;
+var input: texture_2d<f32>;
+@group(0) @binding(1)
+var output: texture_storage_2d<rgba8unorm, write>;
+
+// Bit `n` of `birth` and `survival` is set when a cell with `n` live neighbours, those in state 1,
+// is born or survives.
+struct Automaton {
+    birth: array<vec4<u32>, 2>,
+    survival: array<vec4<u32>, 2>,
+    neighbourhood: u32,
+    radius: i32,
+    states: u32,
+    transition: u32,
+    include_center: u32,
+};
+@group(0) @binding(2)
+var<uniform> automaton: Automaton;
+
+let NEIGHBOURHOOD_MOORE: u32 = 0u;
+let NEIGHBOURHOOD_VON_NEUMANN: u32 = 1u;
+let NEIGHBOURHOOD_HEX: u32 = 2u;
+
+let TRANSITION_GENERATIONS: u32 = 0u;
+let TRANSITION_WIREWORLD: u32 = 1u;
+
+// What neighbours past the edge of the grid are.
+struct Grid {
+    boundary: u32,
+};
+@group(0) @binding(3)
+var<uniform> grid: Grid;
+
+let BOUNDARY_DEAD: u32 = 0u;
+let BOUNDARY_TOROIDAL: u32 = 1u;
+let BOUNDARY_MIRRORED: u32 = 2u;
+
+// A pattern stamped over the random initialization, only bound for `init`.
+struct Seed {
+    offset: vec2<i32>,
+    mode: u32,
+    // Offsets the hash of the random initialization.
+    seed: u32,
+};
+@group(1) @binding(0)
+var pattern: texture_2d<f32>;
+@group(1) @binding(1)
+var<uniform> seed: Seed;
+
+let SEED_RANDOM: u32 = 0u;
+let SEED_REPLACE: u32 = 1u;
+let SEED_COMBINE: u32 = 2u;
+let SEED_CLEAR: u32 = 3u;
+
+// Capsules of cells painted with the mouse, only bound for `paint`.
+struct Stroke {
+    start: vec2<f32>,
+    end: vec2<f32>,
+    radius: f32,
+    state: u32,
+};
+struct Paint {
+    min: vec2<u32>,
+    count: u32,
+    strokes: array<Stroke>,
+};
+@group(1) @binding(2)
+var<storage, read> paint_strokes: Paint;
+
+// The displayed texture and the colour of each state, only bound for `colour`.
+@group(1) @binding(3)
+var display: texture_storage_2d<rgba8unorm, write>;
+struct Palette {
+    colours: array<vec4<f32>, 256>,
+};
+@group(1) @binding(4)
+var<uniform> palette: Palette;
 
 fn hash(value: u32) -> u32 {
     var state = value;
@@ -16,51 +93,174 @@ fn randomFloat(value: u32) -> f32 {
     return f32(hash(value)) / 4294967295.0;
 }
 
+fn load_state(location: vec2<i32>) -> u32 {
+    return u32(round(textureLoad(input, location, 0).x * 255.0));
+}
+
+fn store_state(location: vec2<i32>, state: u32) {
+    textureStore(output, location, vec4<f32>(f32(state) / 255.0, 0.0, 0.0, 0.0));
+}
+
+fn pattern_alive(location: vec2<i32>) -> bool {
+    let texel = location - seed.offset;
+    if (any(texel < vec2<i32>(0)) || any(texel >= textureDimensions(pattern))) {
+        return false;
+    }
+    return textureLoad(pattern, texel, 0).x > 0.5;
+}
+
 @compute @workgroup_size(8, 8, 1)
 fn init(@builtin(global_invocation_id) invocation_id: vec3<u32>, @builtin(num_workgroups) num_workgroups: vec3<u32>) {
     let location = vec2<i32>(i32(invocation_id.x), i32(invocation_id.y));
+    if (any(location >= textureDimensions(output))) {
+        return;
+    }
+
+    let randomNumber = randomFloat(invocation_id.y * num_workgroups.x + invocation_id.x + seed.seed * 2654435769u);
+    var alive = randomNumber > 0.9;
+    if (seed.mode == SEED_REPLACE) {
+        alive = pattern_alive(location);
+    } else if (seed.mode == SEED_COMBINE) {
+        alive = alive || pattern_alive(location);
+    } else if (seed.mode == SEED_CLEAR) {
+        alive = false;
+    }
+
+    store_state(location, u32(alive));
+}
+
+// Sets the cells under this frame's strokes, dispatched over the texels they cover.
+@compute @workgroup_size(8, 8, 1)
+fn paint(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
+    let location = vec2<i32>(paint_strokes.min + invocation_id.xy);
+    if (any(location >= textureDimensions(output))) {
+        return;
+    }
+
+    let center = vec2<f32>(location) + 0.5;
+    var painted = false;
+    var state = 0u;
+    for (var i = 0u; i < paint_strokes.count; i = i + 1u) {
+        let stroke = paint_strokes.strokes[i];
+        let segment = stroke.end - stroke.start;
+        let t = clamp(dot(center - stroke.start, segment) / max(dot(segment, segment), 0.0001), 0.0, 1.0);
+        if (distance(center, stroke.start + segment * t) <= stroke.radius) {
+            painted = true;
+            state = stroke.state;
+        }
+    }
+    if (painted) {
+        store_state(location, state);
+    }
+}
+
+// The cell standing in for `coordinate` along an axis of `size` cells, or -1 if it is dead.
+fn boundary_coordinate(coordinate: i32, size: i32) -> i32 {
+    if (coordinate >= 0 && coordinate < size) {
+        return coordinate;
+    }
+    if (grid.boundary == BOUNDARY_TOROIDAL) {
+        return (coordinate % size + size) % size;
+    }
+    if (grid.boundary == BOUNDARY_MIRRORED) {
+        if (coordinate < 0) {
+            return min(-coordinate - 1, size - 1);
+        }
+        return max(2 * size - coordinate - 1, 0);
+    }
+    return -1;
+}
+
+// 1 if the neighbour at `offset` is in state 1. Dead cells past the edge count as not alive.
+fn is_alive(location: vec2<i32>, offset: vec2<i32>) -> u32 {
+    let size = textureDimensions(input);
+    let neighbour = vec2<i32>(
+        boundary_coordinate(location.x + offset.x, size.x),
+        boundary_coordinate(location.y + offset.y, size.y)
+    );
+    if (any(neighbour < vec2<i32>(0))) {
+        return 0u;
+    }
+    return u32(load_state(neighbour) == 1u);
+}
 
-    let randomNumber = randomFloat(invocation_id.y * num_workgroups.x + invocation_id.x);
-    let alive = randomNumber > 0.9;
-    let color = vec4<f32>(f32(alive));
+fn in_neighbourhood(offset: vec2<i32>) -> bool {
+    if (all(offset == vec2<i32>(0))) {
+        return automaton.include_center == 1u;
+    }
+    if (automaton.neighbourhood == NEIGHBOURHOOD_VON_NEUMANN) {
+        return abs(offset.x) + abs(offset.y) <= automaton.radius;
+    }
+    if (automaton.neighbourhood == NEIGHBOURHOOD_HEX) {
+        // The north-west and south-east neighbours are the ones left out of the Moore neighbourhood.
+        return abs(offset.x) + abs(offset.y) + abs(offset.x + offset.y) <= 2 * automaton.radius;
+    }
+    return true;
+}
 
-    textureStore(texture, location, color);
+fn count_alive(location: vec2<i32>) -> u32 {
+    var count = 0u;
+    for (var y = -automaton.radius; y <= automaton.radius; y = y + 1) {
+        for (var x = -automaton.radius; x <= automaton.radius; x = x + 1) {
+            let offset = vec2<i32>(x, y);
+            if (in_neighbourhood(offset)) {
+                count = count + is_alive(location, offset);
+            }
+        }
+    }
+    return count;
 }
 
-fn is_alive(location: vec2<i32>, offset_x: i32, offset_y: i32) -> i32 {
-    let value: vec4<f32> = textureLoad(texture, location + vec2<i32>(offset_x, offset_y));
-    return i32(value.x);
+fn counts_contain(birth: bool, count: u32) -> bool {
+    var words: vec4<u32>;
+    if (birth) {
+        words = automaton.birth[count / 128u];
+    } else {
+        words = automaton.survival[count / 128u];
+    }
+    return ((words[(count / 32u) % 4u] >> (count % 32u)) & 1u) == 1u;
 }
 
-fn count_alive(location: vec2<i32>) -> i32 {
-    return is_alive(location, -1, -1) +
-           is_alive(location, -1,  0) +
-           is_alive(location, -1,  1) +
-           is_alive(location,  0, -1) +
-           is_alive(location,  0,  1) +
-           is_alive(location,  1, -1) +
-           is_alive(location,  1,  0) +
-           is_alive(location,  1,  1);
+fn next_state(state: u32, count: u32) -> u32 {
+    if (automaton.transition == TRANSITION_WIREWORLD) {
+        if (state == 1u) {
+            return 2u;
+        }
+        if (state == 2u) {
+            return 3u;
+        }
+        if (state == 3u && (count == 1u || count == 2u)) {
+            return 1u;
+        }
+        return state;
+    }
+
+    if (state == 0u) {
+        return u32(counts_contain(true, count));
+    }
+    if (state == 1u && counts_contain(false, count)) {
+        return 1u;
+    }
+    return (state + 1u) % automaton.states;
 }
 
 @compute @workgroup_size(8, 8, 1)
 fn update(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
     let location = vec2<i32>(i32(invocation_id.x), i32(invocation_id.y));
+    if (any(location >= textureDimensions(output))) {
+        return;
+    }
 
-    let n_alive = count_alive(location);
+    store_state(location, next_state(load_state(location), count_alive(location)));
+}
 
-    var alive: bool;
-    if (n_alive == 3) {
-        alive = true;
-    } else if (n_alive == 2) {
-        let currently_alive = is_alive(location, 0, 0);
-        alive = bool(currently_alive);
-    } else {
-        alive = false;
+// Maps the newest generation's states through the palette.
+@compute @workgroup_size(8, 8, 1)
+fn colour(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
+    let location = vec2<i32>(i32(invocation_id.x), i32(invocation_id.y));
+    if (any(location >= textureDimensions(display))) {
+        return;
     }
-    let color = vec4<f32>(f32(alive));
-
-    storageBarrier();
 
-    textureStore(texture, location, color);
-}
\ No newline at end of file
+    textureStore(display, location, palette.colours[min(load_state(location), 255u)]);
+}
@@ -3,21 +3,70 @@
 
 #import bevy_pbr::mesh_functions
 
+struct VoxelTerrainUniforms {
+    palette: array<vec4<f32>, 16>,
+    fog_color: vec4<f32>,
+    fog_start: f32,
+    fog_end: f32,
+    debug_flags: u32,
+    packed_position_scale: f32,
+};
+@group(2) @binding(0)
+var<uniform> terrain: VoxelTerrainUniforms;
+
+let DEBUG_NORMALS: u32 = 1u;
+let DEBUG_MATERIAL_IDS: u32 = 2u;
+let DEBUG_DISABLE_FOG: u32 = 4u;
+
 struct Vertex {
+#ifdef PACKED_POSITION
+    // Unorm16x4, chunk-local in 0..1 with an unused w.
+    @location(0) position: vec4<f32>,
+#else
     @location(0) position: vec3<f32>,
+#endif
+    // Octahedral normal in the low 24 bits, material id in the high 8.
     @location(1) voxel_data: u32,
 };
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_position: vec4<f32>,
+    @location(1) world_normal: vec3<f32>,
+    @location(2) @interpolate(flat) material: u32,
 };
 
+fn decode_octahedral(encoded: vec2<f32>) -> vec3<f32> {
+    var normal = vec3<f32>(encoded, 1.0 - abs(encoded.x) - abs(encoded.y));
+    let fold = max(-normal.z, 0.0);
+    normal.x = normal.x + select(fold, -fold, normal.x >= 0.0);
+    normal.y = normal.y + select(fold, -fold, normal.y >= 0.0);
+    return normalize(normal);
+}
+
+fn unpack_normal(voxel_data: u32) -> vec3<f32> {
+    let encoded = vec2<f32>(
+        f32(voxel_data & 0xfffu),
+        f32((voxel_data >> 12u) & 0xfffu),
+    ) / 4095.0 * 2.0 - 1.0;
+    return decode_octahedral(encoded);
+}
+
 @vertex
 fn vertex(vertex: Vertex) -> VertexOutput {
-    let world_position = mesh_position_local_to_world(mesh.model, vec4<f32>(vertex.position, 1.0));
+#ifdef PACKED_POSITION
+    let position = vertex.position.xyz * terrain.packed_position_scale;
+#else
+    let position = vertex.position;
+#endif
+    let world_position = mesh_position_local_to_world(mesh.model, vec4<f32>(position, 1.0));
+    let normal = unpack_normal(vertex.voxel_data);
 
     var out: VertexOutput;
     out.clip_position = mesh_position_world_to_clip(world_position);
+    out.world_position = world_position;
+    out.world_normal = mesh_normal_local_to_world(normal);
+    out.material = vertex.voxel_data >> 24u;
 
     return out;
 }
@@ -25,9 +74,43 @@ fn vertex(vertex: Vertex) -> VertexOutput {
 struct Fragment {
     @builtin(position) frag_coord: vec4<f32>,
     @builtin(front_facing) front_facing: bool,
+    @location(0) world_position: vec4<f32>,
+    @location(1) world_normal: vec3<f32>,
+    @location(2) @interpolate(flat) material: u32,
 };
 
 @fragment
 fn fragment(frag: Fragment) -> @location(0) vec4<f32> {
-    return vec4(1.0, 0.0, 0.0, 1.0);
-}
\ No newline at end of file
+    let normal = normalize(frag.world_normal);
+
+    if ((terrain.debug_flags & DEBUG_NORMALS) != 0u) {
+        return vec4<f32>(normal * 0.5 + 0.5, 1.0);
+    }
+
+    let base_color = terrain.palette[min(frag.material, 15u)];
+
+    if ((terrain.debug_flags & DEBUG_MATERIAL_IDS) != 0u) {
+        return base_color;
+    }
+
+    var light = lights.ambient_color.rgb;
+    for (var i: u32 = 0u; i < lights.n_directional_lights; i = i + 1u) {
+        let directional_light = lights.directional_lights[i];
+        let n_dot_l = max(dot(normal, directional_light.direction_to_light), 0.0);
+        light = light + directional_light.color.rgb * n_dot_l;
+    }
+
+    var color = base_color.rgb * light;
+
+    if ((terrain.debug_flags & DEBUG_DISABLE_FOG) == 0u) {
+        let distance = length(view.world_position - frag.world_position.xyz);
+        let fog = clamp(
+            (distance - terrain.fog_start) / max(terrain.fog_end - terrain.fog_start, 0.0001),
+            0.0,
+            1.0
+        );
+        color = mix(color, terrain.fog_color.rgb, fog);
+    }
+
+    return vec4<f32>(color, base_color.a);
+}
@@ -3,10 +3,18 @@
 
 struct CustomMaterial {
     color: vec4<f32>,
+    blend_factor: f32,
+    blend_mode: u32,
+    animation_speed: f32,
 };
 @group(1) @binding(0)
 var<uniform> material: CustomMaterial;
 
+let BLEND_MODE_LERP: u32 = 0u;
+let BLEND_MODE_MULTIPLY: u32 = 1u;
+let BLEND_MODE_OVERLAY: u32 = 2u;
+let TAU: f32 = 6.28318530718;
+
 #import bevy_pbr::mesh_functions
 
 struct Vertex {
@@ -35,7 +43,27 @@ struct FragmentInput {
     @location(1) new_color: vec4<f32>,
 };
 
+fn overlay(base: vec4<f32>, blend: vec4<f32>) -> vec4<f32> {
+    let multiplied = 2.0 * base * blend;
+    let screened = 1.0 - 2.0 * (1.0 - base) * (1.0 - blend);
+    return select(screened, multiplied, base < vec4<f32>(0.5));
+}
+
 @fragment
 fn fragment(input: FragmentInput) -> @location(0) vec4<f32> {
-    return material.color * input.new_color;
-}
\ No newline at end of file
+    var blended: vec4<f32>;
+    if (material.blend_mode == BLEND_MODE_MULTIPLY) {
+        blended = input.blend_color * input.new_color;
+    } else if (material.blend_mode == BLEND_MODE_OVERLAY) {
+        blended = overlay(input.blend_color, input.new_color);
+    } else {
+        blended = input.new_color;
+    }
+
+    var factor = material.blend_factor;
+    if (material.animation_speed != 0.0) {
+        factor = factor * (0.5 - 0.5 * cos(TAU * material.animation_speed * globals.time));
+    }
+
+    return material.color * mix(input.blend_color, blended, factor);
+}
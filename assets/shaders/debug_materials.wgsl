@@ -0,0 +1,128 @@
+#import bevy_pbr::mesh_view_bindings
+#import bevy_pbr::mesh_bindings
+#import bevy_pbr::mesh_functions
+
+// Each debug material sets one of these defs in its `specialize`.
+#ifdef DEBUG_MEAN_CURVATURE
+struct MeanCurvatureMaterial {
+    scale: f32,
+};
+@group(1) @binding(0)
+var<uniform> material: MeanCurvatureMaterial;
+#endif
+
+#ifdef DEBUG_CHUNK_COLOR
+struct ChunkColorMaterial {
+    color: vec4<f32>,
+};
+@group(1) @binding(0)
+var<uniform> material: ChunkColorMaterial;
+#endif
+
+#ifdef DEBUG_TRIANGLE_QUALITY
+struct TriangleQualityMaterial {
+    worst: f32,
+};
+@group(1) @binding(0)
+var<uniform> material: TriangleQualityMaterial;
+#endif
+
+#ifdef DEBUG_WIREFRAME
+struct WireframeMaterial {
+    color: vec4<f32>,
+    wire_color: vec4<f32>,
+    width: f32,
+};
+@group(1) @binding(0)
+var<uniform> material: WireframeMaterial;
+#endif
+
+struct Vertex {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+#ifdef DEBUG_MEAN_CURVATURE
+    @location(2) curvature: f32,
+#endif
+#ifdef DEBUG_TRIANGLE_QUALITY
+    @location(2) quality: f32,
+#endif
+#ifdef DEBUG_WIREFRAME
+    @location(2) barycentric: vec3<f32>,
+#endif
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_normal: vec3<f32>,
+    @location(1) value: vec3<f32>,
+};
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = mesh_position_local_to_clip(mesh.model, vec4<f32>(vertex.position, 1.0));
+    out.world_normal = mesh_normal_local_to_world(vertex.normal);
+    out.value = vec3<f32>(0.0);
+#ifdef DEBUG_MEAN_CURVATURE
+    out.value.x = vertex.curvature;
+#endif
+#ifdef DEBUG_TRIANGLE_QUALITY
+    out.value.x = vertex.quality;
+#endif
+#ifdef DEBUG_WIREFRAME
+    out.value = vertex.barycentric;
+#endif
+    return out;
+}
+
+struct FragmentInput {
+    @location(0) world_normal: vec3<f32>,
+    @location(1) value: vec3<f32>,
+};
+
+// Ambient plus lambert from the directional lights, enough to read the shape.
+fn diffuse(normal: vec3<f32>) -> vec3<f32> {
+    var light = vec3<f32>(0.2);
+    for (var i: u32 = 0u; i < lights.n_directional_lights; i = i + 1u) {
+        let directional_light = lights.directional_lights[i];
+        let n_dot_l = max(dot(normal, directional_light.direction_to_light), 0.0);
+        light = light + directional_light.color.rgb * n_dot_l;
+    }
+    return light;
+}
+
+@fragment
+fn fragment(in: FragmentInput) -> @location(0) vec4<f32> {
+    let normal = normalize(in.world_normal);
+    var color = vec4<f32>(normal * 0.5 + 0.5, 1.0);
+
+#ifdef DEBUG_MEAN_CURVATURE
+    // Blue where concave, white where flat, red where convex.
+    let t = clamp(in.value.x * material.scale, -1.0, 1.0);
+    let white = vec3<f32>(1.0);
+    color = vec4<f32>(
+        select(mix(white, vec3<f32>(1.0, 0.1, 0.1), t), mix(white, vec3<f32>(0.1, 0.2, 1.0), -t), t < 0.0),
+        1.0
+    );
+#endif
+
+#ifdef DEBUG_CHUNK_COLOR
+    color = vec4<f32>(material.color.rgb * diffuse(normal), 1.0);
+#endif
+
+#ifdef DEBUG_TRIANGLE_QUALITY
+    // Green for equilateral triangles, red at or below the worst accepted quality.
+    let t = clamp((in.value.x - material.worst) / max(1.0 - material.worst, 0.0001), 0.0, 1.0);
+    color = vec4<f32>(mix(vec3<f32>(1.0, 0.0, 0.0), vec3<f32>(0.0, 1.0, 0.0), t), 1.0);
+#endif
+
+#ifdef DEBUG_WIREFRAME
+    // Distance to the nearest edge in pixels, from the barycentric coordinates' screen gradient.
+    let edge_distance = in.value / max(fwidth(in.value), vec3<f32>(0.0001));
+    let edge = 1.0 - smoothstep(0.0, material.width, min(edge_distance.x, min(edge_distance.y, edge_distance.z)));
+    let surface = material.color.rgb * diffuse(normal);
+    color = vec4<f32>(mix(surface, material.wire_color.rgb, edge * material.wire_color.a), 1.0);
+#endif
+
+    return color;
+}
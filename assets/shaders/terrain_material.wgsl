@@ -0,0 +1,234 @@
+#import bevy_pbr::mesh_view_bindings
+#import bevy_pbr::mesh_bindings
+#import bevy_pbr::mesh_functions
+
+#import bevy_pbr::pbr_types
+#import bevy_pbr::utils
+#import bevy_pbr::clustered_forward
+#import bevy_pbr::lighting
+#import bevy_pbr::shadows
+#import bevy_pbr::pbr_functions
+
+struct TerrainLayer {
+    tint: vec4<f32>,
+    height_range: vec2<f32>,
+    slope_range: vec2<f32>,
+    texture_scale: f32,
+};
+
+struct TerrainMaterial {
+    layers: array<TerrainLayer, 4>,
+    height_blend: f32,
+    slope_blend: f32,
+    perceptual_roughness: f32,
+    flags: u32,
+};
+
+@group(1) @binding(0)
+var<uniform> terrain_material: TerrainMaterial;
+@group(1) @binding(1)
+var albedo_0: texture_2d<f32>;
+@group(1) @binding(2)
+var albedo_1: texture_2d<f32>;
+@group(1) @binding(3)
+var albedo_2: texture_2d<f32>;
+@group(1) @binding(4)
+var albedo_3: texture_2d<f32>;
+@group(1) @binding(5)
+var normal_0: texture_2d<f32>;
+@group(1) @binding(6)
+var normal_1: texture_2d<f32>;
+@group(1) @binding(7)
+var normal_2: texture_2d<f32>;
+@group(1) @binding(8)
+var normal_3: texture_2d<f32>;
+@group(1) @binding(9)
+var terrain_sampler: sampler;
+
+// Bit `i` is set when layer `i` has an albedo texture, bit `i + 4` when it has a normal map.
+let ALBEDO_TEXTURE_BIT: u32 = 1u;
+let NORMAL_MAP_BIT: u32 = 16u;
+
+struct Vertex {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+#ifdef TERRAIN_MATERIAL_WEIGHTS
+    @location(2) weights: vec4<f32>,
+#endif
+#ifdef TERRAIN_AMBIENT_OCCLUSION
+    @location(3) ambient_occlusion: f32,
+#endif
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_position: vec4<f32>,
+    @location(1) world_normal: vec3<f32>,
+    @location(2) weights: vec4<f32>,
+    @location(3) ambient_occlusion: f32,
+};
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    let world_position = mesh_position_local_to_world(mesh.model, vec4<f32>(vertex.position, 1.0));
+
+    var out: VertexOutput;
+    out.clip_position = mesh_position_world_to_clip(world_position);
+    out.world_position = world_position;
+    out.world_normal = mesh_normal_local_to_world(vertex.normal);
+#ifdef TERRAIN_MATERIAL_WEIGHTS
+    out.weights = vertex.weights;
+#else
+    out.weights = vec4<f32>(1.0);
+#endif
+#ifdef TERRAIN_AMBIENT_OCCLUSION
+    out.ambient_occlusion = vertex.ambient_occlusion;
+#else
+    out.ambient_occlusion = 1.0;
+#endif
+
+    return out;
+}
+
+struct Fragment {
+    @builtin(front_facing) is_front: bool,
+    @builtin(position) frag_coord: vec4<f32>,
+    @location(0) world_position: vec4<f32>,
+    @location(1) world_normal: vec3<f32>,
+    @location(2) weights: vec4<f32>,
+    @location(3) ambient_occlusion: f32,
+};
+
+// Weights of the three planar projections, sharpened so the transition between them stays narrow.
+fn triplanar_weights(normal: vec3<f32>) -> vec3<f32> {
+    var weights = pow(abs(normal), vec3<f32>(4.0));
+    return weights / (weights.x + weights.y + weights.z);
+}
+
+fn sample_triplanar(
+    texture: texture_2d<f32>,
+    position: vec3<f32>,
+    weights: vec3<f32>,
+    scale: f32,
+) -> vec4<f32> {
+    let p = position / scale;
+    return textureSample(texture, terrain_sampler, p.zy) * weights.x
+        + textureSample(texture, terrain_sampler, p.xz) * weights.y
+        + textureSample(texture, terrain_sampler, p.xy) * weights.z;
+}
+
+// Triplanar normal mapping with a "UDN" blend: each projection's tangent space normal is added
+// onto the world normal's matching components, then the projections are blended.
+fn sample_triplanar_normal(
+    texture: texture_2d<f32>,
+    position: vec3<f32>,
+    normal: vec3<f32>,
+    weights: vec3<f32>,
+    scale: f32,
+) -> vec3<f32> {
+    let p = position / scale;
+    let tangent_x = textureSample(texture, terrain_sampler, p.zy).xy * 2.0 - 1.0;
+    let tangent_y = textureSample(texture, terrain_sampler, p.xz).xy * 2.0 - 1.0;
+    let tangent_z = textureSample(texture, terrain_sampler, p.xy).xy * 2.0 - 1.0;
+
+    let normal_x = vec3<f32>(normal.x, tangent_x.y + normal.y, tangent_x.x + normal.z);
+    let normal_y = vec3<f32>(tangent_y.x + normal.x, normal.y, tangent_y.y + normal.z);
+    let normal_z = vec3<f32>(tangent_z.x + normal.x, tangent_z.y + normal.y, normal.z);
+
+    return normalize(normal_x * weights.x + normal_y * weights.y + normal_z * weights.z);
+}
+
+// 1 inside `range`, fading to 0 over `blend` on either side.
+fn band(value: f32, range: vec2<f32>, blend: f32) -> f32 {
+    let low = smoothstep(range.x - blend, range.x + blend, value);
+    let high = 1.0 - smoothstep(range.y - blend, range.y + blend, value);
+    return low * high;
+}
+
+fn layer_weight(index: u32, height: f32, slope: f32) -> f32 {
+    let layer = terrain_material.layers[index];
+    return band(height, layer.height_range, terrain_material.height_blend)
+        * band(slope, layer.slope_range, terrain_material.slope_blend);
+}
+
+fn layer_albedo(index: u32, albedo: vec4<f32>) -> vec4<f32> {
+    let tint = terrain_material.layers[index].tint;
+    if ((terrain_material.flags & (ALBEDO_TEXTURE_BIT << index)) != 0u) {
+        return tint * albedo;
+    }
+    return tint;
+}
+
+fn layer_normal(index: u32, mapped: vec3<f32>, normal: vec3<f32>) -> vec3<f32> {
+    if ((terrain_material.flags & (NORMAL_MAP_BIT << index)) != 0u) {
+        return mapped;
+    }
+    return normal;
+}
+
+@fragment
+fn fragment(in: Fragment) -> @location(0) vec4<f32> {
+    let world_normal = prepare_world_normal(in.world_normal, false, in.is_front);
+    let position = in.world_position.xyz;
+    let height = position.y;
+    let slope = acos(clamp(world_normal.y, -1.0, 1.0));
+
+    var weights = vec4<f32>(
+        layer_weight(0u, height, slope),
+        layer_weight(1u, height, slope),
+        layer_weight(2u, height, slope),
+        layer_weight(3u, height, slope),
+    ) * in.weights;
+    var total = weights.x + weights.y + weights.z + weights.w;
+    // Painted materials win over height and slope when none of their bands cover this point.
+    if (total <= 0.0001) {
+        weights = in.weights;
+        total = weights.x + weights.y + weights.z + weights.w;
+    }
+    if (total <= 0.0001) {
+        weights = vec4<f32>(1.0, 0.0, 0.0, 0.0);
+    } else {
+        weights = weights / total;
+    }
+
+    // Every texture is sampled unconditionally; textureSample must stay in uniform control flow.
+    let projection = triplanar_weights(world_normal);
+    let layers = terrain_material.layers;
+    var albedo = array<vec4<f32>, 4>(
+        sample_triplanar(albedo_0, position, projection, layers[0].texture_scale),
+        sample_triplanar(albedo_1, position, projection, layers[1].texture_scale),
+        sample_triplanar(albedo_2, position, projection, layers[2].texture_scale),
+        sample_triplanar(albedo_3, position, projection, layers[3].texture_scale),
+    );
+    var mapped = array<vec3<f32>, 4>(
+        sample_triplanar_normal(normal_0, position, world_normal, projection, layers[0].texture_scale),
+        sample_triplanar_normal(normal_1, position, world_normal, projection, layers[1].texture_scale),
+        sample_triplanar_normal(normal_2, position, world_normal, projection, layers[2].texture_scale),
+        sample_triplanar_normal(normal_3, position, world_normal, projection, layers[3].texture_scale),
+    );
+
+    var base_color = vec4<f32>(0.0);
+    var normal = vec3<f32>(0.0);
+    for (var i: u32 = 0u; i < 4u; i = i + 1u) {
+        base_color = base_color + layer_albedo(i, albedo[i]) * weights[i];
+        normal = normal + layer_normal(i, mapped[i], world_normal) * weights[i];
+    }
+
+    var pbr_input = pbr_input_new();
+    pbr_input.material.base_color = base_color;
+    pbr_input.material.perceptual_roughness = terrain_material.perceptual_roughness;
+    pbr_input.frag_coord = in.frag_coord;
+    pbr_input.world_position = in.world_position;
+    pbr_input.world_normal = world_normal;
+    pbr_input.is_orthographic = view.projection[3].w == 1.0;
+    pbr_input.N = normalize(normal);
+    pbr_input.V = calculate_view(in.world_position, pbr_input.is_orthographic);
+    // Baked occlusion only darkens ambient light; direct light is left to the shadow maps.
+    pbr_input.occlusion = in.ambient_occlusion;
+
+    var output_color = pbr(pbr_input);
+#ifdef TONEMAP_IN_SHADER
+    output_color = tone_mapping(output_color);
+#endif
+    return output_color;
+}
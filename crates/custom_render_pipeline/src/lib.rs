@@ -2,23 +2,29 @@ use bevy::{
     core_pipeline::core_3d::AlphaMask3d,
     prelude::Plugin,
     render::{
-        render_phase::AddRenderCommand, render_resource::SpecializedMeshPipelines, RenderApp,
+        extract_component::ExtractComponentPlugin, render_phase::AddRenderCommand,
+        render_resource::SpecializedMeshPipelines, RenderApp, RenderStage,
     },
 };
-use render_pipeline::{CustomRenderPipeline, DrawCustom};
+use render_pipeline::{queue_voxel_meshes, CustomRenderPipeline, DrawCustom};
+use voxel_mesh::VoxelTerrainMesh;
 
 mod render_pipeline;
+pub mod terrain_uniforms;
+pub mod voxel_mesh;
 
 pub struct CustomRenderPipelinePlugin;
 
 impl Plugin for CustomRenderPipelinePlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
-        // app.add_plugin(ExtractComponentPlugin::<VoxelTerrainMesh>::default())
-        // .add_plugin(terrain_uniforms::VoxelTerrainUniformsPlugin);
+        // The uniforms plugin creates the bind group layout the pipeline is built with, so it has
+        // to be added before `CustomRenderPipeline` is initialized.
+        app.add_plugin(ExtractComponentPlugin::<VoxelTerrainMesh>::default())
+            .add_plugin(terrain_uniforms::VoxelTerrainUniformsPlugin);
         app.sub_app_mut(RenderApp)
             .add_render_command::<AlphaMask3d, DrawCustom>()
             .init_resource::<CustomRenderPipeline>()
-            .init_resource::<SpecializedMeshPipelines<CustomRenderPipeline>>();
-        // .add_system_to_stage(RenderStage::Queue, queue_voxel_meshes);
+            .init_resource::<SpecializedMeshPipelines<CustomRenderPipeline>>()
+            .add_system_to_stage(RenderStage::Queue, queue_voxel_meshes);
     }
 }
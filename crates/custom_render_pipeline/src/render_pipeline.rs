@@ -1,12 +1,34 @@
 use bevy::{
-    pbr::{DrawMesh, MeshPipeline, MeshPipelineKey, SetMeshBindGroup, SetMeshViewBindGroup},
-    prelude::{AssetServer, FromWorld, Handle, Resource, Shader},
-    render::{render_phase::SetItemPipeline, render_resource::SpecializedMeshPipeline},
+    core_pipeline::core_3d::AlphaMask3d,
+    log::error,
+    pbr::{
+        DrawMesh, MeshPipeline, MeshPipelineKey, MeshUniform, SetMeshBindGroup,
+        SetMeshViewBindGroup,
+    },
+    prelude::{
+        AssetServer, FromWorld, Handle, Mesh, Msaa, Query, Res, ResMut, Resource, Shader, With,
+    },
+    render::{
+        mesh::MeshVertexBufferLayout,
+        render_asset::RenderAssets,
+        render_phase::{DrawFunctions, RenderPhase, SetItemPipeline},
+        render_resource::{
+            BindGroupLayout, PipelineCache, RenderPipelineDescriptor, SpecializedMeshPipeline,
+            SpecializedMeshPipelineError, SpecializedMeshPipelines,
+        },
+        view::{ExtractedView, VisibleEntities},
+    },
+};
+
+use crate::{
+    terrain_uniforms::{SetVoxelTerrainUniformsBindGroup, VoxelTerrainUniformsLayout},
+    voxel_mesh::{VoxelTerrainMesh, ATTRIBUTE_PACKED_POSITION, ATTRIBUTE_VOXEL_DATA},
 };
 
 #[derive(Resource)]
 pub struct CustomRenderPipeline {
     mesh_pipeline: MeshPipeline,
+    terrain_uniforms_layout: BindGroupLayout,
     shader: Handle<Shader>,
 }
 
@@ -14,6 +36,11 @@ impl FromWorld for CustomRenderPipeline {
     fn from_world(world: &mut bevy::prelude::World) -> Self {
         Self {
             mesh_pipeline: world.get_resource::<MeshPipeline>().unwrap().clone(),
+            terrain_uniforms_layout: world
+                .get_resource::<VoxelTerrainUniformsLayout>()
+                .unwrap()
+                .0
+                .clone(),
             shader: world
                 .get_resource::<AssetServer>()
                 .unwrap()
@@ -28,12 +55,34 @@ impl SpecializedMeshPipeline for CustomRenderPipeline {
     fn specialize(
         &self,
         key: Self::Key,
-        layout: &bevy::render::mesh::MeshVertexBufferLayout,
-    ) -> Result<
-        bevy::render::render_resource::RenderPipelineDescriptor,
-        bevy::render::render_resource::SpecializedMeshPipelineError,
-    > {
-        todo!()
+        layout: &MeshVertexBufferLayout,
+    ) -> Result<RenderPipelineDescriptor, SpecializedMeshPipelineError> {
+        // Start from the regular mesh pipeline so the view and mesh bind groups, depth state and
+        // output format match what the core 3d passes expect.
+        let mut descriptor = self.mesh_pipeline.specialize(key, layout)?;
+
+        descriptor.label = Some("voxel_terrain_pipeline".into());
+        descriptor.vertex.shader = self.shader.clone();
+
+        let fragment = descriptor.fragment.as_mut().unwrap();
+        fragment.shader = self.shader.clone();
+
+        let position = if layout.contains(ATTRIBUTE_PACKED_POSITION) {
+            descriptor.vertex.shader_defs.push("PACKED_POSITION".into());
+            fragment.shader_defs.push("PACKED_POSITION".into());
+            ATTRIBUTE_PACKED_POSITION.at_shader_location(0)
+        } else {
+            Mesh::ATTRIBUTE_POSITION.at_shader_location(0)
+        };
+        descriptor.vertex.buffers =
+            vec![layout.get_layout(&[position, ATTRIBUTE_VOXEL_DATA.at_shader_location(1)])?];
+        descriptor
+            .layout
+            .as_mut()
+            .unwrap()
+            .push(self.terrain_uniforms_layout.clone());
+
+        Ok(descriptor)
     }
 }
 
@@ -41,5 +90,63 @@ pub type DrawCustom = (
     SetItemPipeline,
     SetMeshViewBindGroup<0>,
     SetMeshBindGroup<1>,
+    SetVoxelTerrainUniformsBindGroup<2>,
     DrawMesh,
 );
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn queue_voxel_meshes(
+    alpha_mask_draw_functions: Res<DrawFunctions<AlphaMask3d>>,
+    custom_pipeline: Res<CustomRenderPipeline>,
+    mut pipelines: ResMut<SpecializedMeshPipelines<CustomRenderPipeline>>,
+    mut pipeline_cache: ResMut<PipelineCache>,
+    msaa: Res<Msaa>,
+    render_meshes: Res<RenderAssets<Mesh>>,
+    voxel_meshes: Query<(&Handle<Mesh>, &MeshUniform), With<VoxelTerrainMesh>>,
+    mut views: Query<(
+        &ExtractedView,
+        &VisibleEntities,
+        &mut RenderPhase<AlphaMask3d>,
+    )>,
+) {
+    let draw_custom = alpha_mask_draw_functions
+        .read()
+        .get_id::<DrawCustom>()
+        .unwrap();
+
+    for (view, visible_entities, mut alpha_mask_phase) in &mut views {
+        let view_key =
+            MeshPipelineKey::from_msaa_samples(msaa.samples) | MeshPipelineKey::from_hdr(view.hdr);
+        let rangefinder = view.rangefinder3d();
+
+        for visible_entity in &visible_entities.entities {
+            let Ok((mesh_handle, mesh_uniform)) = voxel_meshes.get(*visible_entity) else {
+                continue;
+            };
+            let Some(mesh) = render_meshes.get(mesh_handle) else {
+                continue;
+            };
+
+            let key = view_key | MeshPipelineKey::from_primitive_topology(mesh.primitive_topology);
+            let pipeline = match pipelines.specialize(
+                &mut pipeline_cache,
+                &custom_pipeline,
+                key,
+                &mesh.layout,
+            ) {
+                Ok(pipeline) => pipeline,
+                Err(err) => {
+                    error!("{}", err);
+                    continue;
+                }
+            };
+
+            alpha_mask_phase.add(AlphaMask3d {
+                entity: *visible_entity,
+                pipeline,
+                draw_function: draw_custom,
+                distance: rangefinder.distance(&mesh_uniform.transform),
+            });
+        }
+    }
+}
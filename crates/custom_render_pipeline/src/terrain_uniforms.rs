@@ -0,0 +1,178 @@
+//! Terrain-wide shading parameters, bound at group 2 of the custom terrain pipeline.
+
+use bevy::{
+    ecs::system::{lifetimeless::SRes, SystemParamItem},
+    prelude::*,
+    render::{
+        extract_resource::{ExtractResource, ExtractResourcePlugin},
+        render_phase::{EntityRenderCommand, RenderCommandResult, TrackedRenderPass},
+        render_resource::*,
+        renderer::{RenderDevice, RenderQueue},
+        RenderApp, RenderStage,
+    },
+};
+
+/// Number of palette entries available to voxel material ids. Larger ids use the last entry.
+pub const MAX_TERRAIN_MATERIALS: usize = 16;
+
+pub struct VoxelTerrainUniformsPlugin;
+
+impl Plugin for VoxelTerrainUniformsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<VoxelTerrainUniforms>()
+            .add_plugin(ExtractResourcePlugin::<VoxelTerrainUniforms>::default());
+        app.sub_app_mut(RenderApp)
+            .init_resource::<VoxelTerrainUniformsLayout>()
+            .init_resource::<VoxelTerrainUniformBuffer>()
+            .add_system_to_stage(RenderStage::Prepare, prepare_terrain_uniforms)
+            .add_system_to_stage(RenderStage::Queue, queue_terrain_uniforms_bind_group);
+    }
+}
+
+/// Palette, fog and debug settings shared by every voxel terrain mesh.
+#[derive(Resource, Clone, ExtractResource)]
+pub struct VoxelTerrainUniforms {
+    /// Base colour for each voxel material id.
+    pub palette: [Color; MAX_TERRAIN_MATERIALS],
+    pub fog_color: Color,
+    /// Distance from the camera where fog starts.
+    pub fog_start: f32,
+    /// Distance from the camera where fog is fully opaque.
+    pub fog_end: f32,
+    /// A combination of the `DEBUG_*` flags.
+    pub debug_flags: u32,
+    /// World units covered by the `0..1` range of packed positions, i.e. the `extent` the meshes
+    /// were packed with.
+    pub packed_position_scale: f32,
+}
+
+impl VoxelTerrainUniforms {
+    /// Shade with world-space normals instead of lighting.
+    pub const DEBUG_NORMALS: u32 = 1 << 0;
+    /// Shade with the unlit palette colour, making material ids easy to tell apart.
+    pub const DEBUG_MATERIAL_IDS: u32 = 1 << 1;
+    pub const DEBUG_DISABLE_FOG: u32 = 1 << 2;
+}
+
+impl Default for VoxelTerrainUniforms {
+    fn default() -> Self {
+        let mut palette = [Color::GRAY; MAX_TERRAIN_MATERIALS];
+        palette[0] = Color::rgb(0.45, 0.43, 0.41); // rock
+        palette[1] = Color::rgb(0.36, 0.25, 0.16); // dirt
+        palette[2] = Color::rgb(0.76, 0.70, 0.50); // sand
+        palette[3] = Color::rgb(0.27, 0.45, 0.18); // grass
+
+        Self {
+            palette,
+            fog_color: Color::rgb(0.6, 0.7, 0.8),
+            fog_start: 64.0,
+            fog_end: 256.0,
+            debug_flags: 0,
+            packed_position_scale: 64.0,
+        }
+    }
+}
+
+#[derive(ShaderType, Clone, Default)]
+struct GpuVoxelTerrainUniforms {
+    palette: [Vec4; MAX_TERRAIN_MATERIALS],
+    fog_color: Vec4,
+    fog_start: f32,
+    fog_end: f32,
+    debug_flags: u32,
+    packed_position_scale: f32,
+}
+
+impl From<&VoxelTerrainUniforms> for GpuVoxelTerrainUniforms {
+    fn from(uniforms: &VoxelTerrainUniforms) -> Self {
+        Self {
+            palette: uniforms
+                .palette
+                .map(|color| Vec4::from(color.as_linear_rgba_f32())),
+            fog_color: Vec4::from(uniforms.fog_color.as_linear_rgba_f32()),
+            fog_start: uniforms.fog_start,
+            fog_end: uniforms.fog_end,
+            debug_flags: uniforms.debug_flags,
+            packed_position_scale: uniforms.packed_position_scale,
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct VoxelTerrainUniformsLayout(pub BindGroupLayout);
+
+impl FromWorld for VoxelTerrainUniformsLayout {
+    fn from_world(world: &mut World) -> Self {
+        let layout =
+            world
+                .resource::<RenderDevice>()
+                .create_bind_group_layout(&BindGroupLayoutDescriptor {
+                    label: Some("voxel_terrain_uniforms_layout"),
+                    entries: &[BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: ShaderStages::VERTEX | ShaderStages::FRAGMENT,
+                        ty: BindingType::Buffer {
+                            ty: BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: Some(GpuVoxelTerrainUniforms::min_size()),
+                        },
+                        count: None,
+                    }],
+                });
+
+        Self(layout)
+    }
+}
+
+#[derive(Resource, Default)]
+struct VoxelTerrainUniformBuffer(UniformBuffer<GpuVoxelTerrainUniforms>);
+
+#[derive(Resource)]
+pub struct VoxelTerrainUniformsBindGroup(BindGroup);
+
+fn prepare_terrain_uniforms(
+    uniforms: Res<VoxelTerrainUniforms>,
+    mut buffer: ResMut<VoxelTerrainUniformBuffer>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+) {
+    buffer.0.set(GpuVoxelTerrainUniforms::from(&*uniforms));
+    buffer.0.write_buffer(&render_device, &render_queue);
+}
+
+fn queue_terrain_uniforms_bind_group(
+    mut commands: Commands,
+    layout: Res<VoxelTerrainUniformsLayout>,
+    buffer: Res<VoxelTerrainUniformBuffer>,
+    render_device: Res<RenderDevice>,
+) {
+    let Some(binding) = buffer.0.binding() else {
+        return;
+    };
+
+    let bind_group = render_device.create_bind_group(&BindGroupDescriptor {
+        label: Some("voxel_terrain_uniforms_bind_group"),
+        layout: &layout.0,
+        entries: &[BindGroupEntry {
+            binding: 0,
+            resource: binding,
+        }],
+    });
+    commands.insert_resource(VoxelTerrainUniformsBindGroup(bind_group));
+}
+
+pub struct SetVoxelTerrainUniformsBindGroup<const I: usize>;
+
+impl<const I: usize> EntityRenderCommand for SetVoxelTerrainUniformsBindGroup<I> {
+    type Param = SRes<VoxelTerrainUniformsBindGroup>;
+
+    fn render<'w>(
+        _view: Entity,
+        _item: Entity,
+        bind_group: SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        pass.set_bind_group(I, &bind_group.into_inner().0, &[]);
+        RenderCommandResult::Success
+    }
+}
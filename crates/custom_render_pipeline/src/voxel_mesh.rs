@@ -0,0 +1,32 @@
+use bevy::{ecs::query::QueryItem, prelude::*, render::extract_component::ExtractComponent};
+
+pub use marching_cubes::{
+    mesh::{ATTRIBUTE_PACKED_POSITION, ATTRIBUTE_VOXEL_DATA},
+    packing::pack_normal_material,
+};
+
+/// Marks a mesh to be drawn by the custom terrain pipeline instead of a material.
+///
+/// The mesh needs [`ATTRIBUTE_VOXEL_DATA`] and either `Mesh::ATTRIBUTE_POSITION` or the more
+/// compact [`ATTRIBUTE_PACKED_POSITION`], as produced by `marching_cubes::mesh::build_packed_mesh`.
+#[derive(Component, Clone, Copy, Default)]
+pub struct VoxelTerrainMesh;
+
+impl ExtractComponent for VoxelTerrainMesh {
+    type Query = &'static VoxelTerrainMesh;
+    type Filter = ();
+
+    fn extract_component(item: QueryItem<'_, Self::Query>) -> Self {
+        *item
+    }
+}
+
+#[derive(Bundle, Clone, Default)]
+pub struct VoxelTerrainBundle {
+    pub mesh: Handle<Mesh>,
+    pub voxel_terrain_mesh: VoxelTerrainMesh,
+    pub transform: Transform,
+    pub global_transform: GlobalTransform,
+    pub visibility: Visibility,
+    pub computed_visibility: ComputedVisibility,
+}
@@ -0,0 +1,138 @@
+//! Baked per-vertex ambient occlusion, estimated by marching short rays into the density field.
+
+use std::f32::consts::PI;
+
+use bevy::{
+    prelude::{Mesh, Quat, Vec3},
+    render::mesh::VertexAttributeValues,
+};
+
+use crate::{density::DensitySource, mesh::ATTRIBUTE_AMBIENT_OCCLUSION};
+
+/// Settings for the ambient occlusion pass.
+#[derive(Debug, Clone, Copy)]
+pub struct AmbientOcclusion {
+    /// The iso level the mesh was extracted at; samples below it block rays.
+    pub iso_level: f32,
+    /// Rays cast over the hemisphere around each vertex normal.
+    pub rays: u32,
+    /// Samples taken along each ray.
+    pub steps: u32,
+    /// How far rays reach, in world units. Geometry further away does not occlude.
+    pub max_distance: f32,
+}
+
+impl AmbientOcclusion {
+    pub fn new(iso_level: f32, max_distance: f32) -> Self {
+        Self {
+            iso_level,
+            rays: 16,
+            steps: 4,
+            max_distance,
+        }
+    }
+
+    /// How open the surface at `position` is, from `0.0` when every ray is blocked to `1.0` when
+    /// none are. Rays are cosine weighted, so geometry straight above the surface counts most.
+    pub fn occlusion(&self, source: &impl DensitySource, position: Vec3, normal: Vec3) -> f32 {
+        let normal = normal.normalize();
+        let rotation = Quat::from_rotation_arc(Vec3::Z, normal);
+        let step = self.max_distance / self.steps.max(1) as f32;
+
+        let mut total = 0.0;
+        let mut open = 0.0;
+
+        for ray in 0..self.rays {
+            let direction = rotation * hemisphere_direction(ray, self.rays);
+            let weight = direction.dot(normal).max(0.0);
+            let blocked = (1..=self.steps.max(1))
+                .any(|i| source.density(position + direction * step * i as f32) < self.iso_level);
+
+            total += weight;
+            if !blocked {
+                open += weight;
+            }
+        }
+
+        if total > 0.0 {
+            open / total
+        } else {
+            1.0
+        }
+    }
+
+    /// Bakes [`ATTRIBUTE_AMBIENT_OCCLUSION`] into a mesh with `f32` positions and normals, such as
+    /// one from [`build_mesh`](crate::mesh::build_mesh). Positions are taken to be in the same
+    /// space as `source`. Meshes without those attributes are left untouched.
+    pub fn bake(&self, source: &impl DensitySource, mesh: &mut Mesh) {
+        let (
+            Some(VertexAttributeValues::Float32x3(positions)),
+            Some(VertexAttributeValues::Float32x3(normals)),
+        ) = (
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION),
+            mesh.attribute(Mesh::ATTRIBUTE_NORMAL),
+        )
+        else {
+            return;
+        };
+
+        let occlusion = positions
+            .iter()
+            .zip(normals)
+            .map(|(&position, &normal)| {
+                self.occlusion(source, Vec3::from(position), Vec3::from(normal))
+            })
+            .collect::<Vec<_>>();
+
+        mesh.insert_attribute(ATTRIBUTE_AMBIENT_OCCLUSION, occlusion);
+    }
+}
+
+/// The `index`th of `count` directions spread evenly over the hemisphere around +Z, on a
+/// Fibonacci spiral.
+fn hemisphere_direction(index: u32, count: u32) -> Vec3 {
+    let golden_angle = PI * (3.0 - 5.0_f32.sqrt());
+    let z = 1.0 - (index as f32 + 0.5) / count as f32;
+    let radius = (1.0 - z * z).sqrt();
+    let angle = golden_angle * index as f32;
+    Vec3::new(radius * angle.cos(), radius * angle.sin(), z)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AMBIENT_OCCLUSION: AmbientOcclusion = AmbientOcclusion {
+        iso_level: 0.0,
+        rays: 16,
+        steps: 4,
+        max_distance: 4.0,
+    };
+
+    /// Solid ground below `y = 0`.
+    fn ground(position: Vec3) -> f32 {
+        position.y
+    }
+
+    fn occlusion(source: impl Fn(Vec3) -> f32) -> f32 {
+        AMBIENT_OCCLUSION.occlusion(&source, Vec3::ZERO, Vec3::Y)
+    }
+
+    #[test]
+    fn open_planes_are_unoccluded() {
+        assert!((occlusion(ground) - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn overhangs_and_corners_are_occluded() {
+        let open = occlusion(ground);
+        // A ceiling two units up, within reach of the steeper rays.
+        let overhang = occlusion(|position: Vec3| ground(position).min(2.0 - position.y));
+        // A wall half a unit away along +x.
+        let corner = occlusion(|position: Vec3| ground(position).min(0.5 - position.x));
+
+        assert!(overhang < open, "{}", overhang);
+        assert!(corner < open, "{}", corner);
+        assert!(overhang > 0.0 && corner > 0.0, "{} {}", overhang, corner);
+    }
+}
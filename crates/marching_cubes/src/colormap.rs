@@ -0,0 +1,105 @@
+//! A material that colors a surface by one of its [`ATTRIBUTE_SCALAR_FIELDS`], for meshes built
+//! with [`build_field_mesh`](crate::mesh::build_field_mesh).
+//!
+//! Add `MaterialPlugin::<ColormapMaterial>` to the app to use it.
+
+use bevy::{
+    pbr::{MaterialPipeline, MaterialPipelineKey},
+    prelude::*,
+    reflect::TypeUuid,
+    render::{
+        mesh::MeshVertexBufferLayout,
+        render_asset::RenderAssets,
+        render_resource::{
+            AsBindGroup, AsBindGroupShaderType, RenderPipelineDescriptor, ShaderRef, ShaderType,
+            SpecializedMeshPipelineError,
+        },
+    },
+};
+
+use crate::mesh::ATTRIBUTE_SCALAR_FIELDS;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Colormap {
+    /// Perceptually uniform, dark blue to yellow.
+    #[default]
+    Viridis,
+    /// Perceptually uniform, black through purple to pale yellow.
+    Magma,
+    /// Blue through white to red, for fields with a meaningful midpoint.
+    Diverging,
+}
+
+#[derive(AsBindGroup, Debug, Clone, TypeUuid)]
+#[uuid = "db74fe89-18f7-4886-8c5c-31ce22359fc7"]
+#[uniform(0, ColormapMaterialUniform)]
+pub struct ColormapMaterial {
+    pub colormap: Colormap,
+    /// Which channel of [`ATTRIBUTE_SCALAR_FIELDS`] to display.
+    pub field: u32,
+    /// Field values mapped to the start and end of the colormap. Values outside are clamped.
+    pub range: Vec2,
+    /// Skips lighting, so colors can be read off exactly.
+    pub unlit: bool,
+}
+
+impl Default for ColormapMaterial {
+    fn default() -> Self {
+        Self {
+            colormap: Colormap::default(),
+            field: 0,
+            range: Vec2::new(0.0, 1.0),
+            unlit: false,
+        }
+    }
+}
+
+/// The GPU representation of the uniform data of a [`ColormapMaterial`].
+#[derive(Clone, Default, ShaderType)]
+pub struct ColormapMaterialUniform {
+    pub colormap: u32,
+    pub field: u32,
+    pub range: Vec2,
+    pub unlit: u32,
+}
+
+impl AsBindGroupShaderType<ColormapMaterialUniform> for ColormapMaterial {
+    fn as_bind_group_shader_type(&self, _images: &RenderAssets<Image>) -> ColormapMaterialUniform {
+        ColormapMaterialUniform {
+            colormap: match self.colormap {
+                Colormap::Viridis => 0,
+                Colormap::Magma => 1,
+                Colormap::Diverging => 2,
+            },
+            field: self.field,
+            range: self.range,
+            unlit: self.unlit.into(),
+        }
+    }
+}
+
+impl Material for ColormapMaterial {
+    fn vertex_shader() -> ShaderRef {
+        "shaders/colormap_material.wgsl".into()
+    }
+    fn fragment_shader() -> ShaderRef {
+        "shaders/colormap_material.wgsl".into()
+    }
+
+    fn specialize(
+        _pipeline: &MaterialPipeline<Self>,
+        descriptor: &mut RenderPipelineDescriptor,
+        layout: &MeshVertexBufferLayout,
+        _key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        let vertex_layout = layout.get_layout(&[
+            Mesh::ATTRIBUTE_POSITION.at_shader_location(0),
+            Mesh::ATTRIBUTE_NORMAL.at_shader_location(1),
+            ATTRIBUTE_SCALAR_FIELDS.at_shader_location(2),
+        ])?;
+        descriptor.vertex.buffers = vec![vertex_layout];
+        // Surfaces cut open by the grid boundary should show their inside too.
+        descriptor.primitive.cull_mode = None;
+        Ok(())
+    }
+}
@@ -1,6 +1,25 @@
 use bevy::prelude::Vec3;
 
-use crate::lookup_tables::{EDGE_TABLE, TRI_TABLE};
+use crate::{
+    grid::DensityGrid,
+    lookup_tables::{EDGE_TABLE, TRI_TABLE},
+};
+
+/// The corners joined by each cube edge, in the bit order of `EDGE_TABLE`.
+const EDGE_CORNERS: [(usize, usize); 12] = [
+    (0, 1),
+    (1, 2),
+    (2, 3),
+    (3, 0),
+    (4, 5),
+    (5, 6),
+    (6, 7),
+    (7, 4),
+    (0, 4),
+    (1, 5),
+    (2, 6),
+    (3, 7),
+];
 
 pub struct MarchingCubes {
     pub iso_surface: f32,
@@ -13,134 +32,414 @@ pub struct Triangle {
     pub vertex_3: Vec3,
 }
 
+/// A surface vertex and the material of the solid sample on its edge.
+#[derive(Debug, Clone, Copy)]
+pub struct MaterialVertex {
+    pub position: Vec3,
+    pub material: u8,
+}
+
+#[derive(Debug)]
+pub struct MaterialTriangle {
+    pub vertices: [MaterialVertex; 3],
+}
+
+/// A surface carrying extra per-vertex scalar fields, three vertices per triangle.
+#[derive(Debug, Default)]
+pub struct FieldSurface {
+    pub positions: Vec<Vec3>,
+    /// One list per field, each holding a value for every entry of `positions`.
+    pub fields: Vec<Vec<f32>>,
+}
+
+impl FieldSurface {
+    fn with_fields(count: usize) -> Self {
+        Self {
+            positions: Vec::new(),
+            fields: vec![Vec::new(); count],
+        }
+    }
+}
+
 impl MarchingCubes {
     pub fn new(iso_surface: f32) -> Self {
         Self { iso_surface }
     }
 
-    fn interpolate_vertex(&self, a: &(Vec3, f32), b: &(Vec3, f32)) -> Vec3 {
-        if (self.iso_surface - a.1).abs() < f32::EPSILON {
-            return a.0;
+    /// How far along the edge from `a` to `b` the surface crosses, from `0.0` at `a` to `1.0`
+    /// at `b`.
+    fn edge_mu(&self, a: f32, b: f32) -> f32 {
+        if (self.iso_surface - a).abs() < f32::EPSILON {
+            return 0.0;
         }
-        if (self.iso_surface - b.1).abs() < f32::EPSILON {
-            return b.0;
+        if (self.iso_surface - b).abs() < f32::EPSILON {
+            return 1.0;
         }
-        if (a.1 - b.1).abs() < f32::EPSILON {
-            return a.0;
+        if (a - b).abs() < f32::EPSILON {
+            return 0.0;
         }
-        let mu = (self.iso_surface - a.1) / (b.1 - a.1);
-        let x = a.0.x + mu * (b.0.x - a.0.x);
-        let y = a.0.y + mu * (b.0.y - a.0.y);
-        let z = a.0.z + mu * (b.0.z - a.0.z);
-        Vec3::new(x, y, z)
+        (self.iso_surface - a) / (b - a)
+    }
 
-        // (a.0 + b.0) / 2.0
+    fn interpolate_vertex(a: Vec3, b: Vec3, mu: f32) -> Vec3 {
+        let x = a.x + mu * (b.x - a.x);
+        let y = a.y + mu * (b.y - a.y);
+        let z = a.z + mu * (b.z - a.z);
+        Vec3::new(x, y, z)
     }
 
-    pub fn polygonize(&self, grid: [(Vec3, f32); 8]) -> Vec<Triangle> {
+    /// Triangulates one cell given its corner densities. `vertex` is called once per crossed
+    /// edge with the edge's corner indices and [`edge_mu`](Self::edge_mu), and builds whatever
+    /// the caller wants to store per vertex.
+    fn triangulate<V: Copy>(
+        &self,
+        densities: [f32; 8],
+        mut vertex: impl FnMut(usize, usize, f32) -> V,
+    ) -> Vec<[V; 3]> {
         let mut cube_index = 0;
-
-        let mut vertex_list: [Option<Vec3>; 12] = [None; 12];
-        let mut triangle_list: Vec<Triangle> = Vec::with_capacity(5);
-
-        if grid[0].1 < self.iso_surface {
-            cube_index |= 1;
-        }
-
-        if grid[1].1 < self.iso_surface {
-            cube_index |= 2;
+        for (corner, density) in densities.iter().enumerate() {
+            if *density < self.iso_surface {
+                cube_index |= 1 << corner;
+            }
         }
 
-        if grid[2].1 < self.iso_surface {
-            cube_index |= 4;
+        let edges = EDGE_TABLE[cube_index];
+        if edges == 0 {
+            return Vec::new();
         }
 
-        if grid[3].1 < self.iso_surface {
-            cube_index |= 8;
+        let mut vertex_list: [Option<V>; 12] = [None; 12];
+        for (edge, &(a, b)) in EDGE_CORNERS.iter().enumerate() {
+            if edges & (1 << edge) > 0 {
+                let mu = self.edge_mu(densities[a], densities[b]);
+                vertex_list[edge] = Some(vertex(a, b, mu));
+            }
         }
 
-        if grid[4].1 < self.iso_surface {
-            cube_index |= 16;
-        }
+        TRI_TABLE[cube_index]
+            .chunks_exact(3)
+            .take_while(|edges| edges[0] != -1)
+            .map(|edges| {
+                [
+                    vertex_list[edges[0] as usize].unwrap(),
+                    vertex_list[edges[1] as usize].unwrap(),
+                    vertex_list[edges[2] as usize].unwrap(),
+                ]
+            })
+            .collect()
+    }
 
-        if grid[5].1 < self.iso_surface {
-            cube_index |= 32;
-        }
+    pub fn polygonize(&self, grid: [(Vec3, f32); 8]) -> Vec<Triangle> {
+        self.triangulate(grid.map(|corner| corner.1), |a, b, mu| {
+            Self::interpolate_vertex(grid[a].0, grid[b].0, mu)
+        })
+        .into_iter()
+        .map(|[vertex_1, vertex_2, vertex_3]| Triangle {
+            vertex_1,
+            vertex_2,
+            vertex_3,
+        })
+        .collect()
+    }
 
-        if grid[6].1 < self.iso_surface {
-            cube_index |= 64;
-        }
+    /// Like [`polygonize`](Self::polygonize), with a material id per corner.
+    ///
+    /// Each vertex takes the material of the solid corner of its edge: the other corner is air,
+    /// and whatever material it holds is not part of the surface. Blending between materials
+    /// happens across triangles whose vertices come from differently textured corners.
+    pub fn polygonize_materials(
+        &self,
+        grid: [(Vec3, f32); 8],
+        materials: [u8; 8],
+    ) -> Vec<MaterialTriangle> {
+        self.triangulate(grid.map(|corner| corner.1), |a, b, mu| {
+            let solid = if grid[a].1 < self.iso_surface { a } else { b };
+            MaterialVertex {
+                position: Self::interpolate_vertex(grid[a].0, grid[b].0, mu),
+                material: materials[solid],
+            }
+        })
+        .into_iter()
+        .map(|vertices| MaterialTriangle { vertices })
+        .collect()
+    }
 
-        if grid[7].1 < self.iso_surface {
-            cube_index |= 128;
-        }
+    /// Polygonizes every cell of `grid`.
+    pub fn polygonize_grid(&self, grid: &DensityGrid) -> Vec<Triangle> {
+        let cells = grid.cell_count();
+        let mut triangles = Vec::new();
 
-        if EDGE_TABLE[cube_index] == 0 {
-            return triangle_list;
+        for z in 0..cells.z {
+            for y in 0..cells.y {
+                for x in 0..cells.x {
+                    triangles.append(&mut self.polygonize(grid.cell(x, y, z)));
+                }
+            }
         }
 
-        if EDGE_TABLE[cube_index] & 1 > 0 {
-            vertex_list[0] = Some(Self::interpolate_vertex(self, &grid[0], &grid[1]))
-        }
+        triangles
+    }
 
-        if EDGE_TABLE[cube_index] & 2 > 0 {
-            vertex_list[1] = Some(Self::interpolate_vertex(self, &grid[1], &grid[2]))
+    /// Polygonizes every cell of `grid`, keeping the grid's materials.
+    pub fn polygonize_grid_materials(&self, grid: &DensityGrid) -> Vec<MaterialTriangle> {
+        let cells = grid.cell_count();
+        let mut triangles = Vec::new();
+
+        for z in 0..cells.z {
+            for y in 0..cells.y {
+                for x in 0..cells.x {
+                    triangles.append(
+                        &mut self
+                            .polygonize_materials(grid.cell(x, y, z), grid.cell_materials(x, y, z)),
+                    );
+                }
+            }
         }
 
-        if EDGE_TABLE[cube_index] & 4 > 0 {
-            vertex_list[2] = Some(Self::interpolate_vertex(self, &grid[2], &grid[3]))
-        }
+        triangles
+    }
 
-        if EDGE_TABLE[cube_index] & 8 > 0 {
-            vertex_list[3] = Some(Self::interpolate_vertex(self, &grid[3], &grid[0]))
-        }
+    /// Like [`polygonize`](Self::polygonize), carrying extra scalar fields sampled at the same
+    /// corners. Each field is interpolated along the crossed edge with the same `mu` as the
+    /// vertex position.
+    pub fn polygonize_fields(&self, grid: [(Vec3, f32); 8], fields: &[[f32; 8]]) -> FieldSurface {
+        let mut surface = FieldSurface::with_fields(fields.len());
+        self.append_fields(&mut surface, grid, |field, corner| fields[field][corner]);
+        surface
+    }
 
-        if EDGE_TABLE[cube_index] & 16 > 0 {
-            vertex_list[4] = Some(Self::interpolate_vertex(self, &grid[4], &grid[5]))
+    /// Polygonizes every cell of `grid`, interpolating `fields` onto the surface. Each field
+    /// holds one value per grid sample, laid out like [`DensityGrid::values`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if a field's length differs from the grid's sample count.
+    pub fn polygonize_grid_fields(&self, grid: &DensityGrid, fields: &[&[f32]]) -> FieldSurface {
+        for field in fields {
+            assert_eq!(
+                field.len(),
+                grid.values.len(),
+                "field length does not match the grid's sample count"
+            );
         }
 
-        if EDGE_TABLE[cube_index] & 32 > 0 {
-            vertex_list[5] = Some(Self::interpolate_vertex(self, &grid[5], &grid[6]))
+        let cells = grid.cell_count();
+        let mut surface = FieldSurface::with_fields(fields.len());
+
+        for z in 0..cells.z {
+            for y in 0..cells.y {
+                for x in 0..cells.x {
+                    let indices = grid.cell_indices(x, y, z);
+                    self.append_fields(&mut surface, grid.cell(x, y, z), |field, corner| {
+                        fields[field][indices[corner]]
+                    });
+                }
+            }
         }
 
-        if EDGE_TABLE[cube_index] & 64 > 0 {
-            vertex_list[6] = Some(Self::interpolate_vertex(self, &grid[6], &grid[7]))
-        }
+        surface
+    }
 
-        if EDGE_TABLE[cube_index] & 128 > 0 {
-            vertex_list[7] = Some(Self::interpolate_vertex(self, &grid[7], &grid[4]))
+    /// Polygonizes one cell into `surface`. `field(i, corner)` gives field `i` at a corner.
+    fn append_fields(
+        &self,
+        surface: &mut FieldSurface,
+        grid: [(Vec3, f32); 8],
+        field: impl Fn(usize, usize) -> f32,
+    ) {
+        let triangles = self.triangulate(grid.map(|corner| corner.1), |a, b, mu| (a, b, mu));
+
+        for (a, b, mu) in triangles.into_iter().flatten() {
+            surface
+                .positions
+                .push(Self::interpolate_vertex(grid[a].0, grid[b].0, mu));
+            for (i, values) in surface.fields.iter_mut().enumerate() {
+                let (value_a, value_b) = (field(i, a), field(i, b));
+                values.push(value_a + mu * (value_b - value_a));
+            }
         }
+    }
+}
 
-        if EDGE_TABLE[cube_index] & 256 > 0 {
-            vertex_list[8] = Some(Self::interpolate_vertex(self, &grid[0], &grid[4]))
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::UVec3;
+
+    use super::*;
+
+    const ISO: f32 = 0.5;
+
+    /// Corners of the unit cube in the order of [`DensityGrid::cell`].
+    const CORNERS: [Vec3; 8] = [
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(1.0, 0.0, 0.0),
+        Vec3::new(1.0, 0.0, 1.0),
+        Vec3::new(0.0, 0.0, 1.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        Vec3::new(1.0, 1.0, 0.0),
+        Vec3::new(1.0, 1.0, 1.0),
+        Vec3::new(0.0, 1.0, 1.0),
+    ];
+
+    /// A cell whose solid corners are the set bits of `solid`, with uneven densities so the
+    /// surface crosses each edge somewhere other than its middle.
+    fn cell(solid: u8) -> [(Vec3, f32); 8] {
+        let mut corners = [(Vec3::ZERO, 0.0); 8];
+        for (corner, position) in CORNERS.into_iter().enumerate() {
+            let density = if solid & (1 << corner) != 0 {
+                ISO - 0.2 - corner as f32 * 0.1
+            } else {
+                ISO + 0.3 + corner as f32 * 0.07
+            };
+            corners[corner] = (position, density);
         }
+        corners
+    }
 
-        if EDGE_TABLE[cube_index] & 512 > 0 {
-            vertex_list[9] = Some(Self::interpolate_vertex(self, &grid[1], &grid[5]))
-        }
+    /// The original hand-unrolled `polygonize`, kept as a reference for the generic path.
+    fn legacy_polygonize(grid: [(Vec3, f32); 8]) -> Vec<[Vec3; 3]> {
+        let interpolate = |a: (Vec3, f32), b: (Vec3, f32)| {
+            if (ISO - a.1).abs() < f32::EPSILON {
+                return a.0;
+            }
+            if (ISO - b.1).abs() < f32::EPSILON {
+                return b.0;
+            }
+            if (a.1 - b.1).abs() < f32::EPSILON {
+                return a.0;
+            }
+            let mu = (ISO - a.1) / (b.1 - a.1);
+            Vec3::new(
+                a.0.x + mu * (b.0.x - a.0.x),
+                a.0.y + mu * (b.0.y - a.0.y),
+                a.0.z + mu * (b.0.z - a.0.z),
+            )
+        };
 
-        if EDGE_TABLE[cube_index] & 1024 > 0 {
-            vertex_list[10] = Some(Self::interpolate_vertex(self, &grid[2], &grid[6]))
+        let mut cube_index = 0;
+        for (corner, (_, density)) in grid.iter().enumerate() {
+            if *density < ISO {
+                cube_index |= 1 << corner;
+            }
         }
 
-        if EDGE_TABLE[cube_index] & 2048 > 0 {
-            vertex_list[11] = Some(Self::interpolate_vertex(self, &grid[3], &grid[7]))
+        let edges = [
+            (0, 1),
+            (1, 2),
+            (2, 3),
+            (3, 0),
+            (4, 5),
+            (5, 6),
+            (6, 7),
+            (7, 4),
+            (0, 4),
+            (1, 5),
+            (2, 6),
+            (3, 7),
+        ];
+        let mut vertex_list = [None; 12];
+        for (edge, (a, b)) in edges.into_iter().enumerate() {
+            if EDGE_TABLE[cube_index] & (1 << edge) > 0 {
+                vertex_list[edge] = Some(interpolate(grid[a], grid[b]));
+            }
         }
 
+        let mut triangles = Vec::new();
         for i in (0..=12).step_by(3) {
-            if TRI_TABLE[cube_index as usize][i] == -1 {
+            if TRI_TABLE[cube_index][i] == -1 {
                 break;
             }
+            triangles.push([
+                vertex_list[TRI_TABLE[cube_index][i] as usize].unwrap(),
+                vertex_list[TRI_TABLE[cube_index][i + 1] as usize].unwrap(),
+                vertex_list[TRI_TABLE[cube_index][i + 2] as usize].unwrap(),
+            ]);
+        }
+        triangles
+    }
 
-            let triangle = Triangle {
-                vertex_1: vertex_list[TRI_TABLE[cube_index][i] as usize].unwrap(),
-                vertex_2: vertex_list[TRI_TABLE[cube_index][i + 1] as usize].unwrap(),
-                vertex_3: vertex_list[TRI_TABLE[cube_index][i + 2] as usize].unwrap(),
-            };
+    #[test]
+    fn generic_path_matches_the_original_polygonize() {
+        let marching_cubes = MarchingCubes::new(ISO);
+
+        // One corner exactly on the iso level, which the original snapped to that corner.
+        let mut on_iso_level = cell(0b0000_0101);
+        on_iso_level[1].1 = ISO;
+
+        for grid in (0..=u8::MAX).map(cell).chain([on_iso_level]) {
+            let expected = legacy_polygonize(grid);
+
+            let triangles: Vec<[Vec3; 3]> = marching_cubes
+                .polygonize(grid)
+                .into_iter()
+                .map(|triangle| [triangle.vertex_1, triangle.vertex_2, triangle.vertex_3])
+                .collect();
+            assert_eq!(triangles, expected);
+
+            let material_positions: Vec<[Vec3; 3]> = marching_cubes
+                .polygonize_materials(grid, [0; 8])
+                .into_iter()
+                .map(|triangle| triangle.vertices.map(|vertex| vertex.position))
+                .collect();
+            assert_eq!(material_positions, expected);
+
+            let surface = marching_cubes.polygonize_fields(grid, &[]);
+            assert_eq!(surface.positions, expected.concat());
+        }
+    }
 
-            triangle_list.push(triangle);
+    #[test]
+    fn vertex_materials_come_from_the_solid_corner() {
+        let marching_cubes = MarchingCubes::new(ISO);
+        let materials = [10, 11, 12, 13, 14, 15, 16, 17];
+
+        // A lone solid corner, and a lone corner of air whose neighbours all differ.
+        for solid in [0b0000_0001, !0b0100_0000] {
+            let grid = cell(solid);
+            let triangles = marching_cubes.polygonize_materials(grid, materials);
+            assert!(!triangles.is_empty());
+
+            for vertex in triangles.iter().flat_map(|triangle| triangle.vertices) {
+                let on_edge = |&(a, b): &(usize, usize)| {
+                    let mu = marching_cubes.edge_mu(grid[a].1, grid[b].1);
+                    MarchingCubes::interpolate_vertex(grid[a].0, grid[b].0, mu) == vertex.position
+                };
+                let &(a, b) = EDGE_CORNERS.iter().find(|edge| on_edge(edge)).unwrap();
+                let solid = if grid[a].1 < ISO { a } else { b };
+
+                assert_eq!(vertex.material, materials[solid], "{:?}", vertex);
+            }
         }
+    }
 
-        triangle_list
+    #[test]
+    fn grid_fields_use_the_position_mu() {
+        let marching_cubes = MarchingCubes::new(ISO);
+        let grid = DensityGrid::from_fn(UVec3::new(3, 2, 2), Vec3::ZERO, 1.0, |position| {
+            position.x * position.x * 0.3 + position.y * 0.4 + position.z * 0.2
+        });
+        // The density itself lands on the iso level, and the x coordinate, x-fastest like the
+        // samples, on the vertex's x.
+        let x: Vec<f32> = (0..grid.values.len())
+            .map(|index| (index % 3) as f32)
+            .collect();
+
+        let surface = marching_cubes.polygonize_grid_fields(&grid, &[&grid.values, &x]);
+
+        assert!(!surface.positions.is_empty());
+        assert_eq!(surface.fields[0].len(), surface.positions.len());
+        assert_eq!(surface.fields[1].len(), surface.positions.len());
+        for (i, position) in surface.positions.iter().enumerate() {
+            assert!(
+                (surface.fields[0][i] - ISO).abs() < 1e-5,
+                "{}",
+                surface.fields[0][i]
+            );
+            assert!(
+                (surface.fields[1][i] - position.x).abs() < 1e-5,
+                "{}",
+                position
+            );
+        }
     }
 }
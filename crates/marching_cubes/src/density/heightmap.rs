@@ -0,0 +1,270 @@
+use std::{error::Error, fmt};
+
+use bevy::{
+    prelude::{Image, Vec3},
+    render::render_resource::TextureFormat,
+};
+
+use super::DensitySource;
+
+#[derive(Debug)]
+pub enum HeightmapError {
+    UnsupportedFormat(TextureFormat),
+    Empty,
+    SizeMismatch { expected: usize, actual: usize },
+}
+
+impl fmt::Display for HeightmapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HeightmapError::UnsupportedFormat(format) => {
+                write!(f, "unsupported heightmap texture format {:?}", format)
+            }
+            HeightmapError::Empty => write!(f, "heightmap has no texels"),
+            HeightmapError::SizeMismatch { expected, actual } => write!(
+                f,
+                "heightmap data has {} samples but its dimensions require {}",
+                actual, expected
+            ),
+        }
+    }
+}
+
+impl Error for HeightmapError {}
+
+/// Terrain density from a grayscale heightmap.
+///
+/// Texel `(u, v)` covers world position `(u * horizontal_scale, v * horizontal_scale)` on the
+/// XZ plane and a white texel is `vertical_scale` units high. The density is the signed vertical
+/// distance to the surface, negative underground, so it pairs with an iso level of `0.0`. Sampling
+/// outside the image clamps to the nearest edge.
+///
+/// This is library-only: the app's terrain is generated from noise, so build grids from a
+/// heightmap with [`DensityGrid::from_source`](crate::grid::DensityGrid::from_source).
+#[derive(Debug, Clone)]
+pub struct HeightmapDensity {
+    width: u32,
+    depth: u32,
+    /// Normalized heights in `0.0..=1.0`, row-major.
+    heights: Vec<f32>,
+    pub horizontal_scale: f32,
+    pub vertical_scale: f32,
+}
+
+impl HeightmapDensity {
+    pub fn new(
+        width: u32,
+        depth: u32,
+        heights: Vec<f32>,
+        horizontal_scale: f32,
+        vertical_scale: f32,
+    ) -> Result<Self, HeightmapError> {
+        if width == 0 || depth == 0 {
+            return Err(HeightmapError::Empty);
+        }
+
+        let expected = (width * depth) as usize;
+        if heights.len() != expected {
+            return Err(HeightmapError::SizeMismatch {
+                expected,
+                actual: heights.len(),
+            });
+        }
+
+        Ok(Self {
+            width,
+            depth,
+            heights,
+            horizontal_scale,
+            vertical_scale,
+        })
+    }
+
+    pub fn from_luma8(
+        width: u32,
+        depth: u32,
+        data: &[u8],
+        horizontal_scale: f32,
+        vertical_scale: f32,
+    ) -> Result<Self, HeightmapError> {
+        let heights = data.iter().map(|&h| h as f32 / u8::MAX as f32).collect();
+        Self::new(width, depth, heights, horizontal_scale, vertical_scale)
+    }
+
+    pub fn from_luma16(
+        width: u32,
+        depth: u32,
+        data: &[u16],
+        horizontal_scale: f32,
+        vertical_scale: f32,
+    ) -> Result<Self, HeightmapError> {
+        let heights = data.iter().map(|&h| h as f32 / u16::MAX as f32).collect();
+        Self::new(width, depth, heights, horizontal_scale, vertical_scale)
+    }
+
+    /// Reads the first channel of a loaded image.
+    ///
+    /// Bevy expands 8-bit grayscale PNGs to `Rgba8Unorm(Srgb)` and keeps 16-bit ones as
+    /// `R16Uint`, so both of those are accepted alongside the plain single channel formats.
+    pub fn from_image(
+        image: &Image,
+        horizontal_scale: f32,
+        vertical_scale: f32,
+    ) -> Result<Self, HeightmapError> {
+        let size = image.texture_descriptor.size;
+        let format = image.texture_descriptor.format;
+
+        let (bytes_per_pixel, wide) = match format {
+            TextureFormat::R8Unorm => (1, false),
+            TextureFormat::Rg8Unorm => (2, false),
+            TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => (4, false),
+            TextureFormat::R16Uint | TextureFormat::R16Unorm => (2, true),
+            TextureFormat::Rg16Uint | TextureFormat::Rg16Unorm => (4, true),
+            TextureFormat::Rgba16Uint | TextureFormat::Rgba16Unorm => (8, true),
+            format => return Err(HeightmapError::UnsupportedFormat(format)),
+        };
+
+        let heights = image
+            .data
+            .chunks_exact(bytes_per_pixel)
+            .map(|pixel| {
+                if wide {
+                    u16::from_ne_bytes([pixel[0], pixel[1]]) as f32 / u16::MAX as f32
+                } else {
+                    pixel[0] as f32 / u8::MAX as f32
+                }
+            })
+            .collect();
+
+        Self::new(
+            size.width,
+            size.height,
+            heights,
+            horizontal_scale,
+            vertical_scale,
+        )
+    }
+
+    /// Bilinearly interpolated surface height at a world XZ position.
+    pub fn height_at(&self, x: f32, z: f32) -> f32 {
+        let u = (x / self.horizontal_scale).clamp(0.0, (self.width - 1) as f32);
+        let v = (z / self.horizontal_scale).clamp(0.0, (self.depth - 1) as f32);
+
+        let u0 = u.floor() as u32;
+        let v0 = v.floor() as u32;
+        let u1 = (u0 + 1).min(self.width - 1);
+        let v1 = (v0 + 1).min(self.depth - 1);
+        let fu = u - u0 as f32;
+        let fv = v - v0 as f32;
+
+        let top = lerp(self.texel(u0, v0), self.texel(u1, v0), fu);
+        let bottom = lerp(self.texel(u0, v1), self.texel(u1, v1), fu);
+
+        lerp(top, bottom, fv) * self.vertical_scale
+    }
+
+    fn texel(&self, u: u32, v: u32) -> f32 {
+        self.heights[(u + v * self.width) as usize]
+    }
+}
+
+impl DensitySource for HeightmapDensity {
+    fn density(&self, position: Vec3) -> f32 {
+        position.y - self.height_at(position.x, position.z)
+    }
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::render::render_resource::{Extent3d, TextureDimension};
+
+    use super::*;
+
+    /// A 2×2 map rising from 0 to 1 along x, scaled to 2 units per texel and 10 units high.
+    fn ramp() -> HeightmapDensity {
+        HeightmapDensity::new(2, 2, vec![0.0, 1.0, 0.0, 1.0], 2.0, 10.0).unwrap()
+    }
+
+    #[test]
+    fn interpolates_between_texels() {
+        let heightmap = ramp();
+
+        assert_eq!(heightmap.height_at(0.0, 0.0), 0.0);
+        assert_eq!(heightmap.height_at(1.0, 1.0), 5.0);
+        assert_eq!(heightmap.height_at(2.0, 2.0), 10.0);
+    }
+
+    #[test]
+    fn clamps_outside_the_image() {
+        let heightmap = ramp();
+
+        assert_eq!(heightmap.height_at(-5.0, -5.0), 0.0);
+        assert_eq!(heightmap.height_at(50.0, 0.5), 10.0);
+    }
+
+    #[test]
+    fn density_is_negative_underground() {
+        let heightmap = ramp();
+
+        assert_eq!(heightmap.density(Vec3::new(1.0, 2.0, 0.0)), -3.0);
+        assert_eq!(heightmap.density(Vec3::new(1.0, 8.0, 0.0)), 3.0);
+    }
+
+    #[test]
+    fn rejects_mismatched_sizes() {
+        assert!(matches!(
+            HeightmapDensity::new(0, 4, vec![], 1.0, 1.0),
+            Err(HeightmapError::Empty)
+        ));
+        assert!(matches!(
+            HeightmapDensity::from_luma8(2, 2, &[0, 0, 0], 1.0, 1.0),
+            Err(HeightmapError::SizeMismatch {
+                expected: 4,
+                actual: 3
+            })
+        ));
+    }
+
+    #[test]
+    fn normalizes_texels() {
+        let heightmap = HeightmapDensity::from_luma16(2, 1, &[0, u16::MAX], 1.0, 1.0).unwrap();
+
+        assert_eq!(heightmap.height_at(0.0, 0.0), 0.0);
+        assert_eq!(heightmap.height_at(1.0, 0.0), 1.0);
+    }
+
+    #[test]
+    fn reads_the_first_channel_of_images() {
+        let image = Image::new(
+            Extent3d {
+                width: 2,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            vec![0, 255, 255, 255, 255, 0, 0, 255],
+            TextureFormat::Rgba8UnormSrgb,
+        );
+        let heightmap = HeightmapDensity::from_image(&image, 1.0, 1.0).unwrap();
+
+        assert_eq!(heightmap.height_at(0.0, 0.0), 0.0);
+        assert_eq!(heightmap.height_at(1.0, 0.0), 1.0);
+
+        let float = Image::new(
+            Extent3d::default(),
+            TextureDimension::D2,
+            vec![0; 16],
+            TextureFormat::Rgba32Float,
+        );
+        assert!(matches!(
+            HeightmapDensity::from_image(&float, 1.0, 1.0),
+            Err(HeightmapError::UnsupportedFormat(
+                TextureFormat::Rgba32Float
+            ))
+        ));
+    }
+}
@@ -0,0 +1,50 @@
+use bevy::prelude::Vec3;
+
+mod heightmap;
+
+pub use heightmap::{HeightmapDensity, HeightmapError};
+
+/// A scalar field that can be sampled into a [`DensityGrid`](crate::grid::DensityGrid).
+///
+/// Values below the iso level passed to [`MarchingCubes`](crate::cpu::MarchingCubes) are solid.
+pub trait DensitySource {
+    fn density(&self, position: Vec3) -> f32;
+
+    /// Adds the values of `other` to this field, e.g. to carve overhangs into a heightmap with
+    /// 3D noise.
+    fn add<B: DensitySource>(self, other: B) -> Sum<Self, B>
+    where
+        Self: Sized,
+    {
+        Sum(self, other)
+    }
+
+    fn scale(self, factor: f32) -> Scaled<Self>
+    where
+        Self: Sized,
+    {
+        Scaled(self, factor)
+    }
+}
+
+impl<F: Fn(Vec3) -> f32> DensitySource for F {
+    fn density(&self, position: Vec3) -> f32 {
+        self(position)
+    }
+}
+
+pub struct Sum<A, B>(A, B);
+
+impl<A: DensitySource, B: DensitySource> DensitySource for Sum<A, B> {
+    fn density(&self, position: Vec3) -> f32 {
+        self.0.density(position) + self.1.density(position)
+    }
+}
+
+pub struct Scaled<A>(A, f32);
+
+impl<A: DensitySource> DensitySource for Scaled<A> {
+    fn density(&self, position: Vec3) -> f32 {
+        self.0.density(position) * self.1
+    }
+}
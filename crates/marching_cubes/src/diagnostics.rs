@@ -0,0 +1,142 @@
+//! Per-vertex diagnostics for inspecting mesher output: triangle shape quality, barycentric
+//! coordinates for wireframes and the isosurface's mean curvature.
+
+use bevy::{
+    prelude::{Mesh, Vec3},
+    render::mesh::VertexAttributeValues,
+};
+
+use crate::{
+    density::DensitySource,
+    mesh::{ATTRIBUTE_BARYCENTRIC, ATTRIBUTE_MEAN_CURVATURE, ATTRIBUTE_TRIANGLE_QUALITY},
+};
+
+/// How close a triangle is to equilateral: `1.0` for an equilateral triangle, falling to `0.0`
+/// as it degenerates into a line. This is the inverse of the radius ratio, twice the inradius
+/// over the circumradius.
+pub fn triangle_quality(a: Vec3, b: Vec3, c: Vec3) -> f32 {
+    let (ab, bc, ca) = (a.distance(b), b.distance(c), c.distance(a));
+    let product = ab * bc * ca;
+    if product <= f32::EPSILON {
+        return 0.0;
+    }
+    let s = (ab + bc + ca) / 2.0;
+    (8.0 * (s - ab) * (s - bc) * (s - ca) / product).clamp(0.0, 1.0)
+}
+
+/// Mean curvature of the isosurface of `source` through `position`, from central differences
+/// `step` apart. Positive where the surface is convex seen from the air, e.g. `1 / r` on a
+/// solid sphere of radius `r`.
+pub fn mean_curvature(source: &impl DensitySource, position: Vec3, step: f32) -> f32 {
+    let normal = |p: Vec3| {
+        let difference =
+            |axis: Vec3| source.density(p + axis * step) - source.density(p - axis * step);
+        Vec3::new(
+            difference(Vec3::X),
+            difference(Vec3::Y),
+            difference(Vec3::Z),
+        )
+        .normalize_or_zero()
+    };
+
+    // Half the divergence of the unit normal field.
+    let divergence = [Vec3::X, Vec3::Y, Vec3::Z]
+        .into_iter()
+        .map(|axis| (normal(position + axis * step) - normal(position - axis * step)).dot(axis))
+        .sum::<f32>()
+        / (2.0 * step);
+    divergence / 2.0
+}
+
+/// Inserts [`ATTRIBUTE_BARYCENTRIC`] and [`ATTRIBUTE_TRIANGLE_QUALITY`] into a mesh whose
+/// triangles own their vertices, such as one from [`build_mesh`](crate::mesh::build_mesh).
+/// Meshes without `f32` positions are left untouched.
+pub fn insert_triangle_diagnostics(mesh: &mut Mesh) {
+    let Some(VertexAttributeValues::Float32x3(positions)) =
+        mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+    else {
+        return;
+    };
+
+    let quality = positions
+        .chunks_exact(3)
+        .flat_map(|triangle| {
+            let [a, b, c] = [0, 1, 2].map(|i| Vec3::from(triangle[i]));
+            [triangle_quality(a, b, c); 3]
+        })
+        .collect::<Vec<_>>();
+    let barycentric = (0..quality.len())
+        .map(|i| {
+            let mut coordinates = [0.0; 3];
+            coordinates[i % 3] = 1.0;
+            coordinates
+        })
+        .collect::<Vec<_>>();
+
+    mesh.insert_attribute(ATTRIBUTE_BARYCENTRIC, barycentric);
+    mesh.insert_attribute(ATTRIBUTE_TRIANGLE_QUALITY, quality);
+}
+
+/// Bakes [`ATTRIBUTE_MEAN_CURVATURE`] into a mesh with `f32` positions, sampling `source` as
+/// [`mean_curvature`] does. Meshes without `f32` positions are left untouched.
+pub fn bake_mean_curvature(source: &impl DensitySource, mesh: &mut Mesh, step: f32) {
+    let Some(VertexAttributeValues::Float32x3(positions)) =
+        mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+    else {
+        return;
+    };
+
+    let curvature = positions
+        .iter()
+        .map(|&position| mean_curvature(source, Vec3::from(position), step))
+        .collect::<Vec<_>>();
+
+    mesh.insert_attribute(ATTRIBUTE_MEAN_CURVATURE, curvature);
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::UVec3;
+
+    use super::*;
+    use crate::grid::DensityGrid;
+
+    #[test]
+    fn equilateral_triangles_have_full_quality() {
+        let quality = triangle_quality(
+            Vec3::ZERO,
+            Vec3::X,
+            Vec3::new(0.5, 3.0_f32.sqrt() / 2.0, 0.0),
+        );
+
+        assert!((quality - 1.0).abs() < 1e-5, "{}", quality);
+    }
+
+    #[test]
+    fn slivers_have_almost_no_quality() {
+        let sliver = triangle_quality(Vec3::ZERO, Vec3::X, Vec3::new(0.5, 0.001, 0.0));
+        let line = triangle_quality(Vec3::ZERO, Vec3::X, Vec3::new(2.0, 0.0, 0.0));
+
+        assert!(sliver < 0.01, "{}", sliver);
+        assert_eq!(line, 0.0);
+    }
+
+    #[test]
+    fn spheres_curve_by_their_inverse_radius() {
+        let radius = 4.0;
+        let center = Vec3::splat(6.0);
+        let grid = DensityGrid::from_fn(UVec3::splat(25), Vec3::ZERO, 0.5, |position| {
+            position.distance(center) - radius
+        });
+
+        for direction in [Vec3::X, -Vec3::Y, Vec3::new(1.0, 1.0, 1.0).normalize()] {
+            let curvature = mean_curvature(&grid, center + direction * radius, 0.25);
+            assert!(
+                (curvature - 1.0 / radius).abs() < 0.1 / radius,
+                "{} along {}",
+                curvature,
+                direction
+            );
+        }
+    }
+}
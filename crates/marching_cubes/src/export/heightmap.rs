@@ -0,0 +1,324 @@
+//! Top-down rasterization of a density field into heightmap, normal map and slope mask images.
+
+use std::{error::Error, fmt, path::Path};
+
+use bevy::prelude::{UVec2, Vec2, Vec3};
+use image::{ImageBuffer, ImageResult, Luma, Rgb};
+
+use crate::density::DensitySource;
+
+/// Number of bisection steps used to refine a surface crossing once the ray march finds one.
+const REFINEMENT_STEPS: u32 = 8;
+
+/// The region and sampling used by [`TerrainHeightmap::sample`].
+#[derive(Debug, Clone)]
+pub struct HeightmapRegion {
+    /// World XZ position of the first texel.
+    pub min: Vec2,
+    /// Output resolution in texels.
+    pub resolution: UVec2,
+    /// World distance between neighbouring texels.
+    pub texel_size: f32,
+    /// Height the rays start from.
+    pub top: f32,
+    /// Height the rays give up at. Columns without any solid ground are recorded at this height.
+    pub bottom: f32,
+    /// Vertical distance between density samples along each ray. Rays stop early, as if they
+    /// found no ground, once a step is too small to change the height at their magnitude.
+    pub step: f32,
+    pub iso_level: f32,
+}
+
+#[derive(Debug)]
+pub enum HeightmapRegionError {
+    /// `step` must be positive, or the rays would never reach `bottom`.
+    NonPositiveStep(f32),
+    /// `top` must be above `bottom`.
+    EmptyRange { top: f32, bottom: f32 },
+    /// `top`, `bottom` and `step` must be finite, or the rays would never end.
+    NotFinite { field: &'static str, value: f32 },
+}
+
+impl fmt::Display for HeightmapRegionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HeightmapRegionError::NonPositiveStep(step) => {
+                write!(f, "ray step must be greater than zero, got {}", step)
+            }
+            HeightmapRegionError::EmptyRange { top, bottom } => {
+                write!(f, "ray top {} must be above its bottom {}", top, bottom)
+            }
+            HeightmapRegionError::NotFinite { field, value } => {
+                write!(f, "ray `{}` must be finite, got {}", field, value)
+            }
+        }
+    }
+}
+
+impl Error for HeightmapRegionError {}
+
+/// The highest surface crossing in each column of a region, in world units.
+pub struct TerrainHeightmap {
+    pub region: HeightmapRegion,
+    pub heights: Vec<f32>,
+}
+
+impl TerrainHeightmap {
+    /// Marches a ray straight down through every texel of `region` and records where it first
+    /// enters solid ground.
+    pub fn sample(
+        source: &impl DensitySource,
+        region: HeightmapRegion,
+    ) -> Result<Self, HeightmapRegionError> {
+        for (field, value) in [
+            ("top", region.top),
+            ("bottom", region.bottom),
+            ("step", region.step),
+        ] {
+            if !value.is_finite() {
+                return Err(HeightmapRegionError::NotFinite { field, value });
+            }
+        }
+        if region.step <= 0.0 {
+            return Err(HeightmapRegionError::NonPositiveStep(region.step));
+        }
+        if region.top <= region.bottom {
+            return Err(HeightmapRegionError::EmptyRange {
+                top: region.top,
+                bottom: region.bottom,
+            });
+        }
+
+        let mut heights = Vec::with_capacity((region.resolution.x * region.resolution.y) as usize);
+
+        for v in 0..region.resolution.y {
+            for u in 0..region.resolution.x {
+                let column = region.min + Vec2::new(u as f32, v as f32) * region.texel_size;
+                heights.push(march_column(source, &region, column));
+            }
+        }
+
+        Ok(Self { region, heights })
+    }
+
+    pub fn height(&self, u: u32, v: u32) -> f32 {
+        let u = u.min(self.region.resolution.x - 1);
+        let v = v.min(self.region.resolution.y - 1);
+        self.heights[(u + v * self.region.resolution.x) as usize]
+    }
+
+    /// Surface normal at a texel from central differences of the heights, or one-sided
+    /// differences along the edges of the region.
+    pub fn normal(&self, u: u32, v: u32) -> Vec3 {
+        let resolution = self.region.resolution;
+        let (u, v) = (u.min(resolution.x - 1), v.min(resolution.y - 1));
+        let gradient = |before: u32, after: u32, height: &dyn Fn(u32) -> f32| {
+            if after == before {
+                return 0.0;
+            }
+            (height(after) - height(before)) / ((after - before) as f32 * self.region.texel_size)
+        };
+
+        let dx = gradient(u.saturating_sub(1), (u + 1).min(resolution.x - 1), &|u| {
+            self.height(u, v)
+        });
+        let dz = gradient(v.saturating_sub(1), (v + 1).min(resolution.y - 1), &|v| {
+            self.height(u, v)
+        });
+
+        Vec3::new(-dx, 1.0, -dz).normalize()
+    }
+
+    /// Angle between the surface and the horizontal plane, in radians.
+    pub fn slope(&self, u: u32, v: u32) -> f32 {
+        self.normal(u, v).angle_between(Vec3::Y)
+    }
+
+    /// Writes the heights as a 16-bit grayscale PNG, mapping `bottom..=top` to the full range.
+    pub fn save_height_png(&self, path: impl AsRef<Path>) -> ImageResult<()> {
+        let range = self.region.top - self.region.bottom;
+        self.save(path, |u, v| {
+            let height = (self.height(u, v) - self.region.bottom) / range;
+            Luma([(height.clamp(0.0, 1.0) * u16::MAX as f32).round() as u16])
+        })
+    }
+
+    /// Writes the normals as an RGB PNG with X in red, Z in green and Y in blue, so flat ground
+    /// comes out as the usual `(128, 128, 255)` normal map blue.
+    pub fn save_normal_png(&self, path: impl AsRef<Path>) -> ImageResult<()> {
+        self.save(path, |u, v| {
+            let normal = self.normal(u, v);
+            let encode = |value: f32| ((value * 0.5 + 0.5) * u8::MAX as f32).round() as u8;
+            Rgb([encode(normal.x), encode(normal.z), encode(normal.y)])
+        })
+    }
+
+    /// Writes a mask that is white wherever the slope exceeds `max_slope` radians.
+    pub fn save_slope_mask_png(&self, path: impl AsRef<Path>, max_slope: f32) -> ImageResult<()> {
+        self.save(path, |u, v| {
+            Luma([if self.slope(u, v) > max_slope {
+                u8::MAX
+            } else {
+                0
+            }])
+        })
+    }
+
+    fn save<P: image::Pixel + image::PixelWithColorType>(
+        &self,
+        path: impl AsRef<Path>,
+        pixel: impl Fn(u32, u32) -> P,
+    ) -> ImageResult<()>
+    where
+        [P::Subpixel]: image::EncodableLayout,
+    {
+        ImageBuffer::from_fn(self.region.resolution.x, self.region.resolution.y, pixel).save(path)
+    }
+}
+
+fn march_column(source: &impl DensitySource, region: &HeightmapRegion, column: Vec2) -> f32 {
+    let at = |height: f32| source.density(Vec3::new(column.x, height, column.y));
+
+    let mut above = region.top;
+    if at(above) < region.iso_level {
+        return region.top;
+    }
+
+    while above > region.bottom {
+        let below = (above - region.step).max(region.bottom);
+        if below >= above {
+            break;
+        }
+
+        if at(below) < region.iso_level {
+            return refine_crossing(&at, region.iso_level, above, below);
+        }
+
+        above = below;
+    }
+
+    region.bottom
+}
+
+/// Bisects between a height in open air and one in solid ground.
+fn refine_crossing(at: &impl Fn(f32) -> f32, iso_level: f32, mut air: f32, mut solid: f32) -> f32 {
+    for _ in 0..REFINEMENT_STEPS {
+        let middle = (air + solid) / 2.0;
+        if at(middle) < iso_level {
+            solid = middle;
+        } else {
+            air = middle;
+        }
+    }
+
+    (air + solid) / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn region(resolution: UVec2) -> HeightmapRegion {
+        HeightmapRegion {
+            min: Vec2::ZERO,
+            resolution,
+            texel_size: 0.5,
+            top: 10.0,
+            bottom: -10.0,
+            step: 0.25,
+            iso_level: 0.0,
+        }
+    }
+
+    #[test]
+    fn finds_the_surface() {
+        let plane = |position: Vec3| position.y - 2.0;
+        let heightmap = TerrainHeightmap::sample(&plane, region(UVec2::new(3, 2))).unwrap();
+
+        for height in &heightmap.heights {
+            assert!((height - 2.0).abs() < 0.01, "{}", height);
+        }
+    }
+
+    #[test]
+    fn edge_normals_match_interior_ones() {
+        // A plane rising one unit per unit along x.
+        let ramp = |position: Vec3| position.y - position.x;
+        let heightmap = TerrainHeightmap::sample(&ramp, region(UVec2::new(4, 3))).unwrap();
+        let expected = Vec3::new(-1.0, 1.0, 0.0).normalize();
+
+        for v in 0..3 {
+            for u in 0..4 {
+                let normal = heightmap.normal(u, v);
+                assert!(
+                    normal.abs_diff_eq(expected, 0.01),
+                    "{} at {} {}",
+                    normal,
+                    u,
+                    v
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_regions_the_rays_cannot_cross() {
+        let plane = |position: Vec3| position.y;
+
+        let mut stuck = region(UVec2::ONE);
+        stuck.step = 0.0;
+        assert!(matches!(
+            TerrainHeightmap::sample(&plane, stuck),
+            Err(HeightmapRegionError::NonPositiveStep(_))
+        ));
+
+        let mut inverted = region(UVec2::ONE);
+        inverted.bottom = inverted.top;
+        assert!(matches!(
+            TerrainHeightmap::sample(&plane, inverted),
+            Err(HeightmapRegionError::EmptyRange { .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_non_finite_regions() {
+        let plane = |position: Vec3| position.y;
+
+        for (field, value) in [
+            ("top", f32::INFINITY),
+            ("bottom", f32::NEG_INFINITY),
+            ("step", f32::INFINITY),
+            ("step", f32::NAN),
+        ] {
+            let mut unbounded = region(UVec2::ONE);
+            match field {
+                "top" => unbounded.top = value,
+                "bottom" => unbounded.bottom = value,
+                _ => unbounded.step = value,
+            }
+
+            assert!(
+                matches!(
+                    TerrainHeightmap::sample(&plane, unbounded),
+                    Err(HeightmapRegionError::NotFinite { field: rejected, .. }) if rejected == field
+                ),
+                "{} = {}",
+                field,
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn stops_when_steps_no_longer_lower_the_ray() {
+        // At this height a step of 0.25 rounds away, so the ray can never descend.
+        let plane = |position: Vec3| position.y - 2.0;
+        let mut far = region(UVec2::ONE);
+        far.top = 1.0e9;
+        far.bottom = 0.0;
+
+        let heightmap = TerrainHeightmap::sample(&plane, far).unwrap();
+
+        assert_eq!(heightmap.heights, vec![0.0]);
+    }
+}
@@ -0,0 +1,216 @@
+//! Legacy VTK writers, readable by ParaView and VisIt.
+//!
+//! The density grid is written as `STRUCTURED_POINTS` and the extracted surface as `POLYDATA`,
+//! both in the ASCII flavour of the format so the files can be diffed and inspected by hand.
+
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use crate::{cpu::Triangle, grid::DensityGrid};
+
+/// A named per-vertex scalar attached to an exported surface.
+pub struct PointScalars<'a> {
+    pub name: &'a str,
+    pub values: &'a [f32],
+}
+
+pub fn write_structured_points(writer: &mut impl Write, grid: &DensityGrid) -> io::Result<()> {
+    let dimensions = grid.dimensions;
+
+    write_header(writer, "marching cubes density grid")?;
+    writeln!(writer, "DATASET STRUCTURED_POINTS")?;
+    writeln!(
+        writer,
+        "DIMENSIONS {} {} {}",
+        dimensions.x, dimensions.y, dimensions.z
+    )?;
+    writeln!(
+        writer,
+        "ORIGIN {} {} {}",
+        grid.origin.x, grid.origin.y, grid.origin.z
+    )?;
+    writeln!(
+        writer,
+        "SPACING {} {} {}",
+        grid.cell_size, grid.cell_size, grid.cell_size
+    )?;
+
+    // VTK expects x to vary fastest, which is also how `DensityGrid` is laid out.
+    writeln!(writer, "POINT_DATA {}", grid.values.len())?;
+    write_scalars(writer, "density", &grid.values)
+}
+
+/// Writes `triangles` as polygon data.
+///
+/// Vertices are not welded, so every triangle contributes three points and each entry of
+/// `scalars` must hold exactly `triangles.len() * 3` values.
+pub fn write_poly_data(
+    writer: &mut impl Write,
+    triangles: &[Triangle],
+    scalars: &[PointScalars],
+) -> io::Result<()> {
+    let point_count = triangles.len() * 3;
+
+    for scalar in scalars {
+        if scalar.values.len() != point_count {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "scalar `{}` has {} values but the surface has {} points",
+                    scalar.name,
+                    scalar.values.len(),
+                    point_count
+                ),
+            ));
+        }
+    }
+
+    write_header(writer, "marching cubes surface")?;
+    writeln!(writer, "DATASET POLYDATA")?;
+
+    writeln!(writer, "POINTS {} float", point_count)?;
+    for triangle in triangles {
+        for vertex in [triangle.vertex_1, triangle.vertex_2, triangle.vertex_3] {
+            writeln!(writer, "{} {} {}", vertex.x, vertex.y, vertex.z)?;
+        }
+    }
+
+    writeln!(writer, "POLYGONS {} {}", triangles.len(), triangles.len() * 4)?;
+    for index in 0..triangles.len() {
+        let first = index * 3;
+        writeln!(writer, "3 {} {} {}", first, first + 1, first + 2)?;
+    }
+
+    if !scalars.is_empty() {
+        writeln!(writer, "POINT_DATA {}", point_count)?;
+        for scalar in scalars {
+            write_scalars(writer, scalar.name, scalar.values)?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn save_structured_points(path: impl AsRef<Path>, grid: &DensityGrid) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_structured_points(&mut writer, grid)?;
+    writer.flush()
+}
+
+pub fn save_poly_data(
+    path: impl AsRef<Path>,
+    triangles: &[Triangle],
+    scalars: &[PointScalars],
+) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_poly_data(&mut writer, triangles, scalars)?;
+    writer.flush()
+}
+
+fn write_header(writer: &mut impl Write, title: &str) -> io::Result<()> {
+    writeln!(writer, "# vtk DataFile Version 3.0")?;
+    writeln!(writer, "{}", title)?;
+    writeln!(writer, "ASCII")
+}
+
+fn write_scalars(writer: &mut impl Write, name: &str, values: &[f32]) -> io::Result<()> {
+    // Array names are whitespace delimited in the legacy format.
+    let name = name.replace(char::is_whitespace, "_");
+
+    writeln!(writer, "SCALARS {} float 1", name)?;
+    writeln!(writer, "LOOKUP_TABLE default")?;
+    for value in values {
+        writeln!(writer, "{}", value)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::{UVec3, Vec3};
+
+    use super::*;
+
+    fn written(write: impl FnOnce(&mut Vec<u8>) -> io::Result<()>) -> String {
+        let mut bytes = Vec::new();
+        write(&mut bytes).unwrap();
+        String::from_utf8(bytes).unwrap()
+    }
+
+    #[test]
+    fn structured_points_are_x_fastest() {
+        let grid = DensityGrid::from_fn(UVec3::new(2, 2, 1), Vec3::new(1.0, 2.0, 3.0), 0.5, |p| {
+            p.x + p.y * 10.0
+        });
+
+        assert_eq!(
+            written(|writer| write_structured_points(writer, &grid)),
+            "# vtk DataFile Version 3.0\n\
+             marching cubes density grid\n\
+             ASCII\n\
+             DATASET STRUCTURED_POINTS\n\
+             DIMENSIONS 2 2 1\n\
+             ORIGIN 1 2 3\n\
+             SPACING 0.5 0.5 0.5\n\
+             POINT_DATA 4\n\
+             SCALARS density float 1\n\
+             LOOKUP_TABLE default\n\
+             21\n\
+             21.5\n\
+             26\n\
+             26.5\n"
+        );
+    }
+
+    #[test]
+    fn poly_data_does_not_weld_vertices() {
+        let triangle = || Triangle {
+            vertex_1: Vec3::ZERO,
+            vertex_2: Vec3::X,
+            vertex_3: Vec3::Y,
+        };
+        let values = [0.0, 1.0, 2.0, 3.0, 4.0, 5.0];
+        let scalars = [PointScalars {
+            name: "signed distance",
+            values: &values,
+        }];
+
+        assert_eq!(
+            written(|writer| write_poly_data(writer, &[triangle(), triangle()], &scalars)),
+            "# vtk DataFile Version 3.0\n\
+             marching cubes surface\n\
+             ASCII\n\
+             DATASET POLYDATA\n\
+             POINTS 6 float\n\
+             0 0 0\n1 0 0\n0 1 0\n\
+             0 0 0\n1 0 0\n0 1 0\n\
+             POLYGONS 2 8\n\
+             3 0 1 2\n\
+             3 3 4 5\n\
+             POINT_DATA 6\n\
+             SCALARS signed_distance float 1\n\
+             LOOKUP_TABLE default\n\
+             0\n1\n2\n3\n4\n5\n"
+        );
+    }
+
+    #[test]
+    fn poly_data_rejects_scalars_of_the_wrong_length() {
+        let triangle = Triangle {
+            vertex_1: Vec3::ZERO,
+            vertex_2: Vec3::X,
+            vertex_3: Vec3::Y,
+        };
+        let scalars = [PointScalars {
+            name: "density",
+            values: &[0.0, 1.0],
+        }];
+
+        let error = write_poly_data(&mut Vec::new(), &[triangle], &scalars).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+}
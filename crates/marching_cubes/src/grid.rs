@@ -0,0 +1,148 @@
+use bevy::prelude::{UVec3, Vec3};
+
+use crate::density::DensitySource;
+
+/// A regular grid of density samples.
+///
+/// `dimensions` counts sample points, not cells, so a grid of `n` cells along an axis holds
+/// `n + 1` samples along that axis. Samples are stored x-fastest, then y, then z.
+///
+/// Every sample also carries a material id, `0` unless set. Only the materials of solid samples
+/// end up on the mesh.
+#[derive(Debug, Clone)]
+pub struct DensityGrid {
+    pub dimensions: UVec3,
+    pub origin: Vec3,
+    pub cell_size: f32,
+    pub values: Vec<f32>,
+    pub materials: Vec<u8>,
+}
+
+impl DensityGrid {
+    pub fn new(dimensions: UVec3, origin: Vec3, cell_size: f32) -> Self {
+        let len = (dimensions.x * dimensions.y * dimensions.z) as usize;
+        Self {
+            dimensions,
+            origin,
+            cell_size,
+            values: vec![0.0; len],
+            materials: vec![0; len],
+        }
+    }
+
+    /// Samples `density` at every grid point.
+    pub fn from_fn(
+        dimensions: UVec3,
+        origin: Vec3,
+        cell_size: f32,
+        density: impl Fn(Vec3) -> f32,
+    ) -> Self {
+        Self::from_source(dimensions, origin, cell_size, &density)
+    }
+
+    /// Samples `source` at every grid point.
+    pub fn from_source(
+        dimensions: UVec3,
+        origin: Vec3,
+        cell_size: f32,
+        source: &impl DensitySource,
+    ) -> Self {
+        let mut grid = Self::new(dimensions, origin, cell_size);
+
+        for z in 0..dimensions.z {
+            for y in 0..dimensions.y {
+                for x in 0..dimensions.x {
+                    let index = grid.index(x, y, z);
+                    grid.values[index] = source.density(grid.position(x, y, z));
+                }
+            }
+        }
+
+        grid
+    }
+
+    /// Number of cells along each axis.
+    pub fn cell_count(&self) -> UVec3 {
+        self.dimensions.max(UVec3::ONE) - UVec3::ONE
+    }
+
+    pub fn index(&self, x: u32, y: u32, z: u32) -> usize {
+        (x + y * self.dimensions.x + z * self.dimensions.x * self.dimensions.y) as usize
+    }
+
+    pub fn position(&self, x: u32, y: u32, z: u32) -> Vec3 {
+        self.origin + Vec3::new(x as f32, y as f32, z as f32) * self.cell_size
+    }
+
+    pub fn get(&self, x: u32, y: u32, z: u32) -> f32 {
+        self.values[self.index(x, y, z)]
+    }
+
+    pub fn set(&mut self, x: u32, y: u32, z: u32, value: f32) {
+        let index = self.index(x, y, z);
+        self.values[index] = value;
+    }
+
+    pub fn material(&self, x: u32, y: u32, z: u32) -> u8 {
+        self.materials[self.index(x, y, z)]
+    }
+
+    pub fn set_material(&mut self, x: u32, y: u32, z: u32, material: u8) {
+        let index = self.index(x, y, z);
+        self.materials[index] = material;
+    }
+
+    /// The eight corners of the cell whose minimum corner is the sample at `(x, y, z)`, in the
+    /// order expected by [`MarchingCubes::polygonize`](crate::cpu::MarchingCubes::polygonize).
+    pub fn cell(&self, x: u32, y: u32, z: u32) -> [(Vec3, f32); 8] {
+        let corner = |x, y, z| (self.position(x, y, z), self.get(x, y, z));
+
+        [
+            corner(x, y, z),
+            corner(x + 1, y, z),
+            corner(x + 1, y, z + 1),
+            corner(x, y, z + 1),
+            corner(x, y + 1, z),
+            corner(x + 1, y + 1, z),
+            corner(x + 1, y + 1, z + 1),
+            corner(x, y + 1, z + 1),
+        ]
+    }
+
+    /// Material ids of the corners of [`cell`](Self::cell), in the same order.
+    pub fn cell_materials(&self, x: u32, y: u32, z: u32) -> [u8; 8] {
+        self.cell_indices(x, y, z)
+            .map(|index| self.materials[index])
+    }
+
+    /// Sample indices of the corners of [`cell`](Self::cell), in the same order. Useful for
+    /// looking up other per-sample data laid out like `values`.
+    pub fn cell_indices(&self, x: u32, y: u32, z: u32) -> [usize; 8] {
+        [
+            self.index(x, y, z),
+            self.index(x + 1, y, z),
+            self.index(x + 1, y, z + 1),
+            self.index(x, y, z + 1),
+            self.index(x, y + 1, z),
+            self.index(x + 1, y + 1, z),
+            self.index(x + 1, y + 1, z + 1),
+            self.index(x, y + 1, z + 1),
+        ]
+    }
+}
+
+/// Trilinear interpolation between samples. Positions outside the grid clamp to its edge.
+impl DensitySource for DensityGrid {
+    fn density(&self, position: Vec3) -> f32 {
+        let last = self.dimensions.max(UVec3::ONE) - UVec3::ONE;
+        let local = ((position - self.origin) / self.cell_size).clamp(Vec3::ZERO, last.as_vec3());
+        let min = local.floor().as_uvec3().min(last);
+        let max = (min + UVec3::ONE).min(last);
+        let t = local - min.as_vec3();
+
+        let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+        let row = |y, z| lerp(self.get(min.x, y, z), self.get(max.x, y, z), t.x);
+        let plane = |z| lerp(row(min.y, z), row(max.y, z), t.y);
+        lerp(plane(min.z), plane(max.z), t.z)
+    }
+}
@@ -1,7 +1,19 @@
 use bevy::{prelude::Plugin, render::RenderApp};
 
+pub mod ambient_occlusion;
+pub mod colormap;
 pub mod cpu;
+pub mod density;
+pub mod diagnostics;
+pub mod export;
+pub mod grid;
 pub mod lookup_tables;
+pub mod mesh;
+pub mod packing;
+pub mod storage;
+pub mod uv;
+
+pub use density::{DensitySource, HeightmapDensity, HeightmapError};
 
 pub struct MarchingCubesPlugin;
 
@@ -0,0 +1,312 @@
+//! Conversion of polygonized triangles into bevy meshes.
+
+use bevy::{
+    prelude::{Mesh, Vec3},
+    render::{
+        mesh::{Indices, MeshVertexAttribute, VertexAttributeValues},
+        render_resource::{PrimitiveTopology, VertexFormat},
+    },
+};
+
+use crate::{
+    cpu::{FieldSurface, MaterialTriangle, Triangle},
+    packing::{pack_normal_material, pack_position},
+};
+
+// A "high" random id should be used for custom attributes to ensure consistent sorting and avoid collisions with other attributes.
+// See the MeshVertexAttribute docs for more info.
+/// Chunk-local position packed with [`pack_position`].
+pub const ATTRIBUTE_PACKED_POSITION: MeshVertexAttribute =
+    MeshVertexAttribute::new("PackedPosition", 988541521, VertexFormat::Unorm16x4);
+
+// A "high" random id should be used for custom attributes to ensure consistent sorting and avoid collisions with other attributes.
+// See the MeshVertexAttribute docs for more info.
+/// Normal and material id packed with [`pack_normal_material`].
+pub const ATTRIBUTE_VOXEL_DATA: MeshVertexAttribute =
+    MeshVertexAttribute::new("VoxelData", 988541319, VertexFormat::Uint32);
+
+// A "high" random id should be used for custom attributes to ensure consistent sorting and avoid collisions with other attributes.
+// See the MeshVertexAttribute docs for more info.
+/// Blend weights of up to four terrain materials, summing to one.
+pub const ATTRIBUTE_MATERIAL_WEIGHTS: MeshVertexAttribute =
+    MeshVertexAttribute::new("MaterialWeights", 988541723, VertexFormat::Float32x4);
+
+// A "high" random id should be used for custom attributes to ensure consistent sorting and avoid collisions with other attributes.
+// See the MeshVertexAttribute docs for more info.
+/// Up to [`MAX_MESH_FIELDS`] secondary scalar fields, one per channel.
+pub const ATTRIBUTE_SCALAR_FIELDS: MeshVertexAttribute =
+    MeshVertexAttribute::new("ScalarFields", 988541947, VertexFormat::Float32x4);
+
+/// Number of fields [`build_field_mesh`] can store in [`ATTRIBUTE_SCALAR_FIELDS`].
+pub const MAX_MESH_FIELDS: usize = 4;
+
+// A "high" random id should be used for custom attributes to ensure consistent sorting and avoid collisions with other attributes.
+// See the MeshVertexAttribute docs for more info.
+/// Baked ambient occlusion, `1.0` where the surface is fully open. See
+/// [`AmbientOcclusion`](crate::ambient_occlusion::AmbientOcclusion).
+pub const ATTRIBUTE_AMBIENT_OCCLUSION: MeshVertexAttribute =
+    MeshVertexAttribute::new("AmbientOcclusion", 988542113, VertexFormat::Float32);
+
+// A "high" random id should be used for custom attributes to ensure consistent sorting and avoid collisions with other attributes.
+// See the MeshVertexAttribute docs for more info.
+/// Barycentric coordinates of each vertex within its triangle, for drawing edges in a shader.
+pub const ATTRIBUTE_BARYCENTRIC: MeshVertexAttribute =
+    MeshVertexAttribute::new("Barycentric", 988542307, VertexFormat::Float32x3);
+
+// A "high" random id should be used for custom attributes to ensure consistent sorting and avoid collisions with other attributes.
+// See the MeshVertexAttribute docs for more info.
+/// Shape quality of the vertex's triangle, `1.0` when equilateral down to `0.0` when degenerate.
+pub const ATTRIBUTE_TRIANGLE_QUALITY: MeshVertexAttribute =
+    MeshVertexAttribute::new("TriangleQuality", 988542521, VertexFormat::Float32);
+
+// A "high" random id should be used for custom attributes to ensure consistent sorting and avoid collisions with other attributes.
+// See the MeshVertexAttribute docs for more info.
+/// Mean curvature of the isosurface at the vertex, positive where it bulges towards air.
+pub const ATTRIBUTE_MEAN_CURVATURE: MeshVertexAttribute =
+    MeshVertexAttribute::new("MeanCurvature", 988542733, VertexFormat::Float32);
+
+/// Builds a flat-shaded mesh with `f32` positions and normals.
+pub fn build_mesh(triangles: &[Triangle]) -> Mesh {
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+
+    let vertices = triangles
+        .iter()
+        .flat_map(|triangle| [triangle.vertex_1, triangle.vertex_2, triangle.vertex_3])
+        .map(|vector| [vector.x, vector.y, vector.z])
+        .collect::<Vec<_>>();
+
+    let normals = triangles
+        .iter()
+        .flat_map(|triangle| [triangle_normal(triangle).into(); 3])
+        .collect::<Vec<[f32; 3]>>();
+
+    mesh.set_indices(Some(sequential_indices(vertices.len())));
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, vertices);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+
+    mesh
+}
+
+/// Builds a flat-shaded mesh with one-hot [`ATTRIBUTE_MATERIAL_WEIGHTS`] selecting each vertex's
+/// material. The weights blend within triangles whose vertices have different materials.
+///
+/// Material ids past the last weight channel share it.
+pub fn build_material_mesh(triangles: &[MaterialTriangle]) -> Mesh {
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+
+    let vertices = triangles
+        .iter()
+        .flat_map(|triangle| triangle.vertices)
+        .map(|vertex| <[f32; 3]>::from(vertex.position))
+        .collect::<Vec<_>>();
+
+    let normals = triangles
+        .iter()
+        .flat_map(|triangle| [material_triangle_normal(triangle).into(); 3])
+        .collect::<Vec<[f32; 3]>>();
+
+    let weights = triangles
+        .iter()
+        .flat_map(|triangle| triangle.vertices)
+        .map(|vertex| {
+            let mut weights = [0.0; 4];
+            weights[(vertex.material as usize).min(3)] = 1.0;
+            weights
+        })
+        .collect::<Vec<_>>();
+
+    mesh.set_indices(Some(sequential_indices(vertices.len())));
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, vertices);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.insert_attribute(ATTRIBUTE_MATERIAL_WEIGHTS, weights);
+
+    mesh
+}
+
+/// Builds a flat-shaded mesh with the surface's fields in [`ATTRIBUTE_SCALAR_FIELDS`]. Fields
+/// past [`MAX_MESH_FIELDS`] are dropped and missing channels are zero.
+pub fn build_field_mesh(surface: &FieldSurface) -> Mesh {
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+
+    let vertices = surface
+        .positions
+        .iter()
+        .map(|&position| <[f32; 3]>::from(position))
+        .collect::<Vec<_>>();
+
+    let normals = surface
+        .positions
+        .chunks_exact(3)
+        .flat_map(|triangle| {
+            let normal = (triangle[1] - triangle[0])
+                .cross(triangle[2] - triangle[0])
+                .normalize();
+            [normal.into(); 3]
+        })
+        .collect::<Vec<[f32; 3]>>();
+
+    let mut fields = vec![[0.0; MAX_MESH_FIELDS]; vertices.len()];
+    for (channel, values) in surface.fields.iter().take(MAX_MESH_FIELDS).enumerate() {
+        for (vertex, value) in fields.iter_mut().zip(values) {
+            vertex[channel] = *value;
+        }
+    }
+
+    mesh.set_indices(Some(sequential_indices(vertices.len())));
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, vertices);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.insert_attribute(ATTRIBUTE_SCALAR_FIELDS, fields);
+
+    mesh
+}
+
+/// Builds a flat-shaded mesh in the packed vertex format, for the custom terrain pipeline.
+///
+/// Positions are stored relative to the cube at `origin` with sides of `extent`, so the mesh
+/// entity should be placed at `origin` and the pipeline's position scale set to `extent`.
+pub fn build_packed_mesh(triangles: &[Triangle], origin: Vec3, extent: f32) -> Mesh {
+    let positions = triangles
+        .iter()
+        .flat_map(|triangle| [triangle.vertex_1, triangle.vertex_2, triangle.vertex_3])
+        .map(|vertex| pack_position(vertex, origin, extent))
+        .collect::<Vec<_>>();
+
+    let voxel_data = triangles
+        .iter()
+        .flat_map(|triangle| [pack_normal_material(triangle_normal(triangle), 0); 3])
+        .collect::<Vec<_>>();
+
+    packed_mesh(positions, voxel_data)
+}
+
+/// Like [`build_packed_mesh`], keeping each vertex's material id.
+pub fn build_packed_material_mesh(
+    triangles: &[MaterialTriangle],
+    origin: Vec3,
+    extent: f32,
+) -> Mesh {
+    let positions = triangles
+        .iter()
+        .flat_map(|triangle| triangle.vertices)
+        .map(|vertex| pack_position(vertex.position, origin, extent))
+        .collect::<Vec<_>>();
+
+    let voxel_data = triangles
+        .iter()
+        .flat_map(|triangle| {
+            let normal = material_triangle_normal(triangle);
+            triangle
+                .vertices
+                .map(|vertex| pack_normal_material(normal, vertex.material))
+        })
+        .collect::<Vec<_>>();
+
+    packed_mesh(positions, voxel_data)
+}
+
+fn packed_mesh(positions: Vec<[u16; 4]>, voxel_data: Vec<u32>) -> Mesh {
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+
+    mesh.set_indices(Some(sequential_indices(positions.len())));
+    mesh.insert_attribute(
+        ATTRIBUTE_PACKED_POSITION,
+        VertexAttributeValues::Unorm16x4(positions),
+    );
+    mesh.insert_attribute(ATTRIBUTE_VOXEL_DATA, voxel_data);
+
+    mesh
+}
+
+fn triangle_normal(triangle: &Triangle) -> Vec3 {
+    (triangle.vertex_2 - triangle.vertex_1)
+        .cross(triangle.vertex_3 - triangle.vertex_1)
+        .normalize()
+}
+
+fn material_triangle_normal(triangle: &MaterialTriangle) -> Vec3 {
+    let [a, b, c] = triangle.vertices.map(|vertex| vertex.position);
+    (b - a).cross(c - a).normalize()
+}
+
+fn sequential_indices(count: usize) -> Indices {
+    Indices::U32((0..count as u32).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::UVec3;
+
+    use super::*;
+    use crate::{cpu::MarchingCubes, grid::DensityGrid};
+
+    /// A sphere that crosses every cell of a 3×3×3 grid.
+    fn sphere() -> DensityGrid {
+        let mut grid = DensityGrid::from_fn(UVec3::splat(4), Vec3::ZERO, 1.0, |position| {
+            position.distance(Vec3::splat(1.5)) - 1.2
+        });
+        for (index, material) in grid.materials.iter_mut().enumerate() {
+            *material = (index % 5) as u8;
+        }
+        grid
+    }
+
+    fn assert_attribute_lengths(mesh: &Mesh, attributes: &[MeshVertexAttribute], vertices: usize) {
+        assert_eq!(mesh.indices().unwrap().len(), vertices);
+        for attribute in attributes {
+            assert_eq!(
+                mesh.attribute(attribute.clone()).unwrap().len(),
+                vertices,
+                "{}",
+                attribute.name
+            );
+        }
+    }
+
+    #[test]
+    fn material_meshes_have_an_index_and_attributes_per_vertex() {
+        let triangles = MarchingCubes::new(0.0).polygonize_grid_materials(&sphere());
+        assert!(!triangles.is_empty());
+
+        let mesh = build_material_mesh(&triangles);
+
+        assert_attribute_lengths(
+            &mesh,
+            &[
+                Mesh::ATTRIBUTE_POSITION,
+                Mesh::ATTRIBUTE_NORMAL,
+                ATTRIBUTE_MATERIAL_WEIGHTS,
+            ],
+            triangles.len() * 3,
+        );
+    }
+
+    #[test]
+    fn field_meshes_have_an_index_and_attributes_per_vertex() {
+        let grid = sphere();
+        let fields: Vec<Vec<f32>> = (0..MAX_MESH_FIELDS + 1)
+            .map(|field| vec![field as f32; grid.values.len()])
+            .collect();
+        let fields: Vec<&[f32]> = fields.iter().map(Vec::as_slice).collect();
+        let surface = MarchingCubes::new(0.0).polygonize_grid_fields(&grid, &fields);
+        assert!(!surface.positions.is_empty());
+
+        let mesh = build_field_mesh(&surface);
+
+        assert_attribute_lengths(
+            &mesh,
+            &[
+                Mesh::ATTRIBUTE_POSITION,
+                Mesh::ATTRIBUTE_NORMAL,
+                ATTRIBUTE_SCALAR_FIELDS,
+            ],
+            surface.positions.len(),
+        );
+        let Some(VertexAttributeValues::Float32x4(values)) =
+            mesh.attribute(ATTRIBUTE_SCALAR_FIELDS)
+        else {
+            panic!("scalar fields are not Float32x4");
+        };
+        // The field past the last channel is dropped.
+        assert!(values.iter().all(|&value| value == [0.0, 1.0, 2.0, 3.0]));
+    }
+}
@@ -0,0 +1,155 @@
+//! Compact vertex encodings for terrain meshes.
+//!
+//! A packed vertex is 12 bytes instead of the 24 taken by `f32` positions and normals:
+//!
+//! - the chunk-local position as three normalized `u16`s (plus one padding `u16`, since vertex
+//!   formats only come in pairs and quads), and
+//! - a `u32` holding the octahedral-encoded normal in the low 24 bits (12 bits per component) and
+//!   the material id in the high 8 bits.
+//!
+//! `custom_render_pipeline.wgsl` mirrors the decoding.
+
+use bevy::prelude::{Vec2, Vec3};
+
+const OCTAHEDRAL_BITS: u32 = 12;
+const OCTAHEDRAL_MAX: u32 = (1 << OCTAHEDRAL_BITS) - 1;
+
+/// Quantizes `position` relative to a cube at `origin` with sides of `extent`. Positions outside
+/// the cube are clamped to its faces.
+pub fn pack_position(position: Vec3, origin: Vec3, extent: f32) -> [u16; 4] {
+    let normalized = ((position - origin) / extent).clamp(Vec3::ZERO, Vec3::ONE);
+    let quantize = |value: f32| (value * u16::MAX as f32).round() as u16;
+
+    [
+        quantize(normalized.x),
+        quantize(normalized.y),
+        quantize(normalized.z),
+        0,
+    ]
+}
+
+pub fn unpack_position(packed: [u16; 4], origin: Vec3, extent: f32) -> Vec3 {
+    let normalized =
+        Vec3::new(packed[0] as f32, packed[1] as f32, packed[2] as f32) / u16::MAX as f32;
+    origin + normalized * extent
+}
+
+pub fn pack_normal_material(normal: Vec3, material: u8) -> u32 {
+    let encoded = encode_octahedral(normal);
+    let quantize =
+        |value: f32| ((value * 0.5 + 0.5).clamp(0.0, 1.0) * OCTAHEDRAL_MAX as f32).round() as u32;
+
+    quantize(encoded.x) | (quantize(encoded.y) << OCTAHEDRAL_BITS) | ((material as u32) << 24)
+}
+
+pub fn unpack_normal_material(packed: u32) -> (Vec3, u8) {
+    let dequantize =
+        |value: u32| (value & OCTAHEDRAL_MAX) as f32 / OCTAHEDRAL_MAX as f32 * 2.0 - 1.0;
+    let encoded = Vec2::new(dequantize(packed), dequantize(packed >> OCTAHEDRAL_BITS));
+
+    (decode_octahedral(encoded), (packed >> 24) as u8)
+}
+
+/// Maps a unit vector onto the `[-1, 1]²` square by projecting it onto an octahedron and folding
+/// the lower half over the upper one.
+pub fn encode_octahedral(normal: Vec3) -> Vec2 {
+    let normal = normal / (normal.x.abs() + normal.y.abs() + normal.z.abs());
+    let projected = Vec2::new(normal.x, normal.y);
+
+    if normal.z >= 0.0 {
+        projected
+    } else {
+        (Vec2::ONE - Vec2::new(projected.y, projected.x).abs()) * sign_not_zero(projected)
+    }
+}
+
+pub fn decode_octahedral(encoded: Vec2) -> Vec3 {
+    let mut normal = Vec3::new(
+        encoded.x,
+        encoded.y,
+        1.0 - encoded.x.abs() - encoded.y.abs(),
+    );
+    let fold = (-normal.z).max(0.0);
+    normal.x += if normal.x >= 0.0 { -fold } else { fold };
+    normal.y += if normal.y >= 0.0 { -fold } else { fold };
+
+    normal.normalize()
+}
+
+fn sign_not_zero(value: Vec2) -> Vec2 {
+    Vec2::new(
+        if value.x >= 0.0 { 1.0 } else { -1.0 },
+        if value.y >= 0.0 { 1.0 } else { -1.0 },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Unit vectors spread over the whole sphere, including the axes and the octahedron's folds.
+    fn directions() -> impl Iterator<Item = Vec3> {
+        let steps = 48;
+        (0..=steps).flat_map(move |i| {
+            (0..steps * 2).map(move |j| {
+                let polar = std::f32::consts::PI * i as f32 / steps as f32;
+                let azimuth = std::f32::consts::PI * j as f32 / steps as f32;
+                Vec3::new(
+                    polar.sin() * azimuth.cos(),
+                    polar.sin() * azimuth.sin(),
+                    polar.cos(),
+                )
+            })
+        })
+    }
+
+    #[test]
+    fn octahedral_encoding_round_trips() {
+        for direction in directions() {
+            let encoded = encode_octahedral(direction);
+            assert!(encoded.abs().max_element() <= 1.0);
+            let decoded = decode_octahedral(encoded);
+            assert!(
+                decoded.abs_diff_eq(direction, 1e-5),
+                "{} != {}",
+                decoded,
+                direction
+            );
+        }
+    }
+
+    #[test]
+    fn packed_normals_stay_within_a_tenth_of_a_degree() {
+        for direction in directions() {
+            let (normal, material) = unpack_normal_material(pack_normal_material(direction, 201));
+            assert_eq!(material, 201);
+            let error = normal.angle_between(direction).to_degrees();
+            assert!(error < 0.1, "{} is {}° from {}", normal, error, direction);
+        }
+    }
+
+    #[test]
+    fn packed_positions_stay_within_half_a_step() {
+        let origin = Vec3::new(-8.0, 2.0, 100.0);
+        let extent = 64.0;
+        let tolerance = extent / u16::MAX as f32 * 0.5 + 1e-5;
+
+        for i in 0..=100 {
+            let t = i as f32 / 100.0;
+            let position = origin + Vec3::new(t, 1.0 - t, (t * 7.0).fract()) * extent;
+            let unpacked = unpack_position(pack_position(position, origin, extent), origin, extent);
+            assert!(
+                (unpacked - position).abs().max_element() <= tolerance,
+                "{} != {}",
+                unpacked,
+                position
+            );
+        }
+    }
+
+    #[test]
+    fn positions_outside_the_cube_are_clamped() {
+        let packed = pack_position(Vec3::new(-1.0, 2.0, 0.5), Vec3::ZERO, 1.0);
+        assert_eq!(packed, [0, u16::MAX, u16::MAX / 2 + 1, 0]);
+    }
+}
@@ -0,0 +1,321 @@
+//! Chunk payload encoding: 16-bit quantization followed by run-length encoding.
+//!
+//! A payload is a header, the density runs, then the material runs.
+
+use std::io::{self, Read, Write};
+
+use bevy::prelude::{UVec3, Vec3};
+
+use super::region::FORMAT_VERSION;
+use crate::grid::DensityGrid;
+
+/// Far more samples than any chunk holds. A header claiming more is corrupt, and is rejected
+/// before it can trigger a huge allocation.
+const MAX_SAMPLES: u32 = 1 << 24;
+
+/// The density range preserved when a chunk is quantized.
+///
+/// Values outside the range are clamped before encoding. Anything far enough from the iso level
+/// meshes the same regardless of its exact value, so a band around the iso level keeps the surface
+/// intact while turning solid ground and open air into long runs.
+#[derive(Debug, Clone, Copy)]
+pub struct Quantization {
+    pub min: f32,
+    pub max: f32,
+}
+
+impl Quantization {
+    fn quantize(&self, value: f32) -> u16 {
+        let normalized = (value - self.min) / (self.max - self.min);
+        (normalized.clamp(0.0, 1.0) * u16::MAX as f32).round() as u16
+    }
+
+    fn dequantize(&self, value: u16) -> f32 {
+        self.min + value as f32 / u16::MAX as f32 * (self.max - self.min)
+    }
+}
+
+pub fn encode_chunk(
+    writer: &mut impl Write,
+    grid: &DensityGrid,
+    quantization: Quantization,
+) -> io::Result<()> {
+    for component in <[u32; 3]>::from(grid.dimensions) {
+        writer.write_all(&component.to_le_bytes())?;
+    }
+    for component in <[f32; 3]>::from(grid.origin) {
+        writer.write_all(&component.to_le_bytes())?;
+    }
+    writer.write_all(&grid.cell_size.to_le_bytes())?;
+    writer.write_all(&quantization.min.to_le_bytes())?;
+    writer.write_all(&quantization.max.to_le_bytes())?;
+
+    write_runs(
+        writer,
+        grid.values
+            .iter()
+            .map(|&value| quantization.quantize(value)),
+        |writer, value| writer.write_all(&value.to_le_bytes()),
+    )?;
+    write_runs(
+        writer,
+        grid.materials.iter().copied(),
+        |writer, material| writer.write_all(&[material]),
+    )
+}
+
+/// Decodes a chunk written with format `version`, upgrading it to the current layout.
+pub fn decode_chunk(reader: &mut impl Read, version: u16) -> io::Result<DensityGrid> {
+    match version {
+        // Version 1 predates materials, so its chunks load with every material set to 0.
+        1 => decode_densities(reader),
+        FORMAT_VERSION => decode_current(reader),
+        version => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unsupported chunk format version {}", version),
+        )),
+    }
+}
+
+fn decode_current(reader: &mut impl Read) -> io::Result<DensityGrid> {
+    let mut grid = decode_densities(reader)?;
+    read_runs(reader, &mut grid.materials, |reader| {
+        let mut material = [0];
+        reader.read_exact(&mut material)?;
+        Ok(material[0])
+    })?;
+
+    Ok(grid)
+}
+
+/// Reads the header and density runs, which are laid out the same in every version.
+fn decode_densities(reader: &mut impl Read) -> io::Result<DensityGrid> {
+    let dimensions = UVec3::new(read_u32(reader)?, read_u32(reader)?, read_u32(reader)?);
+    let samples = dimensions
+        .x
+        .checked_mul(dimensions.y)
+        .and_then(|samples| samples.checked_mul(dimensions.z))
+        .filter(|&samples| samples <= MAX_SAMPLES);
+    if samples.is_none() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("chunk dimensions {} are too large", dimensions),
+        ));
+    }
+    let origin = Vec3::new(read_f32(reader)?, read_f32(reader)?, read_f32(reader)?);
+    let cell_size = read_f32(reader)?;
+    let quantization = Quantization {
+        min: read_f32(reader)?,
+        max: read_f32(reader)?,
+    };
+
+    let mut grid = DensityGrid::new(dimensions, origin, cell_size);
+    read_runs(reader, &mut grid.values, |reader| {
+        Ok(quantization.dequantize(read_u16(reader)?))
+    })?;
+
+    Ok(grid)
+}
+
+fn write_runs<W: Write, T: PartialEq>(
+    writer: &mut W,
+    mut values: impl Iterator<Item = T>,
+    write_value: impl Fn(&mut W, T) -> io::Result<()>,
+) -> io::Result<()> {
+    let Some(mut current) = values.next() else {
+        return Ok(());
+    };
+    let mut run: u32 = 1;
+
+    for value in values {
+        if value == current {
+            run += 1;
+        } else {
+            write_varint(writer, run)?;
+            write_value(writer, current)?;
+            current = value;
+            run = 1;
+        }
+    }
+
+    write_varint(writer, run)?;
+    write_value(writer, current)
+}
+
+/// Fills `values` from runs written by [`write_runs`].
+fn read_runs<R: Read, T: Copy>(
+    reader: &mut R,
+    values: &mut [T],
+    read_value: impl Fn(&mut R) -> io::Result<T>,
+) -> io::Result<()> {
+    let mut index = 0;
+
+    while index < values.len() {
+        let run = read_varint(reader)? as usize;
+        let value = read_value(reader)?;
+
+        if run == 0 || index + run > values.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "chunk run overflows its grid",
+            ));
+        }
+
+        values[index..index + run].fill(value);
+        index += run;
+    }
+
+    Ok(())
+}
+
+/// LEB128, so the common short runs take a single byte.
+fn write_varint(writer: &mut impl Write, mut value: u32) -> io::Result<()> {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+
+        if value == 0 {
+            return writer.write_all(&[byte]);
+        }
+
+        writer.write_all(&[byte | 0x80])?;
+    }
+}
+
+fn read_varint(reader: &mut impl Read) -> io::Result<u32> {
+    let mut value = 0;
+
+    for shift in (0..32).step_by(7) {
+        let mut byte = [0];
+        reader.read_exact(&mut byte)?;
+        value |= ((byte[0] & 0x7f) as u32) << shift;
+
+        if byte[0] & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+
+    Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        "run length is too long",
+    ))
+}
+
+pub(crate) fn read_u16(reader: &mut impl Read) -> io::Result<u16> {
+    let mut bytes = [0; 2];
+    reader.read_exact(&mut bytes)?;
+    Ok(u16::from_le_bytes(bytes))
+}
+
+pub(crate) fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_f32(reader: &mut impl Read) -> io::Result<f32> {
+    Ok(f32::from_bits(read_u32(reader)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QUANTIZATION: Quantization = Quantization {
+        min: -2.0,
+        max: 2.0,
+    };
+
+    fn grid() -> DensityGrid {
+        let mut grid =
+            DensityGrid::from_fn(UVec3::new(3, 4, 5), Vec3::new(1.0, -2.0, 3.0), 0.5, |p| {
+                (p.x - p.y * 0.5 + p.z * 0.25).sin()
+            });
+        grid.set_material(1, 2, 3, 7);
+        grid.set_material(2, 3, 4, 200);
+        grid
+    }
+
+    fn encoded(grid: &DensityGrid) -> Vec<u8> {
+        let mut payload = Vec::new();
+        encode_chunk(&mut payload, grid, QUANTIZATION).unwrap();
+        payload
+    }
+
+    #[test]
+    fn round_trips_within_quantization_error() {
+        let grid = grid();
+        let decoded = decode_chunk(&mut encoded(&grid).as_slice(), FORMAT_VERSION).unwrap();
+
+        assert_eq!(decoded.dimensions, grid.dimensions);
+        assert_eq!(decoded.origin, grid.origin);
+        assert_eq!(decoded.cell_size, grid.cell_size);
+        assert_eq!(decoded.materials, grid.materials);
+        let tolerance = (QUANTIZATION.max - QUANTIZATION.min) / u16::MAX as f32;
+        for (decoded, value) in decoded.values.iter().zip(&grid.values) {
+            assert!(
+                (decoded - value).abs() <= tolerance,
+                "{} != {}",
+                decoded,
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn upgrades_version_1_without_materials() {
+        let grid = grid();
+        let mut materials = Vec::new();
+        write_runs(
+            &mut materials,
+            grid.materials.iter().copied(),
+            |writer, material| writer.write_all(&[material]),
+        )
+        .unwrap();
+        // Version 1 payloads end with the density runs.
+        let mut payload = encoded(&grid);
+        payload.truncate(payload.len() - materials.len());
+
+        let decoded = decode_chunk(&mut payload.as_slice(), 1).unwrap();
+        assert_eq!(decoded.dimensions, grid.dimensions);
+        assert!(decoded.materials.iter().all(|&material| material == 0));
+        assert_eq!(
+            decoded.values,
+            decode_chunk(&mut encoded(&grid).as_slice(), FORMAT_VERSION)
+                .unwrap()
+                .values
+        );
+    }
+
+    #[test]
+    fn rejects_corrupt_payloads() {
+        let invalid = |payload: &[u8], version| {
+            let error = decode_chunk(&mut &payload[..], version).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData, "{}", error);
+        };
+
+        let mut overflowing = encoded(&grid());
+        overflowing[..12].copy_from_slice(&[0xff; 12]);
+        invalid(&overflowing, FORMAT_VERSION);
+
+        let mut huge = encoded(&grid());
+        huge[..4].copy_from_slice(&MAX_SAMPLES.to_le_bytes());
+        invalid(&huge, FORMAT_VERSION);
+
+        let mut long_run = encoded(&DensityGrid::new(UVec3::ONE, Vec3::ZERO, 1.0));
+        // The single density run, right after the 36 byte header, claims two samples.
+        long_run[36] = 2;
+        invalid(&long_run, FORMAT_VERSION);
+
+        invalid(&encoded(&grid()), FORMAT_VERSION + 1);
+    }
+
+    #[test]
+    fn varints_round_trip() {
+        for value in [0, 1, 127, 128, 300, u32::MAX] {
+            let mut bytes = Vec::new();
+            write_varint(&mut bytes, value).unwrap();
+            assert_eq!(read_varint(&mut bytes.as_slice()).unwrap(), value);
+        }
+    }
+}
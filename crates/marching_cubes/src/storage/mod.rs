@@ -0,0 +1,259 @@
+//! Persistent storage for density chunks.
+//!
+//! Chunks are addressed by integer chunk coordinates and grouped into region files named
+//! `r.<x>.<y>.<z>.region` inside the save directory.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    io::{self, Cursor},
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::IVec3;
+
+use crate::grid::DensityGrid;
+
+mod codec;
+mod region;
+
+pub use codec::Quantization;
+pub use region::{FORMAT_VERSION, REGION_SIZE};
+
+use codec::{decode_chunk, encode_chunk};
+use region::{region_of, slot_of, write_region, RegionReader};
+
+pub struct WorldSave {
+    root: PathBuf,
+    pub quantization: Quantization,
+}
+
+impl WorldSave {
+    /// Opens the save in `root`, creating the directory if it does not exist yet.
+    pub fn open(root: impl Into<PathBuf>, quantization: Quantization) -> io::Result<Self> {
+        let root = root.into();
+        fs::create_dir_all(&root)?;
+
+        Ok(Self { root, quantization })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Writes `chunks`, rewriting each affected region file once.
+    pub fn save_chunks<'a>(
+        &self,
+        chunks: impl IntoIterator<Item = (IVec3, &'a DensityGrid)>,
+    ) -> io::Result<()> {
+        let mut regions: HashMap<IVec3, Vec<(IVec3, &DensityGrid)>> = HashMap::new();
+        for (coord, grid) in chunks {
+            regions
+                .entry(region_of(coord))
+                .or_default()
+                .push((coord, grid));
+        }
+
+        for (region, chunks) in regions {
+            let path = self.region_path(region);
+            let mut payloads = self.read_payloads(&path)?;
+
+            for (coord, grid) in chunks {
+                let mut payload = Vec::new();
+                encode_chunk(&mut payload, grid, self.quantization)?;
+                payloads.insert(slot_of(coord), payload);
+            }
+
+            write_region(&path, &payloads)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn load_chunk(&self, coord: IVec3) -> io::Result<Option<DensityGrid>> {
+        let path = self.region_path(region_of(coord));
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let mut region = RegionReader::open(&path)?;
+        let version = region.version();
+
+        region
+            .read_slot(slot_of(coord))?
+            .map(|payload| decode_chunk(&mut Cursor::new(payload), version))
+            .transpose()
+    }
+
+    /// Loads every saved chunk within `radius` chunks of `center` on each axis. Region files
+    /// outside that box are never opened, and only the requested payloads are read from the ones
+    /// that are.
+    pub fn load_chunks_near(
+        &self,
+        center: IVec3,
+        radius: i32,
+    ) -> io::Result<Vec<(IVec3, DensityGrid)>> {
+        let mut regions: HashMap<IVec3, Vec<IVec3>> = HashMap::new();
+        for z in -radius..=radius {
+            for y in -radius..=radius {
+                for x in -radius..=radius {
+                    let coord = center + IVec3::new(x, y, z);
+                    regions.entry(region_of(coord)).or_default().push(coord);
+                }
+            }
+        }
+
+        let mut chunks = Vec::new();
+        for (region, coords) in regions {
+            let path = self.region_path(region);
+            if !path.exists() {
+                continue;
+            }
+
+            let mut reader = RegionReader::open(&path)?;
+            let version = reader.version();
+
+            for coord in coords {
+                if let Some(payload) = reader.read_slot(slot_of(coord))? {
+                    chunks.push((coord, decode_chunk(&mut Cursor::new(payload), version)?));
+                }
+            }
+        }
+
+        Ok(chunks)
+    }
+
+    fn region_path(&self, region: IVec3) -> PathBuf {
+        self.root
+            .join(format!("r.{}.{}.{}.region", region.x, region.y, region.z))
+    }
+
+    /// Reads every payload of an existing region, re-encoding them if the file predates the
+    /// current format.
+    fn read_payloads(&self, path: &Path) -> io::Result<BTreeMap<usize, Vec<u8>>> {
+        let mut payloads = BTreeMap::new();
+        if !path.exists() {
+            return Ok(payloads);
+        }
+
+        let mut region = RegionReader::open(path)?;
+        let version = region.version();
+        let slots: Vec<usize> = region.occupied_slots().collect();
+
+        for slot in slots {
+            let Some(mut payload) = region.read_slot(slot)? else {
+                continue;
+            };
+
+            if version != FORMAT_VERSION {
+                let grid = decode_chunk(&mut Cursor::new(payload), version)?;
+                payload = Vec::new();
+                encode_chunk(&mut payload, &grid, self.quantization)?;
+            }
+
+            payloads.insert(slot, payload);
+        }
+
+        Ok(payloads)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::{UVec3, Vec3};
+
+    use super::*;
+
+    const QUANTIZATION: Quantization = Quantization {
+        min: -1.0,
+        max: 1.0,
+    };
+
+    /// A fresh save in the system's temporary directory.
+    fn save(name: &str) -> WorldSave {
+        let root =
+            std::env::temp_dir().join(format!("marching_cubes_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        WorldSave::open(root, QUANTIZATION).unwrap()
+    }
+
+    /// A chunk whose densities are exactly representable after quantization.
+    fn chunk(value: f32, material: u8) -> DensityGrid {
+        let mut grid = DensityGrid::new(UVec3::splat(3), Vec3::ZERO, 1.0);
+        grid.values.fill(value);
+        grid.materials.fill(material);
+        grid
+    }
+
+    #[test]
+    fn round_trips_chunks_across_regions() {
+        let save = save("round_trip");
+        let near = IVec3::new(-1, 0, 0);
+        let far = IVec3::new(REGION_SIZE * 3, 0, 0);
+        save.save_chunks([(near, &chunk(-1.0, 3)), (far, &chunk(1.0, 4))])
+            .unwrap();
+
+        let loaded = save.load_chunk(near).unwrap().unwrap();
+        assert_eq!(loaded.values, chunk(-1.0, 3).values);
+        assert_eq!(loaded.materials, chunk(-1.0, 3).materials);
+        assert!(save.load_chunk(IVec3::ZERO).unwrap().is_none());
+
+        let nearby = save.load_chunks_near(IVec3::ZERO, 1).unwrap();
+        assert_eq!(nearby.len(), 1);
+        assert_eq!(nearby[0].0, near);
+
+        fs::remove_dir_all(save.root()).unwrap();
+    }
+
+    #[test]
+    fn upgrades_version_1_regions() {
+        let save = save("upgrade");
+        let old = IVec3::new(1, 2, 3);
+        let new = IVec3::new(2, 2, 3);
+
+        // A version 1 payload is a current one without the trailing material runs: one run of
+        // all 27 samples, then material 0.
+        let mut payload = Vec::new();
+        encode_chunk(&mut payload, &chunk(1.0, 0), QUANTIZATION).unwrap();
+        payload.truncate(payload.len() - 2);
+        let path = save.region_path(region_of(old));
+        write_region(&path, &BTreeMap::from([(slot_of(old), payload)])).unwrap();
+        let mut file = fs::read(&path).unwrap();
+        file[4..6].copy_from_slice(&1u16.to_le_bytes());
+        fs::write(&path, file).unwrap();
+
+        let loaded = save.load_chunk(old).unwrap().unwrap();
+        assert_eq!(loaded.values, chunk(1.0, 0).values);
+        assert!(loaded.materials.iter().all(|&material| material == 0));
+
+        // Saving into the region rewrites the old chunk in the current version.
+        save.save_chunks([(new, &chunk(-1.0, 9))]).unwrap();
+        assert_eq!(RegionReader::open(&path).unwrap().version(), FORMAT_VERSION);
+        assert_eq!(save.load_chunk(old).unwrap().unwrap().values, loaded.values);
+        assert_eq!(
+            save.load_chunk(new).unwrap().unwrap().materials,
+            chunk(-1.0, 9).materials
+        );
+
+        fs::remove_dir_all(save.root()).unwrap();
+    }
+
+    #[test]
+    fn rejects_payloads_past_the_end_of_the_region() {
+        let save = save("truncated");
+        let coord = IVec3::new(1, 2, 3);
+        save.save_chunks([(coord, &chunk(1.0, 0))]).unwrap();
+
+        // The table entry's length follows its offset, after the 6 byte magic and version.
+        let path = save.region_path(region_of(coord));
+        let length = 6 + slot_of(coord) * 8 + 4;
+        let mut file = fs::read(&path).unwrap();
+        file[length..length + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        fs::write(&path, file).unwrap();
+
+        let error = save.load_chunk(coord).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData, "{}", error);
+
+        fs::remove_dir_all(save.root()).unwrap();
+    }
+}
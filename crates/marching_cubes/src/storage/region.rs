@@ -0,0 +1,167 @@
+//! Region files group `REGION_SIZE³` chunks so a world is not spread over thousands of files.
+//!
+//! Layout, little endian throughout:
+//!
+//! ```text
+//! magic          b"RPRG"
+//! version        u16
+//! chunk table    CHUNKS_PER_REGION × (offset: u32, length: u32), length 0 if absent
+//! payloads       encoded chunks, see `codec`
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+use bevy::prelude::IVec3;
+
+use super::codec::{read_u16, read_u32};
+
+pub const REGION_SIZE: i32 = 8;
+pub const CHUNKS_PER_REGION: usize = (REGION_SIZE * REGION_SIZE * REGION_SIZE) as usize;
+
+/// Bumped whenever the region or chunk layout changes. Files with an older version are upgraded
+/// by `codec::decode_chunk` as they are read, and rewritten in the current version on save.
+pub const FORMAT_VERSION: u16 = 2;
+
+const MAGIC: &[u8; 4] = b"RPRG";
+const HEADER_SIZE: u64 = 4 + 2 + CHUNKS_PER_REGION as u64 * 8;
+
+pub fn region_of(chunk: IVec3) -> IVec3 {
+    IVec3::new(
+        chunk.x.div_euclid(REGION_SIZE),
+        chunk.y.div_euclid(REGION_SIZE),
+        chunk.z.div_euclid(REGION_SIZE),
+    )
+}
+
+/// Index of `chunk` within its region's chunk table.
+pub fn slot_of(chunk: IVec3) -> usize {
+    let local = IVec3::new(
+        chunk.x.rem_euclid(REGION_SIZE),
+        chunk.y.rem_euclid(REGION_SIZE),
+        chunk.z.rem_euclid(REGION_SIZE),
+    );
+    (local.x + local.y * REGION_SIZE + local.z * REGION_SIZE * REGION_SIZE) as usize
+}
+
+/// An open region file. Only the header is read up front; payloads are read on demand.
+pub struct RegionReader {
+    reader: BufReader<File>,
+    /// Bounds the payloads, so a corrupt table cannot make `read_slot` allocate more than the
+    /// file holds.
+    file_length: u64,
+    version: u16,
+    table: Vec<(u32, u32)>,
+}
+
+impl RegionReader {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let file_length = file.metadata()?.len();
+        let mut reader = BufReader::new(file);
+
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} is not a region file", path.display()),
+            ));
+        }
+
+        let version = read_u16(&mut reader)?;
+        if version > FORMAT_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "{} has format version {}, newer than the supported {}",
+                    path.display(),
+                    version,
+                    FORMAT_VERSION
+                ),
+            ));
+        }
+
+        let mut table = Vec::with_capacity(CHUNKS_PER_REGION);
+        for _ in 0..CHUNKS_PER_REGION {
+            table.push((read_u32(&mut reader)?, read_u32(&mut reader)?));
+        }
+
+        Ok(Self {
+            reader,
+            file_length,
+            version,
+            table,
+        })
+    }
+
+    pub fn version(&self) -> u16 {
+        self.version
+    }
+
+    /// The raw payload stored in `slot`, if any.
+    pub fn read_slot(&mut self, slot: usize) -> io::Result<Option<Vec<u8>>> {
+        let (offset, length) = self.table[slot];
+        if length == 0 {
+            return Ok(None);
+        }
+        if offset as u64 + length as u64 > self.file_length {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "slot {} claims {} bytes at offset {}, past the end of the {} byte region",
+                    slot, length, offset, self.file_length
+                ),
+            ));
+        }
+
+        let mut payload = vec![0; length as usize];
+        self.reader.seek(SeekFrom::Start(offset as u64))?;
+        self.reader.read_exact(&mut payload)?;
+
+        Ok(Some(payload))
+    }
+
+    pub fn occupied_slots(&self) -> impl Iterator<Item = usize> + '_ {
+        self.table
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, length))| *length > 0)
+            .map(|(slot, _)| slot)
+    }
+}
+
+/// Writes a complete region file from current-version payloads.
+///
+/// The file is written next to `path` and renamed over it, so an interrupted save leaves the
+/// previous region intact.
+pub fn write_region(path: &Path, payloads: &BTreeMap<usize, Vec<u8>>) -> io::Result<()> {
+    let temporary = path.with_extension("tmp");
+    let mut writer = BufWriter::new(File::create(&temporary)?);
+
+    writer.write_all(MAGIC)?;
+    writer.write_all(&FORMAT_VERSION.to_le_bytes())?;
+
+    let mut offset = HEADER_SIZE as u32;
+    for slot in 0..CHUNKS_PER_REGION {
+        let length = payloads
+            .get(&slot)
+            .map_or(0, |payload| payload.len() as u32);
+        writer.write_all(&offset.to_le_bytes())?;
+        writer.write_all(&length.to_le_bytes())?;
+        offset += length;
+    }
+
+    for payload in payloads.values() {
+        writer.write_all(payload)?;
+    }
+
+    writer.flush()?;
+    drop(writer);
+
+    fs::rename(temporary, path)
+}
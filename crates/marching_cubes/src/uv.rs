@@ -0,0 +1,245 @@
+//! Texture coordinates and MikkTSpace tangents for isosurface meshes, so they can use normal
+//! mapped materials.
+
+use std::{error::Error, fmt};
+
+use bevy::{
+    prelude::{Mesh, Vec2, Vec3},
+    render::mesh::{GenerateTangentsError, Indices, MeshVertexAttribute, VertexAttributeValues},
+};
+
+/// How [`generate_uvs`] lays out texture coordinates.
+#[derive(Debug, Clone, Copy)]
+pub enum UvProjection {
+    /// Projects each vertex along the axis its normal is closest to, with `scale` world units per
+    /// texture repeat. Suits tiling textures.
+    Box { scale: f32 },
+    /// Gives every triangle its own chart in a grid atlas covering `0.0..1.0`, with uniform texel
+    /// density and `padding` UV units between charts. Suits baked, non-repeating textures. Each
+    /// triangle must own its vertices, as in the meshes built by [`crate::mesh`].
+    Chart { padding: f32 },
+}
+
+#[derive(Debug)]
+pub enum UvError {
+    MissingAttribute(&'static str),
+    MissingIndices,
+    /// [`UvProjection::Chart`] was asked to lay out a mesh whose triangles share vertices.
+    SharedVertices,
+    Tangents(GenerateTangentsError),
+}
+
+impl fmt::Display for UvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UvError::MissingAttribute(name) => {
+                write!(f, "mesh has no `f32` {} attribute", name)
+            }
+            UvError::MissingIndices => write!(f, "mesh has no indices"),
+            UvError::SharedVertices => {
+                write!(f, "chart layout needs every triangle to own its vertices")
+            }
+            UvError::Tangents(error) => write!(f, "failed to generate tangents: {}", error),
+        }
+    }
+}
+
+impl Error for UvError {}
+
+impl From<GenerateTangentsError> for UvError {
+    fn from(error: GenerateTangentsError) -> Self {
+        UvError::Tangents(error)
+    }
+}
+
+/// Inserts [`Mesh::ATTRIBUTE_UV_0`] into an indexed mesh with `f32` positions and normals.
+pub fn generate_uvs(mesh: &mut Mesh, projection: UvProjection) -> Result<(), UvError> {
+    let positions = float3_attribute(mesh, Mesh::ATTRIBUTE_POSITION)?;
+    let indices = mesh
+        .indices()
+        .map(Indices::iter)
+        .ok_or(UvError::MissingIndices)?
+        .collect::<Vec<_>>();
+
+    let uvs = match projection {
+        UvProjection::Box { scale } => {
+            let normals = float3_attribute(mesh, Mesh::ATTRIBUTE_NORMAL)?;
+            box_uvs(&positions, &normals, scale)
+        }
+        UvProjection::Chart { padding } => chart_uvs(&positions, &indices, padding)?,
+    };
+
+    mesh.insert_attribute(
+        Mesh::ATTRIBUTE_UV_0,
+        uvs.into_iter().map(<[f32; 2]>::from).collect::<Vec<_>>(),
+    );
+    Ok(())
+}
+
+/// Generates UVs as [`generate_uvs`] does, then MikkTSpace tangents into
+/// [`Mesh::ATTRIBUTE_TANGENT`].
+pub fn generate_uvs_and_tangents(mesh: &mut Mesh, projection: UvProjection) -> Result<(), UvError> {
+    generate_uvs(mesh, projection)?;
+    mesh.generate_tangents()?;
+    Ok(())
+}
+
+fn float3_attribute(mesh: &Mesh, attribute: MeshVertexAttribute) -> Result<Vec<Vec3>, UvError> {
+    match mesh.attribute(attribute.id) {
+        Some(VertexAttributeValues::Float32x3(values)) => {
+            Ok(values.iter().map(|&value| Vec3::from(value)).collect())
+        }
+        _ => Err(UvError::MissingAttribute(attribute.name)),
+    }
+}
+
+fn box_uvs(positions: &[Vec3], normals: &[Vec3], scale: f32) -> Vec<Vec2> {
+    positions
+        .iter()
+        .zip(normals)
+        .map(|(&position, &normal)| {
+            let p = position / scale;
+            let n = normal.abs();
+            // Flip by the normal's sign so opposite faces are not mirrored.
+            if n.x >= n.y && n.x >= n.z {
+                Vec2::new(-p.z * normal.x.signum(), -p.y)
+            } else if n.y >= n.z {
+                Vec2::new(p.x, p.z * normal.y.signum())
+            } else {
+                Vec2::new(p.x * normal.z.signum(), -p.y)
+            }
+        })
+        .collect()
+}
+
+fn chart_uvs(positions: &[Vec3], indices: &[usize], padding: f32) -> Result<Vec<Vec2>, UvError> {
+    let mut owned = vec![false; positions.len()];
+    for &index in indices {
+        if std::mem::replace(&mut owned[index], true) {
+            return Err(UvError::SharedVertices);
+        }
+    }
+
+    // Flatten each triangle into its own plane, first edge along +U.
+    let charts = indices
+        .chunks_exact(3)
+        .map(|triangle| {
+            let [a, b, c] = [0, 1, 2].map(|i| positions[triangle[i]]);
+            let u = (b - a).normalize_or_zero();
+            let v = (b - a).cross(c - a).cross(u).normalize_or_zero();
+            let flat = [a, b, c].map(|p| Vec2::new((p - a).dot(u), (p - a).dot(v)));
+            let min = flat[0].min(flat[1]).min(flat[2]);
+            flat.map(|p| p - min)
+        })
+        .collect::<Vec<_>>();
+
+    let columns = ((charts.len() as f32).sqrt().ceil() as usize).max(1);
+    let cell = 1.0 / columns as f32;
+    let largest = charts
+        .iter()
+        .flatten()
+        .fold(0.0_f32, |largest, p| largest.max(p.max_element()));
+    let scale = if largest > 0.0 {
+        (cell - padding * 2.0).max(0.0) / largest
+    } else {
+        0.0
+    };
+
+    let mut uvs = vec![Vec2::ZERO; positions.len()];
+    for (i, (triangle, chart)) in indices.chunks_exact(3).zip(charts).enumerate() {
+        let corner =
+            Vec2::new((i % columns) as f32, (i / columns) as f32) * cell + Vec2::splat(padding);
+        for (&index, p) in triangle.iter().zip(chart) {
+            uvs[index] = corner + p * scale;
+        }
+    }
+
+    Ok(uvs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Unwelded triangles of different sizes and orientations.
+    fn triangles() -> (Vec<Vec3>, Vec<usize>) {
+        let positions = vec![
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec3::new(5.0, 2.0, 1.0),
+            Vec3::new(5.0, 2.0, 3.0),
+            Vec3::new(6.0, 4.0, 3.0),
+            Vec3::new(-1.0, 0.0, 0.0),
+            Vec3::new(-1.0, 0.5, 0.5),
+            Vec3::new(-1.0, 0.0, 0.5),
+        ];
+        (positions, (0..9).collect())
+    }
+
+    #[test]
+    fn charts_fit_their_own_grid_cells() {
+        let (positions, indices) = triangles();
+        let padding = 0.01;
+        let uvs = chart_uvs(&positions, &indices, padding).unwrap();
+
+        // Three charts take a 2×2 grid of cells half a unit wide.
+        for (i, triangle) in indices.chunks_exact(3).enumerate() {
+            let min = Vec2::new((i % 2) as f32, (i / 2) as f32) * 0.5 + padding;
+            let max = min + 0.5 - padding * 2.0;
+            for &index in triangle {
+                let uv = uvs[index];
+                assert!(
+                    uv.cmpge(min - 1e-6).all() && uv.cmple(max + 1e-6).all(),
+                    "{} outside {}..{}",
+                    uv,
+                    min,
+                    max
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn charts_keep_shapes_at_one_texel_density() {
+        let (positions, indices) = triangles();
+        let uvs = chart_uvs(&positions, &indices, 0.0).unwrap();
+
+        let mut density = None;
+        for triangle in indices.chunks_exact(3) {
+            for (a, b) in [(0, 1), (1, 2), (2, 0)] {
+                let world = positions[triangle[a]].distance(positions[triangle[b]]);
+                let uv = uvs[triangle[a]].distance(uvs[triangle[b]]);
+                let ratio = uv / world;
+                let expected = *density.get_or_insert(ratio);
+                assert!((ratio - expected).abs() < 1e-4, "{} != {}", ratio, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn charts_need_unshared_vertices() {
+        let (positions, mut indices) = triangles();
+        indices[3] = 0;
+
+        assert!(matches!(
+            chart_uvs(&positions, &indices, 0.0),
+            Err(UvError::SharedVertices)
+        ));
+    }
+
+    #[test]
+    fn box_projection_follows_the_dominant_axis() {
+        let positions = [Vec3::new(2.0, 4.0, 6.0); 3];
+        let normals = [Vec3::Y, Vec3::NEG_X, Vec3::new(0.1, 0.2, 0.9)];
+
+        assert_eq!(
+            box_uvs(&positions, &normals, 2.0),
+            [
+                Vec2::new(1.0, 3.0),
+                Vec2::new(3.0, -2.0),
+                Vec2::new(1.0, -2.0)
+            ]
+        );
+    }
+}
@@ -0,0 +1,36 @@
+//! Runs Lenia or Gray-Scott reaction-diffusion on the GPU, picked by preset name:
+//!
+//! `cargo run --example continuous -- coral`
+//!
+//! The presets are `orbium`, `hydrogeminium`, `mitosis`, `coral` and `maze`. The controls are the
+//! same as the `game_of_life` example's.
+
+use bevy::prelude::*;
+use debug_ui::DebugUIPlugin;
+use rusty_polygons::game_of_life::{
+    continuous_setup, ContinuousAutomaton, ContinuousComputePlugin, GrayScott, Lenia,
+};
+
+fn main() {
+    let preset = std::env::args().nth(1);
+    let automaton = match preset.as_deref().unwrap_or("orbium") {
+        "orbium" => ContinuousAutomaton::Lenia(Lenia::orbium()),
+        "hydrogeminium" => ContinuousAutomaton::Lenia(Lenia::hydrogeminium()),
+        "mitosis" => ContinuousAutomaton::GrayScott(GrayScott::mitosis()),
+        "coral" => ContinuousAutomaton::GrayScott(GrayScott::coral()),
+        "maze" => ContinuousAutomaton::GrayScott(GrayScott::maze()),
+        preset => {
+            eprintln!("unknown preset `{}`", preset);
+            std::process::exit(2);
+        }
+    };
+
+    App::new()
+        .insert_resource(ClearColor(Color::BLACK))
+        .add_plugins(DefaultPlugins)
+        .add_plugin(DebugUIPlugin)
+        .add_plugin(ContinuousComputePlugin)
+        .insert_resource(automaton)
+        .add_startup_system(continuous_setup)
+        .run();
+}
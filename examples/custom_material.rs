@@ -0,0 +1,43 @@
+//! Blends each vertex's `BlendColor` and `NewColor` with [`CustomMaterial`], on a cube that
+//! animates the blend and on meshes showing the multiply and overlay modes.
+
+use bevy::prelude::*;
+use rusty_polygons::custom_material::{
+    custom_material_setup, insert_blend_colors, BlendMode, CustomMaterial,
+};
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugin(MaterialPlugin::<CustomMaterial>::default())
+        .add_startup_system(custom_material_setup)
+        .add_startup_system(spawn_blend_modes)
+        .run();
+}
+
+fn spawn_blend_modes(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<CustomMaterial>>,
+) {
+    let shapes = [
+        (
+            Mesh::from(shape::UVSphere::default()),
+            BlendMode::Multiply,
+            -1.5,
+        ),
+        (Mesh::from(shape::Torus::default()), BlendMode::Overlay, 1.5),
+    ];
+    for (mut mesh, blend_mode, x) in shapes {
+        insert_blend_colors(&mut mesh, Color::ORANGE, Color::GRAY);
+        commands.spawn(MaterialMeshBundle {
+            mesh: meshes.add(mesh),
+            transform: Transform::from_xyz(x, 0.5, 0.0),
+            material: materials.add(CustomMaterial {
+                blend_mode,
+                ..default()
+            }),
+            ..default()
+        });
+    }
+}
@@ -0,0 +1,35 @@
+//! Runs a cellular automaton on the GPU, from the shader's random initialization or a Life pattern
+//! under `assets/`:
+//!
+//! `cargo run --example game_of_life -- patterns/gosper_glider_gun.rle`
+//!
+//! Space pauses, `.` single-steps, `=` and `-` change the speed, `R` reseeds and `C` clears. The
+//! left mouse button paints cells, the right one erases them, and `[` and `]` resize the brush.
+
+use bevy::prelude::*;
+use debug_ui::DebugUIPlugin;
+use rusty_polygons::game_of_life::{
+    game_of_life_setup, GameOfLifeComputePlugin, GameOfLifeStamp, StampMode,
+};
+
+fn main() {
+    let mut app = App::new();
+    app.insert_resource(ClearColor(Color::BLACK))
+        .add_plugins(DefaultPlugins.set(AssetPlugin {
+            watch_for_changes: true,
+            ..default()
+        }))
+        .add_plugin(DebugUIPlugin)
+        .add_plugin(GameOfLifeComputePlugin)
+        .add_startup_system(game_of_life_setup);
+    if let Some(path) = std::env::args().nth(1) {
+        app.add_startup_system(move |mut commands: Commands, assets: Res<AssetServer>| {
+            commands.insert_resource(GameOfLifeStamp {
+                pattern: assets.load(path.as_str()),
+                offset: IVec2::new(100, 100),
+                mode: StampMode::Replace,
+            });
+        });
+    }
+    app.run();
+}
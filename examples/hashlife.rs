@@ -0,0 +1,53 @@
+//! Runs a Life pattern on an unbounded Hashlife universe, a power of two generations per frame:
+//!
+//! `cargo run --example hashlife -- assets/patterns/gosper_glider_gun.rle 10`
+//!
+//! The pattern defaults to the Gosper glider gun, and the step to `2^0` generations.
+
+use std::path::Path;
+
+use bevy::prelude::*;
+use rusty_polygons::game_of_life::{
+    game_of_life_setup, GameOfLifeGrid, Hashlife, HashlifePlugin, HashlifeView, LifePattern,
+    PatternFormat,
+};
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let path = args
+        .next()
+        .unwrap_or_else(|| "assets/patterns/gosper_glider_gun.rle".to_string());
+    let log2_step = args.next().map_or(0, |step| {
+        step.parse()
+            .unwrap_or_else(|_| exit(format!("`{}` is not a power of two exponent", step)))
+    });
+    let hashlife =
+        load(Path::new(&path)).unwrap_or_else(|error| exit(format!("{}: {}", path, error)));
+
+    App::new()
+        .insert_resource(ClearColor(Color::BLACK))
+        .add_plugins(DefaultPlugins)
+        .add_plugin(HashlifePlugin)
+        .init_resource::<GameOfLifeGrid>()
+        .insert_resource(HashlifeView {
+            log2_step,
+            ..default()
+        })
+        .insert_resource(hashlife)
+        .add_startup_system(game_of_life_setup)
+        .run();
+}
+
+fn load(path: &Path) -> Result<Hashlife, Box<dyn std::error::Error>> {
+    let format = path
+        .extension()
+        .and_then(|extension| PatternFormat::from_extension(&extension.to_string_lossy()))
+        .ok_or("unknown format")?;
+    let pattern = LifePattern::parse(&std::fs::read_to_string(path)?, format)?;
+    Ok(Hashlife::from_pattern(&pattern, (0, 0))?)
+}
+
+fn exit(message: String) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(2);
+}
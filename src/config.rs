@@ -0,0 +1,177 @@
+//! World generation settings loaded from `assets/config/default.world.ron`.
+//!
+//! The file is watched for changes, and saving it regenerates the terrain with the new values.
+
+use std::{error::Error, fmt};
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+    utils::BoxedFuture,
+};
+use bevy_flycam::MovementSettings;
+use serde::Deserialize;
+
+const CONFIG_PATH: &str = "config/default.world.ron";
+
+pub struct WorldConfigPlugin;
+
+impl Plugin for WorldConfigPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<WorldConfig>()
+            .init_asset_loader::<WorldConfigLoader>()
+            .add_startup_system(load_world_config)
+            .add_system_to_stage(CoreStage::First, apply_world_config);
+    }
+}
+
+#[derive(Resource, Deserialize, TypeUuid, Debug, Clone)]
+#[uuid = "4b8bb483-9cbb-4442-ae52-a82b2d8faed7"]
+pub struct WorldConfig {
+    pub seed: u32,
+    pub iso_level: f32,
+    pub cell_size: f32,
+    /// Cells along each axis of a chunk.
+    pub chunk_size: u32,
+    /// World units per noise period.
+    pub noise_scale: f64,
+    /// Chunks within this many chunks of the camera on each axis are kept loaded.
+    pub load_radius: i32,
+    /// Each chunk and cell size is saved in its own subdirectory of this one.
+    pub save_directory: String,
+    /// Bakes ambient occlusion into chunk meshes as they load. The bake runs on the main thread
+    /// and is slow for large chunks, so it is off when omitted.
+    #[serde(default)]
+    pub ambient_occlusion: bool,
+    pub movement: MovementConfig,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct MovementConfig {
+    pub sensitivity: f32,
+    pub speed: f32,
+}
+
+#[derive(Debug)]
+pub enum WorldConfigError {
+    NotPositive { field: &'static str, value: f64 },
+    NotFinite { field: &'static str },
+    Negative { field: &'static str, value: f64 },
+}
+
+impl fmt::Display for WorldConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WorldConfigError::NotPositive { field, value } => {
+                write!(f, "`{}` must be greater than zero, got {}", field, value)
+            }
+            WorldConfigError::NotFinite { field } => write!(f, "`{}` must be finite", field),
+            WorldConfigError::Negative { field, value } => {
+                write!(f, "`{}` must not be negative, got {}", field, value)
+            }
+        }
+    }
+}
+
+impl Error for WorldConfigError {}
+
+impl WorldConfig {
+    pub fn validate(&self) -> Result<(), WorldConfigError> {
+        let finite = |field, value: f64| {
+            if value.is_finite() {
+                Ok(())
+            } else {
+                Err(WorldConfigError::NotFinite { field })
+            }
+        };
+        let positive = |field, value: f64| {
+            finite(field, value)?;
+            if value > 0.0 {
+                Ok(())
+            } else {
+                Err(WorldConfigError::NotPositive { field, value })
+            }
+        };
+        let non_negative = |field, value: f64| {
+            finite(field, value)?;
+            if value >= 0.0 {
+                Ok(())
+            } else {
+                Err(WorldConfigError::Negative { field, value })
+            }
+        };
+
+        finite("iso_level", self.iso_level as f64)?;
+        positive("cell_size", self.cell_size as f64)?;
+        positive("chunk_size", self.chunk_size as f64)?;
+        positive("noise_scale", self.noise_scale)?;
+        non_negative("load_radius", self.load_radius as f64)?;
+        non_negative("movement.sensitivity", self.movement.sensitivity as f64)?;
+        non_negative("movement.speed", self.movement.speed as f64)
+    }
+}
+
+#[derive(Default)]
+struct WorldConfigLoader;
+
+impl AssetLoader for WorldConfigLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let path = load_context.path().display().to_string();
+
+            let config: WorldConfig = ron::de::from_bytes(bytes)
+                .map_err(|error| bevy::asset::Error::msg(format!("{}: {}", path, error)))?;
+            config
+                .validate()
+                .map_err(|error| bevy::asset::Error::msg(format!("{}: {}", path, error)))?;
+
+            load_context.set_default_asset(LoadedAsset::new(config));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["world.ron"]
+    }
+}
+
+#[derive(Resource)]
+struct WorldConfigHandle(Handle<WorldConfig>);
+
+fn load_world_config(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(WorldConfigHandle(asset_server.load(CONFIG_PATH)));
+}
+
+/// Copies the config asset into the [`WorldConfig`] resource whenever it is loaded or modified.
+/// A file that fails to parse or validate is never turned into an asset, so the previous config
+/// stays in effect until the file is fixed.
+fn apply_world_config(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<WorldConfig>>,
+    handle: Res<WorldConfigHandle>,
+    configs: Res<Assets<WorldConfig>>,
+    mut movement: ResMut<MovementSettings>,
+) {
+    for event in events.iter() {
+        match event {
+            AssetEvent::Created { handle: changed } | AssetEvent::Modified { handle: changed }
+                if *changed == handle.0 =>
+            {
+                let Some(config) = configs.get(changed) else {
+                    continue;
+                };
+
+                movement.sensitivity = config.movement.sensitivity;
+                movement.speed = config.movement.speed;
+                commands.insert_resource(config.clone());
+                info!("applied world config from {}", CONFIG_PATH);
+            }
+            _ => {}
+        }
+    }
+}
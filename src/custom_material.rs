@@ -4,21 +4,22 @@ use bevy::{
     reflect::TypeUuid,
     render::{
         mesh::{MeshVertexAttribute, MeshVertexBufferLayout},
+        render_asset::RenderAssets,
         render_resource::{
-            AsBindGroup, RenderPipelineDescriptor, ShaderRef, SpecializedMeshPipelineError,
-            VertexFormat,
+            AsBindGroup, AsBindGroupShaderType, RenderPipelineDescriptor, ShaderRef, ShaderType,
+            SpecializedMeshPipelineError, VertexFormat,
         },
     },
 };
 
 // A "high" random id should be used for custom attributes to ensure consistent sorting and avoid collisions with other attributes.
 // See the MeshVertexAttribute docs for more info.
-const ATTRIBUTE_BLEND_COLOR: MeshVertexAttribute =
+pub const ATTRIBUTE_BLEND_COLOR: MeshVertexAttribute =
     MeshVertexAttribute::new("BlendColor", 988540917, VertexFormat::Float32x4);
 
 // A "high" random id should be used for custom attributes to ensure consistent sorting and avoid collisions with other attributes.
 // See the MeshVertexAttribute docs for more info.
-const ATTRIBUTE_NEW_COLOR: MeshVertexAttribute =
+pub const ATTRIBUTE_NEW_COLOR: MeshVertexAttribute =
     MeshVertexAttribute::new("NewColor", 988541117, VertexFormat::Float32x4);
 
 /// set up a simple 3D scene
@@ -28,23 +29,15 @@ pub fn custom_material_setup(
     mut materials: ResMut<Assets<CustomMaterial>>,
 ) {
     let mut mesh = Mesh::from(shape::Cube { size: 1.0 });
-    mesh.insert_attribute(
-        ATTRIBUTE_BLEND_COLOR,
-        // The cube mesh has 24 vertices (6 faces, 4 vertices per face), so we insert one BlendColor for each
-        vec![[1.0, 0.0, 0.0, 1.0]; 24],
-    );
-    mesh.insert_attribute(
-        ATTRIBUTE_NEW_COLOR,
-        // The cube mesh has 24 vertices (6 faces, 4 vertices per face), so we insert one BlendColor for each
-        vec![[0.0, 1.0, 0.0, 1.0]; 24],
-    );
+    insert_blend_colors(&mut mesh, Color::RED, Color::GREEN);
 
     // cube
     commands.spawn(MaterialMeshBundle {
         mesh: meshes.add(mesh),
         transform: Transform::from_xyz(0.0, 0.5, 0.0),
         material: materials.add(CustomMaterial {
-            color: Color::WHITE,
+            animation_speed: 0.5,
+            ..default()
         }),
         ..default()
     });
@@ -56,12 +49,79 @@ pub fn custom_material_setup(
     });
 }
 
+/// Gives every vertex of `mesh` the same `BlendColor` and `NewColor`, whatever its vertex count.
+pub fn insert_blend_colors(mesh: &mut Mesh, blend_color: Color, new_color: Color) {
+    let count = mesh.count_vertices();
+    mesh.insert_attribute(
+        ATTRIBUTE_BLEND_COLOR,
+        vec![blend_color.as_linear_rgba_f32(); count],
+    );
+    mesh.insert_attribute(
+        ATTRIBUTE_NEW_COLOR,
+        vec![new_color.as_linear_rgba_f32(); count],
+    );
+}
+
+/// How `NewColor` is combined with `BlendColor`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BlendMode {
+    /// Replaces `BlendColor` with `NewColor`.
+    #[default]
+    Lerp,
+    Multiply,
+    /// Multiplies dark `BlendColor` channels and screens light ones, keeping contrast.
+    Overlay,
+}
+
 // This is the struct that will be passed to your shader
+//
+// Each vertex shows its `BlendColor` blended towards the `blend_mode` combination with its
+// `NewColor` by `blend_factor`, tinted by `color`.
 #[derive(AsBindGroup, Debug, Clone, TypeUuid)]
 #[uuid = "f690fdae-d598-45ab-8225-97e2a3f056e0"]
+#[uniform(0, CustomMaterialUniform)]
 pub struct CustomMaterial {
-    #[uniform(0)]
-    color: Color,
+    pub color: Color,
+    /// `0.0` shows `BlendColor` alone, `1.0` the fully blended color.
+    pub blend_factor: f32,
+    pub blend_mode: BlendMode,
+    /// Blend cycles per second, swinging between `0.0` and `blend_factor`. `0.0` holds the
+    /// factor still.
+    pub animation_speed: f32,
+}
+
+impl Default for CustomMaterial {
+    fn default() -> Self {
+        Self {
+            color: Color::WHITE,
+            blend_factor: 1.0,
+            blend_mode: BlendMode::default(),
+            animation_speed: 0.0,
+        }
+    }
+}
+
+#[derive(Clone, Default, ShaderType)]
+pub struct CustomMaterialUniform {
+    pub color: Vec4,
+    pub blend_factor: f32,
+    pub blend_mode: u32,
+    pub animation_speed: f32,
+}
+
+impl AsBindGroupShaderType<CustomMaterialUniform> for CustomMaterial {
+    fn as_bind_group_shader_type(&self, _images: &RenderAssets<Image>) -> CustomMaterialUniform {
+        CustomMaterialUniform {
+            color: self.color.as_linear_rgba_f32().into(),
+            blend_factor: self.blend_factor,
+            blend_mode: match self.blend_mode {
+                BlendMode::Lerp => 0,
+                BlendMode::Multiply => 1,
+                BlendMode::Overlay => 2,
+            },
+            animation_speed: self.animation_speed,
+        }
+    }
 }
 
 impl Material for CustomMaterial {
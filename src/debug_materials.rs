@@ -0,0 +1,390 @@
+//! Diagnostic shading for terrain chunks, for tuning the mesher.
+//!
+//! F6 cycles through the modes, which can also be picked from the `TerrainShading` resource in
+//! the debug UI. Diagnostic attributes are computed the first time a chunk needs them.
+
+use bevy::{
+    pbr::{MaterialPipeline, MaterialPipelineKey},
+    prelude::*,
+    reflect::TypeUuid,
+    render::{
+        mesh::{MeshVertexAttribute, MeshVertexBufferLayout},
+        render_resource::{
+            AsBindGroup, RenderPipelineDescriptor, ShaderRef, SpecializedMeshPipelineError,
+        },
+    },
+};
+use marching_cubes::{
+    diagnostics::{bake_mean_curvature, insert_triangle_diagnostics},
+    mesh::{ATTRIBUTE_BARYCENTRIC, ATTRIBUTE_MEAN_CURVATURE, ATTRIBUTE_TRIANGLE_QUALITY},
+};
+
+use crate::{terrain::TerrainChunk, terrain_material::TerrainMaterial};
+
+const SHADING_KEY: KeyCode = KeyCode::F6;
+const SHADER_PATH: &str = "shaders/debug_materials.wgsl";
+
+pub struct DebugMaterialsPlugin;
+
+impl Plugin for DebugMaterialsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(MaterialPlugin::<WorldNormalMaterial>::default())
+            .add_plugin(MaterialPlugin::<MeanCurvatureMaterial>::default())
+            .add_plugin(MaterialPlugin::<ChunkColorMaterial>::default())
+            .add_plugin(MaterialPlugin::<TriangleQualityMaterial>::default())
+            .add_plugin(MaterialPlugin::<WireframeMaterial>::default())
+            .register_type::<TerrainShading>()
+            .init_resource::<TerrainShading>()
+            .init_resource::<DebugMaterials>()
+            .add_system(cycle_terrain_shading)
+            .add_system(apply_terrain_shading.after(cycle_terrain_shading));
+    }
+}
+
+/// How terrain chunks are drawn.
+#[derive(Resource, Reflect, Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[reflect(Resource)]
+pub enum TerrainShading {
+    #[default]
+    Standard,
+    WorldNormals,
+    MeanCurvature,
+    ChunkColors,
+    TriangleQuality,
+    Wireframe,
+}
+
+impl TerrainShading {
+    fn next(self) -> Self {
+        match self {
+            TerrainShading::Standard => TerrainShading::WorldNormals,
+            TerrainShading::WorldNormals => TerrainShading::MeanCurvature,
+            TerrainShading::MeanCurvature => TerrainShading::ChunkColors,
+            TerrainShading::ChunkColors => TerrainShading::TriangleQuality,
+            TerrainShading::TriangleQuality => TerrainShading::Wireframe,
+            TerrainShading::Wireframe => TerrainShading::Standard,
+        }
+    }
+}
+
+/// Shows world space normals, mapped from `-1..1` to `0..1`.
+#[derive(AsBindGroup, Debug, Clone, TypeUuid)]
+#[uuid = "5c563f2a-3df2-4aec-beec-5e8226f74c3d"]
+pub struct WorldNormalMaterial {}
+
+/// Shows baked mean curvature, blue where concave and red where convex.
+#[derive(AsBindGroup, Debug, Clone, TypeUuid)]
+#[uuid = "8993f07b-6fae-4226-be15-ac7489c71ba7"]
+pub struct MeanCurvatureMaterial {
+    /// Curvature is multiplied by this before saturating at full color.
+    #[uniform(0)]
+    pub scale: f32,
+}
+
+/// Shades a chunk in a flat color, to show chunk boundaries.
+#[derive(AsBindGroup, Debug, Clone, TypeUuid)]
+#[uuid = "b99ecde1-4df1-4d2a-9a2f-62361e6a3c8d"]
+pub struct ChunkColorMaterial {
+    #[uniform(0)]
+    pub color: Color,
+}
+
+impl ChunkColorMaterial {
+    /// A color picked by hashing the chunk coordinate, so neighbours rarely match.
+    pub fn for_chunk(coord: IVec3) -> Self {
+        let hash = (coord.x.wrapping_mul(73_856_093)
+            ^ coord.y.wrapping_mul(19_349_663)
+            ^ coord.z.wrapping_mul(83_492_791)) as u32;
+        Self {
+            color: Color::hsl((hash % 360) as f32, 0.6, 0.55),
+        }
+    }
+}
+
+/// Heatmap of triangle shape quality, green for equilateral triangles and red for slivers.
+#[derive(AsBindGroup, Debug, Clone, TypeUuid)]
+#[uuid = "bf22e657-0d10-45fa-8e3c-83e54a1cde86"]
+pub struct TriangleQualityMaterial {
+    /// Quality at and below which triangles are fully red.
+    #[uniform(0)]
+    pub worst: f32,
+}
+
+/// Draws triangle edges over diffuse shading.
+#[derive(AsBindGroup, Debug, Clone, TypeUuid)]
+#[uuid = "1e905791-6e3e-450f-9846-83dd9eb9fa3a"]
+pub struct WireframeMaterial {
+    #[uniform(0)]
+    pub color: Color,
+    #[uniform(0)]
+    pub wire_color: Color,
+    /// Line width in pixels.
+    #[uniform(0)]
+    pub width: f32,
+}
+
+/// Points `descriptor` at the shared debug shader, with `def` selecting the mode and
+/// `attribute`, if any, bound at location 2.
+fn specialize_debug(
+    descriptor: &mut RenderPipelineDescriptor,
+    layout: &MeshVertexBufferLayout,
+    def: &str,
+    attribute: Option<MeshVertexAttribute>,
+) -> Result<(), SpecializedMeshPipelineError> {
+    let mut attributes = vec![
+        Mesh::ATTRIBUTE_POSITION.at_shader_location(0),
+        Mesh::ATTRIBUTE_NORMAL.at_shader_location(1),
+    ];
+    if let Some(attribute) = attribute {
+        attributes.push(attribute.at_shader_location(2));
+    }
+    descriptor.vertex.buffers = vec![layout.get_layout(&attributes)?];
+
+    descriptor.vertex.shader_defs.push(def.into());
+    if let Some(fragment) = descriptor.fragment.as_mut() {
+        fragment.shader_defs.push(def.into());
+    }
+    Ok(())
+}
+
+impl Material for WorldNormalMaterial {
+    fn vertex_shader() -> ShaderRef {
+        SHADER_PATH.into()
+    }
+    fn fragment_shader() -> ShaderRef {
+        SHADER_PATH.into()
+    }
+
+    fn specialize(
+        _pipeline: &MaterialPipeline<Self>,
+        descriptor: &mut RenderPipelineDescriptor,
+        layout: &MeshVertexBufferLayout,
+        _key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        specialize_debug(descriptor, layout, "DEBUG_WORLD_NORMALS", None)
+    }
+}
+
+impl Material for MeanCurvatureMaterial {
+    fn vertex_shader() -> ShaderRef {
+        SHADER_PATH.into()
+    }
+    fn fragment_shader() -> ShaderRef {
+        SHADER_PATH.into()
+    }
+
+    fn specialize(
+        _pipeline: &MaterialPipeline<Self>,
+        descriptor: &mut RenderPipelineDescriptor,
+        layout: &MeshVertexBufferLayout,
+        _key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        specialize_debug(
+            descriptor,
+            layout,
+            "DEBUG_MEAN_CURVATURE",
+            Some(ATTRIBUTE_MEAN_CURVATURE),
+        )
+    }
+}
+
+impl Material for ChunkColorMaterial {
+    fn vertex_shader() -> ShaderRef {
+        SHADER_PATH.into()
+    }
+    fn fragment_shader() -> ShaderRef {
+        SHADER_PATH.into()
+    }
+
+    fn specialize(
+        _pipeline: &MaterialPipeline<Self>,
+        descriptor: &mut RenderPipelineDescriptor,
+        layout: &MeshVertexBufferLayout,
+        _key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        specialize_debug(descriptor, layout, "DEBUG_CHUNK_COLOR", None)
+    }
+}
+
+impl Material for TriangleQualityMaterial {
+    fn vertex_shader() -> ShaderRef {
+        SHADER_PATH.into()
+    }
+    fn fragment_shader() -> ShaderRef {
+        SHADER_PATH.into()
+    }
+
+    fn specialize(
+        _pipeline: &MaterialPipeline<Self>,
+        descriptor: &mut RenderPipelineDescriptor,
+        layout: &MeshVertexBufferLayout,
+        _key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        specialize_debug(
+            descriptor,
+            layout,
+            "DEBUG_TRIANGLE_QUALITY",
+            Some(ATTRIBUTE_TRIANGLE_QUALITY),
+        )
+    }
+}
+
+impl Material for WireframeMaterial {
+    fn vertex_shader() -> ShaderRef {
+        SHADER_PATH.into()
+    }
+    fn fragment_shader() -> ShaderRef {
+        SHADER_PATH.into()
+    }
+
+    fn specialize(
+        _pipeline: &MaterialPipeline<Self>,
+        descriptor: &mut RenderPipelineDescriptor,
+        layout: &MeshVertexBufferLayout,
+        _key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        specialize_debug(
+            descriptor,
+            layout,
+            "DEBUG_WIREFRAME",
+            Some(ATTRIBUTE_BARYCENTRIC),
+        )
+    }
+}
+
+/// The debug materials shared by every chunk.
+#[derive(Resource)]
+struct DebugMaterials {
+    world_normals: Handle<WorldNormalMaterial>,
+    mean_curvature: Handle<MeanCurvatureMaterial>,
+    triangle_quality: Handle<TriangleQualityMaterial>,
+    wireframe: Handle<WireframeMaterial>,
+}
+
+impl FromWorld for DebugMaterials {
+    fn from_world(world: &mut World) -> Self {
+        Self {
+            world_normals: world
+                .resource_mut::<Assets<WorldNormalMaterial>>()
+                .add(WorldNormalMaterial {}),
+            mean_curvature: world
+                .resource_mut::<Assets<MeanCurvatureMaterial>>()
+                .add(MeanCurvatureMaterial { scale: 4.0 }),
+            triangle_quality: world
+                .resource_mut::<Assets<TriangleQualityMaterial>>()
+                .add(TriangleQualityMaterial { worst: 0.2 }),
+            wireframe: world
+                .resource_mut::<Assets<WireframeMaterial>>()
+                .add(WireframeMaterial {
+                    color: Color::GRAY,
+                    wire_color: Color::BLACK,
+                    width: 1.0,
+                }),
+        }
+    }
+}
+
+/// Holds a chunk's terrain material while a debug material replaces it.
+#[derive(Component)]
+struct StandardShading(Handle<TerrainMaterial>);
+
+/// The chunk's own [`ChunkColorMaterial`], created the first time it is shown and reused after.
+#[derive(Component)]
+struct ChunkColor(Handle<ChunkColorMaterial>);
+
+fn cycle_terrain_shading(keys: Res<Input<KeyCode>>, mut shading: ResMut<TerrainShading>) {
+    if keys.just_pressed(SHADING_KEY) {
+        *shading = shading.next();
+        info!("terrain shading: {:?}", *shading);
+    }
+}
+
+/// Swaps chunk materials whenever the shading mode changes, and gives newly loaded or edited
+/// chunks the current mode. Edits replace the chunk's mesh, so its diagnostics are baked again.
+#[allow(clippy::type_complexity)]
+fn apply_terrain_shading(
+    mut commands: Commands,
+    shading: Res<TerrainShading>,
+    debug_materials: Res<DebugMaterials>,
+    mut chunk_colors: ResMut<Assets<ChunkColorMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    chunks: Query<(
+        Entity,
+        &TerrainChunk,
+        &Handle<Mesh>,
+        Option<&Handle<TerrainMaterial>>,
+        Option<&StandardShading>,
+        Option<&ChunkColor>,
+        ChangeTrackers<TerrainChunk>,
+    )>,
+) {
+    for (entity, chunk, mesh, terrain_material, standard, chunk_color, tracker) in &chunks {
+        if !shading.is_changed() && !tracker.is_changed() {
+            continue;
+        }
+
+        let mut entity = commands.entity(entity);
+        entity
+            .remove::<Handle<WorldNormalMaterial>>()
+            .remove::<Handle<MeanCurvatureMaterial>>()
+            .remove::<Handle<ChunkColorMaterial>>()
+            .remove::<Handle<TriangleQualityMaterial>>()
+            .remove::<Handle<WireframeMaterial>>();
+
+        if *shading == TerrainShading::Standard {
+            if let Some(StandardShading(material)) = standard {
+                entity.insert(material.clone()).remove::<StandardShading>();
+            }
+            continue;
+        }
+
+        if let Some(material) = terrain_material {
+            entity
+                .insert(StandardShading(material.clone()))
+                .remove::<Handle<TerrainMaterial>>();
+        }
+
+        // Only borrow the mesh mutably when it is missing an attribute, as that reuploads it.
+        let Some(chunk_mesh) = meshes.get(mesh) else {
+            continue;
+        };
+        match *shading {
+            TerrainShading::Standard => {}
+            TerrainShading::WorldNormals => {
+                entity.insert(debug_materials.world_normals.clone());
+            }
+            TerrainShading::MeanCurvature => {
+                if chunk_mesh.attribute(ATTRIBUTE_MEAN_CURVATURE).is_none() {
+                    // The chunk's own grid clamps at its faces, so curvature right at chunk
+                    // borders is slightly off.
+                    let mesh = meshes.get_mut(mesh).unwrap();
+                    bake_mean_curvature(chunk.grid(), mesh, chunk.grid().cell_size * 0.5);
+                }
+                entity.insert(debug_materials.mean_curvature.clone());
+            }
+            TerrainShading::ChunkColors => {
+                let material = match chunk_color {
+                    Some(ChunkColor(material)) => material.clone(),
+                    None => {
+                        let material = chunk_colors.add(ChunkColorMaterial::for_chunk(chunk.coord));
+                        entity.insert(ChunkColor(material.clone()));
+                        material
+                    }
+                };
+                entity.insert(material);
+            }
+            TerrainShading::TriangleQuality => {
+                if chunk_mesh.attribute(ATTRIBUTE_TRIANGLE_QUALITY).is_none() {
+                    insert_triangle_diagnostics(meshes.get_mut(mesh).unwrap());
+                }
+                entity.insert(debug_materials.triangle_quality.clone());
+            }
+            TerrainShading::Wireframe => {
+                if chunk_mesh.attribute(ATTRIBUTE_BARYCENTRIC).is_none() {
+                    insert_triangle_diagnostics(meshes.get_mut(mesh).unwrap());
+                }
+                entity.insert(debug_materials.wireframe.clone());
+            }
+        }
+    }
+}
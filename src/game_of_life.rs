@@ -1,4 +1,4 @@
-//! A compute shader that simulates Conway's Game of Life.
+//! A compute shader that simulates Conway's Game of Life, or any other [`CellularAutomaton`].
 //!
 //! Compute shaders use the GPU for computing arbitrary information, that may be independent of what
 //! is rendered to the screen.
@@ -10,54 +10,110 @@ use bevy::{
         render_asset::RenderAssets,
         render_graph::{self, RenderGraph},
         render_resource::*,
-        renderer::{RenderContext, RenderDevice},
+        renderer::{RenderContext, RenderDevice, RenderQueue},
+        texture::{FallbackImage, TextureFormatPixelInfo},
         RenderApp, RenderStage,
     },
 };
 use std::borrow::Cow;
 
-const SIZE: (u32, u32) = (1280, 720);
+mod automaton;
+mod continuous;
+mod controls;
+pub mod cpu;
+mod grid;
+mod hashlife;
+mod painting;
+mod pattern;
+mod rules;
+
+pub use automaton::{
+    CellularAutomaton, NeighbourCounts, Neighbourhood, Transition, MAX_RADIUS, MAX_STATES,
+};
+use automaton::{CellularAutomatonUniform, PaletteUniform};
+pub use continuous::{
+    continuous_setup, ContinuousAutomaton, ContinuousComputePlugin, GrayScott, Lenia,
+    MAX_KERNEL_RADIUS,
+};
+pub use controls::{GameOfLifeControls, SimulationSpeed};
+use grid::GameOfLifeGridUniform;
+pub use grid::{Boundary, GameOfLifeGrid};
+pub use hashlife::{FillsEmptySpace, Hashlife, HashlifePlugin, HashlifeView};
+pub use painting::GameOfLifeBrush;
+use painting::{GameOfLifePaint, PaintStroke};
+pub use pattern::{LifePattern, LifePatternLoader, PatternFormat, PatternParseError};
+pub use rules::{GameOfLifeRules, RuleParseError};
+
 const WORKGROUP_SIZE: u32 = 8;
 
-pub fn game_of_life_setup(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
-    let mut image = Image::new_fill(
-        Extent3d {
-            width: SIZE.0,
-            height: SIZE.1,
-            depth_or_array_layers: 1,
-        },
-        TextureDimension::D2,
-        &[0, 0, 0, 255],
-        TextureFormat::Rgba8Unorm,
-    );
-    image.texture_descriptor.usage =
-        TextureUsages::COPY_DST | TextureUsages::STORAGE_BINDING | TextureUsages::TEXTURE_BINDING;
-    let image = images.add(image);
-
-    commands.spawn(SpriteBundle {
-        sprite: Sprite {
-            custom_size: Some(Vec2::new(SIZE.0 as f32, SIZE.1 as f32)),
+pub fn game_of_life_setup(
+    commands: Commands,
+    images: ResMut<Assets<Image>>,
+    grid: Res<GameOfLifeGrid>,
+) {
+    spawn_simulation(commands, images, &grid, TextureFormat::Rgba8Unorm);
+}
+
+/// Creates the state textures in `format` and the displayed texture, and the sprite and camera
+/// showing it.
+fn spawn_simulation(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    grid: &GameOfLifeGrid,
+    format: TextureFormat,
+) {
+    let texture = |format: TextureFormat, pixel: &[u8]| {
+        let mut image = Image::new_fill(grid.extent(), TextureDimension::D2, pixel, format);
+        image.texture_descriptor.usage = TextureUsages::COPY_DST
+            | TextureUsages::STORAGE_BINDING
+            | TextureUsages::TEXTURE_BINDING;
+        image
+    };
+    let state = texture(format, &vec![0; format.pixel_size()]);
+    let textures = [images.add(state.clone()), images.add(state)];
+    let display = images.add(texture(TextureFormat::Rgba8Unorm, &[0, 0, 0, 255]));
+
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(grid.texture_size().as_vec2()),
+                ..default()
+            },
+            texture: display.clone(),
             ..default()
         },
-        texture: image.clone(),
-        ..default()
-    });
+        GameOfLifeSprite,
+    ));
     commands.spawn(Camera2dBundle::default());
 
-    commands.insert_resource(GameOfLifeImage(image));
+    commands.insert_resource(GameOfLifeImage {
+        textures,
+        display,
+        current: 0,
+        generations: 0,
+    });
 }
 
+/// Runs a [`CellularAutomaton`] on the GPU. Takes the place of [`ContinuousComputePlugin`] rather
+/// than running alongside it.
 pub struct GameOfLifeComputePlugin;
 
 impl Plugin for GameOfLifeComputePlugin {
     fn build(&self, app: &mut App) {
-        // Extract the game of life image resource from the main world into the render world
-        // for operation on by the compute shader and display on the sprite.
-        app.add_plugin(ExtractResourcePlugin::<GameOfLifeImage>::default());
+        add_simulation(app);
+        // The automaton may be changed at any time, taking effect from the next generation.
+        app.init_resource::<CellularAutomaton>()
+            .add_plugin(ExtractResourcePlugin::<CellularAutomaton>::default());
+        app.add_asset::<LifePattern>()
+            .init_asset_loader::<LifePatternLoader>()
+            .add_system(apply_game_of_life_stamp);
         let render_app = app.sub_app_mut(RenderApp);
         render_app
             .init_resource::<GameOfLifePipeline>()
-            .add_system_to_stage(RenderStage::Queue, queue_bind_group);
+            .init_resource::<CellularAutomatonBuffer>()
+            .add_system_to_stage(RenderStage::Prepare, prepare_automaton)
+            .add_system_to_stage(RenderStage::Queue, queue_bind_group)
+            .add_system_to_stage(RenderStage::Queue, queue_paint_bind_group);
 
         let mut render_graph = render_app.world.resource_mut::<RenderGraph>();
         render_graph.add_node("game_of_life", GameOfLifeNode::default());
@@ -70,79 +126,561 @@ impl Plugin for GameOfLifeComputePlugin {
     }
 }
 
-#[derive(Resource, Clone, Deref, ExtractResource)]
-struct GameOfLifeImage(Handle<Image>);
+/// The controls, painting, grid and seed that [`GameOfLifeComputePlugin`] and
+/// [`ContinuousComputePlugin`] share.
+fn add_simulation(app: &mut App) {
+    // Extract the game of life image resource from the main world into the render world
+    // for operation on by the compute shader and display on the sprite.
+    app.add_plugin(ExtractResourcePlugin::<GameOfLifeImage>::default())
+        .register_type::<GameOfLifeControls>()
+        .init_resource::<GameOfLifeControls>()
+        .add_system(controls::game_of_life_keyboard)
+        .add_system(controls::advance_game_of_life.after(controls::game_of_life_keyboard));
+    // Painted cells are written by the compute pass itself, ahead of the frame's generations.
+    app.register_type::<GameOfLifeBrush>()
+        .init_resource::<GameOfLifeBrush>()
+        .init_resource::<GameOfLifePaint>()
+        .add_plugin(ExtractResourcePlugin::<GameOfLifePaint>::default())
+        .add_system(painting::resize_brush)
+        .add_system(painting::paint_cells.after(painting::resize_brush));
+    // Resizing restarts the simulation; the boundary takes effect from the next generation.
+    app.register_type::<GameOfLifeGrid>()
+        .init_resource::<GameOfLifeGrid>()
+        .add_plugin(ExtractResourcePlugin::<GameOfLifeGrid>::default())
+        .add_system(grid::resize_game_of_life);
+    app.init_resource::<GameOfLifeSeed>()
+        .add_plugin(ExtractResourcePlugin::<GameOfLifeSeed>::default());
+    app.sub_app_mut(RenderApp)
+        .init_resource::<GameOfLifeGridBuffer>()
+        .init_resource::<GameOfLifeSeedBuffer>()
+        .init_resource::<GameOfLifePaintBuffer>()
+        .add_system_to_stage(RenderStage::Prepare, prepare_grid)
+        .add_system_to_stage(RenderStage::Prepare, prepare_seed)
+        .add_system_to_stage(RenderStage::Prepare, prepare_paint);
+}
+
+/// The two textures the simulation ping-pongs between: each generation is computed from one
+/// into the other, so no invocation reads a cell another has already overwritten. Each texel's red
+/// channel holds its cell's state, which the `colour` entry point maps through the palette into
+/// the displayed texture. A [`ContinuousAutomaton`]'s textures are `Rgba32Float` instead.
+#[derive(Resource, Clone, ExtractResource)]
+struct GameOfLifeImage {
+    textures: [Handle<Image>; 2],
+    display: Handle<Image>,
+    /// The texture holding the newest generation once this frame's passes have run.
+    current: usize,
+    /// Generations to run this frame, set from [`GameOfLifeControls`].
+    generations: u32,
+}
+
+/// Marks the sprite displaying [`GameOfLifeImage`].
+#[derive(Component)]
+struct GameOfLifeSprite;
+
+#[derive(Resource, Default)]
+struct CellularAutomatonBuffer {
+    automaton: UniformBuffer<CellularAutomatonUniform>,
+    palette: UniformBuffer<PaletteUniform>,
+}
+
+fn prepare_automaton(
+    automaton: Res<CellularAutomaton>,
+    mut buffer: ResMut<CellularAutomatonBuffer>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+) {
+    buffer.automaton.set((&*automaton).into());
+    buffer.automaton.write_buffer(&render_device, &render_queue);
+    buffer.palette.set((&*automaton).into());
+    buffer.palette.write_buffer(&render_device, &render_queue);
+}
+
+#[derive(Resource, Default)]
+struct GameOfLifeGridBuffer(UniformBuffer<GameOfLifeGridUniform>);
+
+fn prepare_grid(
+    grid: Res<GameOfLifeGrid>,
+    mut buffer: ResMut<GameOfLifeGridBuffer>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+) {
+    buffer.0.set((&*grid).into());
+    buffer.0.write_buffer(&render_device, &render_queue);
+}
+
+/// Stamps a loaded [`LifePattern`] into the simulation, restarting it from the shader's random
+/// initialization with the pattern's top-left corner at texel `offset`. Rules declared by the
+/// pattern replace the [`CellularAutomaton`] with [`CellularAutomaton::life`]. Inserting or
+/// changing this resource, or modifying the pattern file, restamps it.
+#[derive(Resource, Clone)]
+pub struct GameOfLifeStamp {
+    pub pattern: Handle<LifePattern>,
+    pub offset: IVec2,
+    pub mode: StampMode,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StampMode {
+    /// Only the pattern's cells are alive.
+    #[default]
+    Replace,
+    /// The pattern's live cells are added to the random initialization.
+    Combine,
+}
 
+/// What the shader's `init` writes. Bumping `version` restarts the simulation from it.
+#[derive(Resource, Clone, Default, ExtractResource)]
+struct GameOfLifeSeed {
+    stamp: Option<(Handle<Image>, IVec2, StampMode)>,
+    /// Offsets the hash of the random initialization.
+    seed: u32,
+    /// Starts from an empty grid, ignoring the stamp.
+    clear: bool,
+    version: u32,
+}
+
+fn apply_game_of_life_stamp(
+    stamp: Option<Res<GameOfLifeStamp>>,
+    mut events: EventReader<AssetEvent<LifePattern>>,
+    patterns: Res<Assets<LifePattern>>,
+    mut images: ResMut<Assets<Image>>,
+    mut automaton: ResMut<CellularAutomaton>,
+    mut seed: ResMut<GameOfLifeSeed>,
+    mut stamped: Local<bool>,
+) {
+    let Some(stamp) = stamp else {
+        return;
+    };
+    let reloaded = events.iter().any(|event| match event {
+        AssetEvent::Created { handle } | AssetEvent::Modified { handle } => {
+            *handle == stamp.pattern
+        }
+        AssetEvent::Removed { .. } => false,
+    });
+    if stamp.is_changed() {
+        *stamped = false;
+    }
+    if *stamped && !reloaded {
+        return;
+    }
+    // Wait for the pattern to load.
+    let Some(pattern) = patterns.get(&stamp.pattern) else {
+        return;
+    };
+
+    if let Some(rules) = pattern.rules {
+        *automaton = CellularAutomaton::life(rules);
+    }
+    if let Some((image, ..)) = seed.stamp.take() {
+        images.remove(image);
+    }
+    seed.stamp = Some((images.add(pattern.to_image()), stamp.offset, stamp.mode));
+    seed.clear = false;
+    seed.version += 1;
+    *stamped = true;
+}
+
+/// [`GameOfLifeSeed`] as laid out in `game_of_life.wgsl`.
+#[derive(Clone, Default, ShaderType)]
+struct GameOfLifeSeedUniform {
+    offset: IVec2,
+    /// `0` for random initialization, `1 + StampMode` to stamp and `3` to clear.
+    mode: u32,
+    seed: u32,
+}
+
+#[derive(Resource, Default)]
+struct GameOfLifeSeedBuffer(UniformBuffer<GameOfLifeSeedUniform>);
+
+fn prepare_seed(
+    seed: Res<GameOfLifeSeed>,
+    mut buffer: ResMut<GameOfLifeSeedBuffer>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+) {
+    let (offset, mode) = match seed.stamp {
+        _ if seed.clear => (IVec2::ZERO, 3),
+        Some((_, offset, mode)) => (offset, 1 + mode as u32),
+        None => (IVec2::ZERO, 0),
+    };
+    buffer.0.set(GameOfLifeSeedUniform {
+        offset,
+        mode,
+        seed: seed.seed,
+    });
+    buffer.0.write_buffer(&render_device, &render_queue);
+}
+
+/// The pattern and its placement, read by the `init` entry point.
 #[derive(Resource)]
-struct GameOfLifeImageBindGroup(BindGroup);
+struct GameOfLifeSeedBindGroup {
+    bind_group: BindGroup,
+    /// The [`GameOfLifeSeed::version`] this was created from.
+    version: u32,
+}
 
-fn queue_bind_group(
+/// [`GameOfLifePaint`] as laid out in `game_of_life.wgsl`. The buffer is reused when it shrinks,
+/// so the shader reads `count` rather than the array's length.
+#[derive(Default, ShaderType)]
+struct GameOfLifePaintStorage {
+    min: UVec2,
+    count: u32,
+    #[size(runtime)]
+    strokes: Vec<PaintStroke>,
+}
+
+#[derive(Resource, Default)]
+struct GameOfLifePaintBuffer(StorageBuffer<GameOfLifePaintStorage>);
+
+fn prepare_paint(
+    paint: Res<GameOfLifePaint>,
+    mut buffer: ResMut<GameOfLifePaintBuffer>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+) {
+    if paint.strokes.is_empty() {
+        return;
+    }
+    buffer.0.set(GameOfLifePaintStorage {
+        min: paint.min,
+        count: paint.strokes.len() as u32,
+        strokes: paint.strokes.clone(),
+    });
+    buffer.0.write_buffer(&render_device, &render_queue);
+}
+
+/// This frame's strokes, read by the `paint` entry point. Only present while painting.
+#[derive(Resource)]
+struct GameOfLifePaintBindGroup {
+    bind_group: BindGroup,
+    workgroups: UVec2,
+}
+
+fn queue_paint_bind_group(
     mut commands: Commands,
     pipeline: Res<GameOfLifePipeline>,
-    gpu_images: Res<RenderAssets<Image>>,
-    game_of_life_image: Res<GameOfLifeImage>,
+    paint: Res<GameOfLifePaint>,
+    buffer: Res<GameOfLifePaintBuffer>,
     render_device: Res<RenderDevice>,
 ) {
-    let view = &gpu_images[&game_of_life_image.0];
+    let binding = buffer.0.binding().filter(|_| !paint.strokes.is_empty());
+    let Some(binding) = binding else {
+        commands.remove_resource::<GameOfLifePaintBindGroup>();
+        return;
+    };
     let bind_group = render_device.create_bind_group(&BindGroupDescriptor {
         label: None,
-        layout: &pipeline.texture_bind_group_layout,
+        layout: &pipeline.paint_bind_group_layout,
         entries: &[BindGroupEntry {
-            binding: 0,
-            resource: BindingResource::TextureView(&view.texture_view),
+            binding: 2,
+            resource: binding,
         }],
     });
-    commands.insert_resource(GameOfLifeImageBindGroup(bind_group));
+    let size = paint.max - paint.min;
+    commands.insert_resource(GameOfLifePaintBindGroup {
+        bind_group,
+        workgroups: (size + WORKGROUP_SIZE - 1) / WORKGROUP_SIZE,
+    });
+}
+
+/// One bind group per direction: `[i]` reads texture `i` and writes the other.
+#[derive(Resource)]
+struct GameOfLifeImageBindGroup([BindGroup; 2]);
+
+/// The displayed texture and the palette, written and read by the `colour` entry point.
+#[derive(Resource)]
+struct GameOfLifeColourBindGroup(BindGroup);
+
+#[allow(clippy::too_many_arguments)]
+fn queue_bind_group(
+    mut commands: Commands,
+    pipeline: Res<GameOfLifePipeline>,
+    gpu_images: Res<RenderAssets<Image>>,
+    game_of_life_image: Res<GameOfLifeImage>,
+    automaton: Res<CellularAutomatonBuffer>,
+    grid: Res<GameOfLifeGridBuffer>,
+    seed: Res<GameOfLifeSeed>,
+    seed_buffer: Res<GameOfLifeSeedBuffer>,
+    fallback_image: Res<FallbackImage>,
+    render_device: Res<RenderDevice>,
+) {
+    let [a, b] = &game_of_life_image.textures;
+    let (Some(a), Some(b), Some(display)) = (
+        gpu_images.get(a),
+        gpu_images.get(b),
+        gpu_images.get(&game_of_life_image.display),
+    ) else {
+        return;
+    };
+    let (Some(palette), Some(automaton), Some(grid)) = (
+        automaton.palette.binding(),
+        automaton.automaton.binding(),
+        grid.0.binding(),
+    ) else {
+        return;
+    };
+    let bind_group = |input: &TextureView, output: &TextureView| {
+        render_device.create_bind_group(&BindGroupDescriptor {
+            label: None,
+            layout: &pipeline.texture_bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(input),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::TextureView(output),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: automaton.clone(),
+                },
+                BindGroupEntry {
+                    binding: 3,
+                    resource: grid.clone(),
+                },
+            ],
+        })
+    };
+    commands.insert_resource(GameOfLifeImageBindGroup([
+        bind_group(&a.texture_view, &b.texture_view),
+        bind_group(&b.texture_view, &a.texture_view),
+    ]));
+    commands.insert_resource(GameOfLifeColourBindGroup(render_device.create_bind_group(
+        &BindGroupDescriptor {
+            label: None,
+            layout: &pipeline.colour_bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 3,
+                    resource: BindingResource::TextureView(&display.texture_view),
+                },
+                BindGroupEntry {
+                    binding: 4,
+                    resource: palette,
+                },
+            ],
+        },
+    )));
+
+    // Until the pattern reaches the GPU the fallback stands in, which `init` ignores when seeding
+    // randomly. A stamp still waiting for its image skips the frame instead.
+    let pattern = match &seed.stamp {
+        Some((image, ..)) => match gpu_images.get(image) {
+            Some(image) => image,
+            None => return,
+        },
+        None => &**fallback_image,
+    };
+    let Some(seed_binding) = seed_buffer.0.binding() else {
+        return;
+    };
+    let seed_bind_group = render_device.create_bind_group(&BindGroupDescriptor {
+        label: None,
+        layout: &pipeline.seed_bind_group_layout,
+        entries: &[
+            BindGroupEntry {
+                binding: 0,
+                resource: BindingResource::TextureView(&pattern.texture_view),
+            },
+            BindGroupEntry {
+                binding: 1,
+                resource: seed_binding,
+            },
+        ],
+    });
+    commands.insert_resource(GameOfLifeSeedBindGroup {
+        bind_group: seed_bind_group,
+        version: seed.version,
+    });
 }
 
 #[derive(Resource)]
 pub struct GameOfLifePipeline {
     texture_bind_group_layout: BindGroupLayout,
+    seed_bind_group_layout: BindGroupLayout,
+    paint_bind_group_layout: BindGroupLayout,
+    colour_bind_group_layout: BindGroupLayout,
     init_pipeline: CachedComputePipelineId,
+    paint_pipeline: CachedComputePipelineId,
     update_pipeline: CachedComputePipelineId,
+    colour_pipeline: CachedComputePipelineId,
 }
 
 impl FromWorld for GameOfLifePipeline {
     fn from_world(world: &mut World) -> Self {
         let texture_bind_group_layout =
+            world
+                .resource::<RenderDevice>()
+                .create_bind_group_layout(&BindGroupLayoutDescriptor {
+                    label: None,
+                    entries: &[
+                        BindGroupLayoutEntry {
+                            binding: 0,
+                            visibility: ShaderStages::COMPUTE,
+                            ty: BindingType::Texture {
+                                sample_type: TextureSampleType::Float { filterable: false },
+                                view_dimension: TextureViewDimension::D2,
+                                multisampled: false,
+                            },
+                            count: None,
+                        },
+                        BindGroupLayoutEntry {
+                            binding: 1,
+                            visibility: ShaderStages::COMPUTE,
+                            ty: BindingType::StorageTexture {
+                                access: StorageTextureAccess::WriteOnly,
+                                format: TextureFormat::Rgba8Unorm,
+                                view_dimension: TextureViewDimension::D2,
+                            },
+                            count: None,
+                        },
+                        BindGroupLayoutEntry {
+                            binding: 2,
+                            visibility: ShaderStages::COMPUTE,
+                            ty: BindingType::Buffer {
+                                ty: BufferBindingType::Uniform,
+                                has_dynamic_offset: false,
+                                min_binding_size: Some(CellularAutomatonUniform::min_size()),
+                            },
+                            count: None,
+                        },
+                        BindGroupLayoutEntry {
+                            binding: 3,
+                            visibility: ShaderStages::COMPUTE,
+                            ty: BindingType::Buffer {
+                                ty: BufferBindingType::Uniform,
+                                has_dynamic_offset: false,
+                                min_binding_size: Some(GameOfLifeGridUniform::min_size()),
+                            },
+                            count: None,
+                        },
+                    ],
+                });
+        let seed_bind_group_layout =
+            world
+                .resource::<RenderDevice>()
+                .create_bind_group_layout(&BindGroupLayoutDescriptor {
+                    label: None,
+                    entries: &[
+                        BindGroupLayoutEntry {
+                            binding: 0,
+                            visibility: ShaderStages::COMPUTE,
+                            ty: BindingType::Texture {
+                                sample_type: TextureSampleType::Float { filterable: false },
+                                view_dimension: TextureViewDimension::D2,
+                                multisampled: false,
+                            },
+                            count: None,
+                        },
+                        BindGroupLayoutEntry {
+                            binding: 1,
+                            visibility: ShaderStages::COMPUTE,
+                            ty: BindingType::Buffer {
+                                ty: BufferBindingType::Uniform,
+                                has_dynamic_offset: false,
+                                min_binding_size: Some(GameOfLifeSeedUniform::min_size()),
+                            },
+                            count: None,
+                        },
+                    ],
+                });
+        let paint_bind_group_layout =
             world
                 .resource::<RenderDevice>()
                 .create_bind_group_layout(&BindGroupLayoutDescriptor {
                     label: None,
                     entries: &[BindGroupLayoutEntry {
-                        binding: 0,
+                        binding: 2,
                         visibility: ShaderStages::COMPUTE,
-                        ty: BindingType::StorageTexture {
-                            access: StorageTextureAccess::ReadWrite,
-                            format: TextureFormat::Rgba8Unorm,
-                            view_dimension: TextureViewDimension::D2,
+                        ty: BindingType::Buffer {
+                            ty: BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: Some(GameOfLifePaintStorage::min_size()),
                         },
                         count: None,
                     }],
                 });
+        let colour_bind_group_layout =
+            world
+                .resource::<RenderDevice>()
+                .create_bind_group_layout(&BindGroupLayoutDescriptor {
+                    label: None,
+                    entries: &[
+                        BindGroupLayoutEntry {
+                            binding: 3,
+                            visibility: ShaderStages::COMPUTE,
+                            ty: BindingType::StorageTexture {
+                                access: StorageTextureAccess::WriteOnly,
+                                format: TextureFormat::Rgba8Unorm,
+                                view_dimension: TextureViewDimension::D2,
+                            },
+                            count: None,
+                        },
+                        BindGroupLayoutEntry {
+                            binding: 4,
+                            visibility: ShaderStages::COMPUTE,
+                            ty: BindingType::Buffer {
+                                ty: BufferBindingType::Uniform,
+                                has_dynamic_offset: false,
+                                min_binding_size: Some(PaletteUniform::min_size()),
+                            },
+                            count: None,
+                        },
+                    ],
+                });
         let shader = world
             .resource::<AssetServer>()
             .load("shaders/compute/game_of_life.wgsl");
         let mut pipeline_cache = world.resource_mut::<PipelineCache>();
         let init_pipeline = pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
             label: None,
-            layout: Some(vec![texture_bind_group_layout.clone()]),
+            layout: Some(vec![
+                texture_bind_group_layout.clone(),
+                seed_bind_group_layout.clone(),
+            ]),
             shader: shader.clone(),
             shader_defs: vec![],
             entry_point: Cow::from("init"),
         });
+        let paint_pipeline = pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
+            label: None,
+            layout: Some(vec![
+                texture_bind_group_layout.clone(),
+                paint_bind_group_layout.clone(),
+            ]),
+            shader: shader.clone(),
+            shader_defs: vec![],
+            entry_point: Cow::from("paint"),
+        });
         let update_pipeline = pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
             label: None,
             layout: Some(vec![texture_bind_group_layout.clone()]),
-            shader,
+            shader: shader.clone(),
             shader_defs: vec![],
             entry_point: Cow::from("update"),
         });
+        let colour_pipeline = pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
+            label: None,
+            layout: Some(vec![
+                texture_bind_group_layout.clone(),
+                colour_bind_group_layout.clone(),
+            ]),
+            shader,
+            shader_defs: vec![],
+            entry_point: Cow::from("colour"),
+        });
 
         GameOfLifePipeline {
             texture_bind_group_layout,
+            seed_bind_group_layout,
+            paint_bind_group_layout,
+            colour_bind_group_layout,
             init_pipeline,
+            paint_pipeline,
             update_pipeline,
+            colour_pipeline,
         }
     }
 }
@@ -155,12 +693,15 @@ enum GameOfLifeState {
 
 struct GameOfLifeNode {
     state: GameOfLifeState,
+    /// The seed last initialized from.
+    seed_version: u32,
 }
 
 impl Default for GameOfLifeNode {
     fn default() -> Self {
         Self {
             state: GameOfLifeState::Loading,
+            seed_version: 0,
         }
     }
 }
@@ -169,6 +710,9 @@ impl render_graph::Node for GameOfLifeNode {
     fn update(&mut self, world: &mut World) {
         let pipeline = world.resource::<GameOfLifePipeline>();
         let pipeline_cache = world.resource::<PipelineCache>();
+        let seed_version = world
+            .get_resource::<GameOfLifeSeedBindGroup>()
+            .map(|seed| seed.version);
 
         // if the corresponding pipeline has loaded, transition to the next stage
         match self.state {
@@ -186,7 +730,15 @@ impl render_graph::Node for GameOfLifeNode {
                     self.state = GameOfLifeState::Update;
                 }
             }
-            GameOfLifeState::Update => {}
+            GameOfLifeState::Update => {
+                // a new seed restarts the simulation
+                if seed_version.is_some_and(|version| version != self.seed_version) {
+                    self.state = GameOfLifeState::Init;
+                }
+            }
+        }
+        if let (GameOfLifeState::Init, Some(version)) = (&self.state, seed_version) {
+            self.seed_version = version;
         }
     }
 
@@ -196,35 +748,73 @@ impl render_graph::Node for GameOfLifeNode {
         render_context: &mut RenderContext,
         world: &World,
     ) -> Result<(), render_graph::NodeRunError> {
-        let texture_bind_group = &world.resource::<GameOfLifeImageBindGroup>().0;
+        let Some(bind_groups) = world.get_resource::<GameOfLifeImageBindGroup>() else {
+            return Ok(());
+        };
+        let game_of_life_image = world.resource::<GameOfLifeImage>();
+        // Round up so partial workgroups cover the edges, which the shader bounds checks.
+        let workgroups = (world.resource::<GameOfLifeGrid>().texture_size() + WORKGROUP_SIZE - 1)
+            / WORKGROUP_SIZE;
         let pipeline_cache = world.resource::<PipelineCache>();
         let pipeline = world.resource::<GameOfLifePipeline>();
 
+        let update_pipeline = match self.state {
+            GameOfLifeState::Loading => return Ok(()),
+            _ => pipeline_cache.get_compute_pipeline(pipeline.update_pipeline),
+        };
+        let generations = match update_pipeline {
+            Some(_) => game_of_life_image.generations,
+            None => 0,
+        };
+        // Passes alternate textures so the last one writes `current`. Texture `i` is read by bind
+        // group `i`, which writes the other.
+        let input = |pass: u32| game_of_life_image.current ^ ((generations - pass) & 1) as usize;
+
         let mut pass = render_context
             .command_encoder
             .begin_compute_pass(&ComputePassDescriptor::default());
 
-        pass.set_bind_group(0, texture_bind_group, &[]);
-
-        // select the pipeline based on the current state
-        match self.state {
-            GameOfLifeState::Loading => {}
-            GameOfLifeState::Init => {
+        // initialize into the input of the first update
+        if let GameOfLifeState::Init = self.state {
+            if let Some(seed) = world.get_resource::<GameOfLifeSeedBindGroup>() {
                 let init_pipeline = pipeline_cache
                     .get_compute_pipeline(pipeline.init_pipeline)
                     .unwrap();
                 pass.set_pipeline(init_pipeline);
-                pass.dispatch_workgroups(SIZE.0 / WORKGROUP_SIZE, SIZE.1 / WORKGROUP_SIZE, 1);
+                pass.set_bind_group(0, &bind_groups.0[1 - input(0)], &[]);
+                pass.set_bind_group(1, &seed.bind_group, &[]);
+                pass.dispatch_workgroups(workgroups.x, workgroups.y, 1);
             }
-            GameOfLifeState::Update => {
-                let update_pipeline = pipeline_cache
-                    .get_compute_pipeline(pipeline.update_pipeline)
-                    .unwrap();
-                pass.set_pipeline(update_pipeline);
-                pass.dispatch_workgroups(SIZE.0 / WORKGROUP_SIZE, SIZE.1 / WORKGROUP_SIZE, 1);
+        }
+
+        // paint over the same texture, so edits can't race the updates reading it
+        let paint_pipeline = pipeline_cache.get_compute_pipeline(pipeline.paint_pipeline);
+        let paint = world.get_resource::<GameOfLifePaintBindGroup>();
+        if let (Some(paint_pipeline), Some(paint)) = (paint_pipeline, paint) {
+            pass.set_pipeline(paint_pipeline);
+            pass.set_bind_group(0, &bind_groups.0[1 - input(0)], &[]);
+            pass.set_bind_group(1, &paint.bind_group, &[]);
+            pass.dispatch_workgroups(paint.workgroups.x, paint.workgroups.y, 1);
+        }
+
+        if let Some(update_pipeline) = update_pipeline {
+            pass.set_pipeline(update_pipeline);
+            for generation in 0..generations {
+                pass.set_bind_group(0, &bind_groups.0[input(generation)], &[]);
+                pass.dispatch_workgroups(workgroups.x, workgroups.y, 1);
             }
         }
 
+        // colour the newest generation for display, after edits even while paused
+        let colour_pipeline = pipeline_cache.get_compute_pipeline(pipeline.colour_pipeline);
+        let colour = world.get_resource::<GameOfLifeColourBindGroup>();
+        if let (Some(colour_pipeline), Some(colour)) = (colour_pipeline, colour) {
+            pass.set_pipeline(colour_pipeline);
+            pass.set_bind_group(0, &bind_groups.0[game_of_life_image.current], &[]);
+            pass.set_bind_group(1, &colour.0, &[]);
+            pass.dispatch_workgroups(workgroups.x, workgroups.y, 1);
+        }
+
         Ok(())
     }
 }
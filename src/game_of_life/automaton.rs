@@ -0,0 +1,342 @@
+//! Automata beyond Conway's: multi-state Generations rules, Wireworld and Larger than Life, over
+//! Moore, von Neumann or hexagonal neighbourhoods.
+//!
+//! A [`CellularAutomaton`] parameterizes both `game_of_life.wgsl` and the CPU's
+//! [`AutomatonGrid`](super::cpu::AutomatonGrid). Cells hold a state below
+//! [`CellularAutomaton::states`], which the palette colours for display.
+
+use std::ops::RangeInclusive;
+
+use bevy::{
+    prelude::*,
+    render::{extract_resource::ExtractResource, render_resource::ShaderType},
+};
+
+use super::GameOfLifeRules;
+
+/// The largest neighbourhood radius the shader supports.
+pub const MAX_RADIUS: u32 = 7;
+/// States are stored in a byte per cell.
+pub const MAX_STATES: u32 = 256;
+/// Counts are sets of bits, enough for the largest Moore neighbourhood and its center.
+const MAX_COUNT: u32 = 256;
+
+/// The cells whose states a cell's next state depends on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Neighbourhood {
+    /// The square of cells within the radius along both axes.
+    Moore(u32),
+    /// The diamond of cells within the radius in steps along the axes.
+    VonNeumann(u32),
+    /// The hexagon of cells within the radius on a grid skewed so that each cell's six adjacent
+    /// neighbours are its Moore neighbours but for the north-west and south-east ones.
+    Hex(u32),
+}
+
+impl Neighbourhood {
+    /// Clamped to [`MAX_RADIUS`].
+    pub fn radius(self) -> u32 {
+        match self {
+            Neighbourhood::Moore(radius)
+            | Neighbourhood::VonNeumann(radius)
+            | Neighbourhood::Hex(radius) => radius.min(MAX_RADIUS),
+        }
+    }
+
+    /// Whether the cell at `offset` from the center is in the neighbourhood.
+    pub fn contains(self, offset: IVec2) -> bool {
+        let radius = self.radius() as i32;
+        match self {
+            Neighbourhood::Moore(_) => offset.abs().max_element() <= radius,
+            Neighbourhood::VonNeumann(_) => offset.x.abs() + offset.y.abs() <= radius,
+            Neighbourhood::Hex(_) => {
+                offset.x.abs() + offset.y.abs() + (offset.x + offset.y).abs() <= 2 * radius
+            }
+        }
+    }
+
+    /// The offsets of the neighbours, excluding the center, in rows from the top left.
+    pub fn offsets(self) -> impl Iterator<Item = IVec2> {
+        let radius = self.radius() as i32;
+        (-radius..=radius)
+            .flat_map(move |y| (-radius..=radius).map(move |x| IVec2::new(x, y)))
+            .filter(move |&offset| offset != IVec2::ZERO && self.contains(offset))
+    }
+}
+
+/// A set of neighbour counts, one bit each.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NeighbourCounts(pub [u32; 8]);
+
+impl NeighbourCounts {
+    pub fn new(counts: impl IntoIterator<Item = u32>) -> Self {
+        let mut set = Self::default();
+        for count in counts.into_iter().filter(|&count| count < MAX_COUNT) {
+            set.0[count as usize / 32] |= 1 << (count % 32);
+        }
+        set
+    }
+
+    pub fn contains(&self, count: u32) -> bool {
+        count < MAX_COUNT && self.0[count as usize / 32] >> (count % 32) & 1 == 1
+    }
+}
+
+impl<const N: usize> From<[u32; N]> for NeighbourCounts {
+    fn from(counts: [u32; N]) -> Self {
+        Self::new(counts)
+    }
+}
+
+impl From<RangeInclusive<u32>> for NeighbourCounts {
+    fn from(counts: RangeInclusive<u32>) -> Self {
+        Self::new(counts)
+    }
+}
+
+/// A birth or survival mask of [`GameOfLifeRules`].
+impl From<u16> for NeighbourCounts {
+    fn from(mask: u16) -> Self {
+        Self::new((0..16).filter(|n| mask >> n & 1 == 1))
+    }
+}
+
+/// How a cell's state changes with the number of live neighbours, those in state `1`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Transition {
+    /// Dead cells, in state `0`, with a count in `birth` come alive in state `1`, and live cells
+    /// with a count in `survival` stay alive. Other live cells pass through each dying state
+    /// `2..states` in turn before dying, or die at once with two states. Larger than Life's `M1`
+    /// is `include_center`, counting the cell itself.
+    Generations {
+        birth: NeighbourCounts,
+        survival: NeighbourCounts,
+        states: u32,
+        include_center: bool,
+    },
+    /// Empty cells (`0`) stay empty, electron heads (`1`) become tails (`2`), tails become
+    /// conductors (`3`), and conductors become heads next to one or two heads.
+    Wireworld,
+}
+
+/// The automaton `game_of_life.wgsl` runs. Changing it takes effect from the next generation,
+/// though cells in states it lacks are left to the transition.
+#[derive(Resource, ExtractResource, Debug, Clone, PartialEq)]
+pub struct CellularAutomaton {
+    pub neighbourhood: Neighbourhood,
+    pub transition: Transition,
+    /// Colours by state. States past the end use the last colour.
+    pub palette: Vec<Color>,
+}
+
+impl Default for CellularAutomaton {
+    fn default() -> Self {
+        Self::life(GameOfLifeRules::CONWAY)
+    }
+}
+
+impl CellularAutomaton {
+    /// A Life-like rule, live cells white.
+    pub fn life(rules: GameOfLifeRules) -> Self {
+        Self::generations(Neighbourhood::Moore(1), rules.birth, rules.survival, 2)
+    }
+
+    /// A Generations rule, live cells white and dying cells fading from orange to red.
+    pub fn generations(
+        neighbourhood: Neighbourhood,
+        birth: impl Into<NeighbourCounts>,
+        survival: impl Into<NeighbourCounts>,
+        states: u32,
+    ) -> Self {
+        let states = states.clamp(2, MAX_STATES);
+        let dying = states - 2;
+        let palette = [Color::NONE, Color::WHITE]
+            .into_iter()
+            .chain((0..dying).map(|i| {
+                let fade = i as f32 / dying as f32;
+                Color::hsl(40.0 * (1.0 - fade), 1.0, 0.5 - 0.3 * fade)
+            }))
+            .collect();
+        Self {
+            neighbourhood,
+            transition: Transition::Generations {
+                birth: birth.into(),
+                survival: survival.into(),
+                states,
+                include_center: false,
+            },
+            palette,
+        }
+    }
+
+    /// Larger than Life's `Rr,Cc,Mm,Sb..s,Bb..b,Nn`, with `C0` and `C2` both being two states.
+    pub fn larger_than_life(
+        neighbourhood: Neighbourhood,
+        birth: RangeInclusive<u32>,
+        survival: RangeInclusive<u32>,
+        states: u32,
+        include_center: bool,
+    ) -> Self {
+        let mut automaton = Self::generations(neighbourhood, birth, survival, states);
+        if let Transition::Generations {
+            include_center: center,
+            ..
+        } = &mut automaton.transition
+        {
+            *center = include_center;
+        }
+        automaton
+    }
+
+    /// B2/S/C3
+    pub fn brians_brain() -> Self {
+        Self::generations(Neighbourhood::Moore(1), [2], NeighbourCounts::default(), 3)
+    }
+
+    /// B2/S345/C4
+    pub fn star_wars() -> Self {
+        Self::generations(Neighbourhood::Moore(1), [2], [3, 4, 5], 4)
+    }
+
+    /// Bosco's rule, `R5,C0,M1,S34..58,B34..45,NM`.
+    pub fn bosco() -> Self {
+        Self::larger_than_life(Neighbourhood::Moore(5), 34..=45, 34..=58, 2, true)
+    }
+
+    /// Wireworld, heads blue, tails red and conductors yellow.
+    pub fn wireworld() -> Self {
+        Self {
+            neighbourhood: Neighbourhood::Moore(1),
+            transition: Transition::Wireworld,
+            palette: vec![
+                Color::NONE,
+                Color::rgb(0.0, 0.4, 1.0),
+                Color::rgb(1.0, 0.2, 0.0),
+                Color::rgb(1.0, 0.8, 0.0),
+            ],
+        }
+    }
+
+    pub fn states(&self) -> u32 {
+        match self.transition {
+            Transition::Generations { states, .. } => states.clamp(2, MAX_STATES),
+            Transition::Wireworld => 4,
+        }
+    }
+
+    /// The offsets of the cells counted for each cell, which include the cell itself for
+    /// Larger than Life's `M1`.
+    pub fn counted_offsets(&self) -> Vec<IVec2> {
+        let include_center = matches!(
+            self.transition,
+            Transition::Generations {
+                include_center: true,
+                ..
+            }
+        );
+        let mut offsets: Vec<_> = self.neighbourhood.offsets().collect();
+        if include_center {
+            offsets.push(IVec2::ZERO);
+        }
+        offsets
+    }
+
+    /// The next state of a cell in `state` with `count` live neighbours.
+    pub fn next(&self, state: u32, count: u32) -> u32 {
+        match &self.transition {
+            Transition::Generations {
+                birth, survival, ..
+            } => match state {
+                0 if birth.contains(count) => 1,
+                0 => 0,
+                1 if survival.contains(count) => 1,
+                _ => (state + 1) % self.states(),
+            },
+            Transition::Wireworld => match state {
+                1 => 2,
+                2 => 3,
+                3 if count == 1 || count == 2 => 1,
+                _ => state,
+            },
+        }
+    }
+
+    /// The display colour of `state`.
+    pub fn colour(&self, state: u32) -> Color {
+        self.palette
+            .get(state as usize)
+            .or_else(|| self.palette.last())
+            .copied()
+            .unwrap_or(Color::NONE)
+    }
+}
+
+/// [`CellularAutomaton`] as laid out in `game_of_life.wgsl`, without the palette.
+#[derive(Clone, Default, ShaderType)]
+pub(super) struct CellularAutomatonUniform {
+    pub birth: [UVec4; 2],
+    pub survival: [UVec4; 2],
+    /// `0` for Moore, `1` for von Neumann and `2` for hex.
+    pub neighbourhood: u32,
+    pub radius: i32,
+    pub states: u32,
+    /// `0` for Generations and `1` for Wireworld.
+    pub transition: u32,
+    pub include_center: u32,
+}
+
+impl From<&CellularAutomaton> for CellularAutomatonUniform {
+    fn from(automaton: &CellularAutomaton) -> Self {
+        let words = |counts: &NeighbourCounts| {
+            let [a, b, c, d, e, f, g, h] = counts.0;
+            [UVec4::new(a, b, c, d), UVec4::new(e, f, g, h)]
+        };
+        let (birth, survival, transition, include_center) = match &automaton.transition {
+            Transition::Generations {
+                birth,
+                survival,
+                include_center,
+                ..
+            } => (words(birth), words(survival), 0, *include_center as u32),
+            Transition::Wireworld => ([UVec4::ZERO; 2], [UVec4::ZERO; 2], 1, 0),
+        };
+        Self {
+            birth,
+            survival,
+            neighbourhood: match automaton.neighbourhood {
+                Neighbourhood::Moore(_) => 0,
+                Neighbourhood::VonNeumann(_) => 1,
+                Neighbourhood::Hex(_) => 2,
+            },
+            radius: automaton.neighbourhood.radius() as i32,
+            states: automaton.states(),
+            transition,
+            include_center,
+        }
+    }
+}
+
+/// The palette as read by the `colour` entry point, in linear colour as the display texture isn't
+/// sRGB.
+#[derive(Clone, ShaderType)]
+pub(super) struct PaletteUniform {
+    pub colours: [Vec4; MAX_STATES as usize],
+}
+
+impl Default for PaletteUniform {
+    fn default() -> Self {
+        Self {
+            colours: [Vec4::ZERO; MAX_STATES as usize],
+        }
+    }
+}
+
+impl From<&CellularAutomaton> for PaletteUniform {
+    fn from(automaton: &CellularAutomaton) -> Self {
+        let mut palette = Self::default();
+        for (state, colour) in palette.colours.iter_mut().enumerate() {
+            *colour = Vec4::from(automaton.colour(state as u32).as_linear_rgba_f32());
+        }
+        palette
+    }
+}
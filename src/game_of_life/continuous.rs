@@ -0,0 +1,723 @@
+//! Continuous automata, whose cells hold real values rather than states: Lenia, a kernel
+//! convolution with a growth function, and Gray-Scott reaction-diffusion.
+//!
+//! [`ContinuousComputePlugin`] runs a [`ContinuousAutomaton`] in `continuous.wgsl` on
+//! `Rgba32Float` textures, sharing the controls, painting and grid of the
+//! [`GameOfLifeComputePlugin`](super::GameOfLifeComputePlugin). Each texel's red and green
+//! channels hold its cell. [`ContinuousGrid`](super::cpu::ContinuousGrid) is the CPU reference.
+
+use std::borrow::Cow;
+
+use bevy::{
+    prelude::*,
+    render::{
+        extract_resource::{ExtractResource, ExtractResourcePlugin},
+        render_asset::RenderAssets,
+        render_graph::{self, RenderGraph},
+        render_resource::*,
+        renderer::{RenderContext, RenderDevice, RenderQueue},
+        RenderApp, RenderStage,
+    },
+};
+
+use super::{
+    add_simulation, cpu::random_float, spawn_simulation, GameOfLifeGrid, GameOfLifeGridBuffer,
+    GameOfLifeGridUniform, GameOfLifeImage, GameOfLifePaint, GameOfLifePaintBuffer,
+    GameOfLifePaintStorage, GameOfLifeSeed, GameOfLifeSeedBuffer, GameOfLifeSeedUniform,
+    GameOfLifeState, WORKGROUP_SIZE,
+};
+
+/// The largest kernel radius, beyond which convolving every cell each generation gets too slow.
+pub const MAX_KERNEL_RADIUS: u32 = 32;
+/// Random initialization fills or leaves empty square blocks of this many cells a side.
+const SEED_BLOCK_SIZE: u32 = 16;
+
+/// Each generation convolves the grid with a ring-shaped kernel, and grows each cell by the growth
+/// function of its convolution, a bell around `mu` that is negative far from it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lenia {
+    /// The kernel's radius in cells.
+    pub radius: u32,
+    /// The heights of the kernel's concentric rings, from the center out.
+    pub rings: Vec<f32>,
+    /// The convolution at which cells grow fastest.
+    pub mu: f32,
+    /// The width of the growth function's bell.
+    pub sigma: f32,
+    /// The time step, `1 / T`.
+    pub dt: f32,
+}
+
+impl Lenia {
+    /// Orbium unicaudatus, a glider.
+    pub fn orbium() -> Self {
+        Self {
+            radius: 13,
+            rings: vec![1.0],
+            mu: 0.15,
+            sigma: 0.015,
+            dt: 0.1,
+        }
+    }
+
+    /// Hydrogeminium natans, which divides and swims.
+    pub fn hydrogeminium() -> Self {
+        Self {
+            radius: 18,
+            rings: vec![0.5, 1.0, 2.0 / 3.0],
+            mu: 0.26,
+            sigma: 0.036,
+            dt: 0.1,
+        }
+    }
+
+    /// `1` at `mu`, falling to `-1` far from it.
+    pub fn growth(&self, convolution: f32) -> f32 {
+        let distance = (convolution - self.mu) / self.sigma;
+        2.0 * (-distance * distance / 2.0).exp() - 1.0
+    }
+
+    /// The kernel's height `distance` cells from the center, before normalization: a smooth bump
+    /// across each ring.
+    fn shell(&self, distance: f32) -> f32 {
+        let radius = self.radius.clamp(1, MAX_KERNEL_RADIUS) as f32;
+        let rings = distance / radius * self.rings.len() as f32;
+        let (ring, within) = (rings.floor() as usize, rings.fract());
+        match self.rings.get(ring) {
+            Some(height) if within > 0.0 => height * (4.0 - 1.0 / (within * (1.0 - within))).exp(),
+            _ => 0.0,
+        }
+    }
+}
+
+/// Two chemicals react as `u + 2v -> 3v` while diffusing, `u` fed in and `v` killed off at fixed
+/// rates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GrayScott {
+    pub feed: f32,
+    pub kill: f32,
+    pub diffusion_u: f32,
+    pub diffusion_v: f32,
+    pub dt: f32,
+}
+
+impl GrayScott {
+    /// Spots that grow and divide.
+    pub fn mitosis() -> Self {
+        Self::new(0.0367, 0.0649)
+    }
+
+    /// Branching growth.
+    pub fn coral() -> Self {
+        Self::new(0.0545, 0.062)
+    }
+
+    /// Winding stripes that fill the grid.
+    pub fn maze() -> Self {
+        Self::new(0.029, 0.057)
+    }
+
+    /// With `u` diffusing twice as fast as `v`, at the largest stable time step.
+    pub fn new(feed: f32, kill: f32) -> Self {
+        Self {
+            feed,
+            kill,
+            diffusion_u: 1.0,
+            diffusion_v: 0.5,
+            dt: 1.0,
+        }
+    }
+}
+
+/// The automaton [`ContinuousComputePlugin`] runs. Changing it takes effect from the next
+/// generation.
+#[derive(Resource, ExtractResource, Debug, Clone, PartialEq)]
+pub enum ContinuousAutomaton {
+    /// The red channel holds each cell's value in `0.0..=1.0`.
+    Lenia(Lenia),
+    /// The red and green channels hold each cell's `u` and `v`.
+    GrayScott(GrayScott),
+}
+
+impl Default for ContinuousAutomaton {
+    fn default() -> Self {
+        ContinuousAutomaton::Lenia(Lenia::orbium())
+    }
+}
+
+impl ContinuousAutomaton {
+    pub fn radius(&self) -> u32 {
+        match self {
+            ContinuousAutomaton::Lenia(lenia) => lenia.radius.clamp(1, MAX_KERNEL_RADIUS),
+            ContinuousAutomaton::GrayScott(_) => 1,
+        }
+    }
+
+    /// The convolution weights of the square of cells within [`Self::radius`], a row at a time
+    /// from the top left: Lenia's kernel normalized to sum to `1`, or Gray-Scott's Laplacian.
+    pub fn kernel(&self) -> Vec<f32> {
+        match self {
+            ContinuousAutomaton::Lenia(lenia) => {
+                let radius = self.radius() as i32;
+                let mut kernel: Vec<f32> = (-radius..=radius)
+                    .flat_map(|y| (-radius..=radius).map(move |x| Vec2::new(x as f32, y as f32)))
+                    .map(|offset| lenia.shell(offset.length()))
+                    .collect();
+                let total: f32 = kernel.iter().sum();
+                if total > 0.0 {
+                    kernel.iter_mut().for_each(|weight| *weight /= total);
+                }
+                kernel
+            }
+            ContinuousAutomaton::GrayScott(_) => vec![
+                0.05, 0.2, 0.05, //
+                0.2, -1.0, 0.2, //
+                0.05, 0.2, 0.05,
+            ],
+        }
+    }
+
+    /// A cell after one generation, from the cell and its convolution with [`Self::kernel`].
+    pub fn next(&self, cell: Vec2, convolution: Vec2) -> Vec2 {
+        match self {
+            ContinuousAutomaton::Lenia(lenia) => {
+                let value = cell.x + lenia.dt * lenia.growth(convolution.x);
+                Vec2::new(value.clamp(0.0, 1.0), 0.0)
+            }
+            ContinuousAutomaton::GrayScott(gray_scott) => {
+                let GrayScott {
+                    feed,
+                    kill,
+                    diffusion_u,
+                    diffusion_v,
+                    dt,
+                } = *gray_scott;
+                let reaction = cell.x * cell.y * cell.y;
+                let change = Vec2::new(
+                    diffusion_u * convolution.x - reaction + feed * (1.0 - cell.x),
+                    diffusion_v * convolution.y + reaction - (feed + kill) * cell.y,
+                );
+                (cell + dt * change).clamp(Vec2::ZERO, Vec2::ONE)
+            }
+        }
+    }
+
+    /// A cell with nothing in it, as cleared or erased.
+    pub fn empty(&self) -> Vec2 {
+        match self {
+            ContinuousAutomaton::Lenia(_) => Vec2::ZERO,
+            ContinuousAutomaton::GrayScott(_) => Vec2::new(1.0, 0.0),
+        }
+    }
+
+    /// A cell of `value` strength, as randomly initialized or painted.
+    pub fn filled(&self, value: f32) -> Vec2 {
+        match self {
+            ContinuousAutomaton::Lenia(_) => Vec2::new(value, 0.0),
+            ContinuousAutomaton::GrayScott(_) => Vec2::new(0.5, 0.25),
+        }
+    }
+
+    /// The cell the shader's `init` writes for `seed`: blocks of cells are filled at random, each
+    /// cell with a random value.
+    pub fn seeded_cell(&self, x: u32, y: u32, width: u32, seed: u32) -> Vec2 {
+        let offset = seed.wrapping_mul(2654435769);
+        let block = ((y / SEED_BLOCK_SIZE) << 16) | (x / SEED_BLOCK_SIZE);
+        if random_float(block.wrapping_add(offset)) <= 0.8 {
+            return self.empty();
+        }
+        let cell = y.wrapping_mul(width).wrapping_add(x).wrapping_add(offset);
+        self.filled(random_float(cell ^ 2654435769))
+    }
+}
+
+/// [`ContinuousAutomaton`] as laid out in `continuous.wgsl`.
+#[derive(Clone, Default, ShaderType)]
+struct ContinuousAutomatonUniform {
+    /// `0` for Lenia and `1` for Gray-Scott.
+    kind: u32,
+    radius: i32,
+    mu: f32,
+    sigma: f32,
+    dt: f32,
+    feed: f32,
+    kill: f32,
+    diffusion: Vec2,
+}
+
+impl From<&ContinuousAutomaton> for ContinuousAutomatonUniform {
+    fn from(automaton: &ContinuousAutomaton) -> Self {
+        let radius = automaton.radius() as i32;
+        match automaton {
+            ContinuousAutomaton::Lenia(lenia) => Self {
+                kind: 0,
+                radius,
+                mu: lenia.mu,
+                sigma: lenia.sigma,
+                dt: lenia.dt,
+                ..default()
+            },
+            ContinuousAutomaton::GrayScott(gray_scott) => Self {
+                kind: 1,
+                radius,
+                dt: gray_scott.dt,
+                feed: gray_scott.feed,
+                kill: gray_scott.kill,
+                diffusion: Vec2::new(gray_scott.diffusion_u, gray_scott.diffusion_v),
+                ..default()
+            },
+        }
+    }
+}
+
+#[derive(Default, ShaderType)]
+struct ContinuousKernelStorage {
+    #[size(runtime)]
+    weights: Vec<f32>,
+}
+
+#[derive(Resource, Default)]
+struct ContinuousAutomatonBuffer {
+    automaton: UniformBuffer<ContinuousAutomatonUniform>,
+    kernel: StorageBuffer<ContinuousKernelStorage>,
+}
+
+/// Runs a [`ContinuousAutomaton`] on the GPU, set up by [`continuous_setup`]. Takes the place of
+/// [`GameOfLifeComputePlugin`](super::GameOfLifeComputePlugin) rather than running alongside it.
+/// Life patterns can't be stamped, but painting fills cells.
+pub struct ContinuousComputePlugin;
+
+impl Plugin for ContinuousComputePlugin {
+    fn build(&self, app: &mut App) {
+        add_simulation(app);
+        app.init_resource::<ContinuousAutomaton>()
+            .add_plugin(ExtractResourcePlugin::<ContinuousAutomaton>::default());
+        let render_app = app.sub_app_mut(RenderApp);
+        render_app
+            .init_resource::<ContinuousPipeline>()
+            .init_resource::<ContinuousAutomatonBuffer>()
+            .add_system_to_stage(RenderStage::Prepare, prepare_continuous_automaton)
+            .add_system_to_stage(RenderStage::Queue, queue_continuous_bind_groups);
+
+        let mut render_graph = render_app.world.resource_mut::<RenderGraph>();
+        render_graph.add_node("continuous", ContinuousNode::default());
+        render_graph
+            .add_node_edge("continuous", bevy::render::main_graph::node::CAMERA_DRIVER)
+            .unwrap();
+    }
+}
+
+pub fn continuous_setup(
+    commands: Commands,
+    images: ResMut<Assets<Image>>,
+    grid: Res<GameOfLifeGrid>,
+) {
+    spawn_simulation(commands, images, &grid, TextureFormat::Rgba32Float);
+}
+
+/// The kernel is only rebuilt and uploaded when the automaton changes.
+fn prepare_continuous_automaton(
+    automaton: Res<ContinuousAutomaton>,
+    mut buffer: ResMut<ContinuousAutomatonBuffer>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+    mut prepared: Local<Option<ContinuousAutomaton>>,
+) {
+    if prepared.as_ref() == Some(&*automaton) {
+        return;
+    }
+    buffer.automaton.set((&*automaton).into());
+    buffer.automaton.write_buffer(&render_device, &render_queue);
+    buffer.kernel.set(ContinuousKernelStorage {
+        weights: automaton.kernel(),
+    });
+    buffer.kernel.write_buffer(&render_device, &render_queue);
+    *prepared = Some(automaton.clone());
+}
+
+#[derive(Resource)]
+struct ContinuousBindGroups {
+    /// `[i]` reads texture `i` and writes the other.
+    images: [BindGroup; 2],
+    seed: BindGroup,
+    /// The [`GameOfLifeSeed::version`] `seed` was created from.
+    seed_version: u32,
+    colour: BindGroup,
+    /// This frame's strokes, and the workgroups covering them, only present while painting.
+    paint: Option<(BindGroup, UVec2)>,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn queue_continuous_bind_groups(
+    mut commands: Commands,
+    pipeline: Res<ContinuousPipeline>,
+    gpu_images: Res<RenderAssets<Image>>,
+    game_of_life_image: Res<GameOfLifeImage>,
+    automaton: Res<ContinuousAutomatonBuffer>,
+    grid: Res<GameOfLifeGridBuffer>,
+    seed: Res<GameOfLifeSeed>,
+    seed_buffer: Res<GameOfLifeSeedBuffer>,
+    paint: Res<GameOfLifePaint>,
+    paint_buffer: Res<GameOfLifePaintBuffer>,
+    render_device: Res<RenderDevice>,
+) {
+    let [a, b] = &game_of_life_image.textures;
+    let (Some(a), Some(b), Some(display)) = (
+        gpu_images.get(a),
+        gpu_images.get(b),
+        gpu_images.get(&game_of_life_image.display),
+    ) else {
+        return;
+    };
+    let (Some(uniform), Some(kernel), Some(grid), Some(seed_binding)) = (
+        automaton.automaton.binding(),
+        automaton.kernel.binding(),
+        grid.0.binding(),
+        seed_buffer.0.binding(),
+    ) else {
+        return;
+    };
+
+    let bind_group = |layout: &BindGroupLayout, entries: &[BindGroupEntry]| {
+        render_device.create_bind_group(&BindGroupDescriptor {
+            label: None,
+            layout,
+            entries,
+        })
+    };
+    let image_bind_group = |input: &TextureView, output: &TextureView| {
+        bind_group(
+            &pipeline.texture_bind_group_layout,
+            &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(input),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::TextureView(output),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: uniform.clone(),
+                },
+                BindGroupEntry {
+                    binding: 3,
+                    resource: grid.clone(),
+                },
+                BindGroupEntry {
+                    binding: 4,
+                    resource: kernel.clone(),
+                },
+            ],
+        )
+    };
+    let paint = paint_buffer
+        .0
+        .binding()
+        .filter(|_| !paint.strokes.is_empty())
+        .map(|binding| {
+            let size = paint.max - paint.min;
+            (
+                bind_group(
+                    &pipeline.paint_bind_group_layout,
+                    &[BindGroupEntry {
+                        binding: 2,
+                        resource: binding,
+                    }],
+                ),
+                (size + WORKGROUP_SIZE - 1) / WORKGROUP_SIZE,
+            )
+        });
+
+    commands.insert_resource(ContinuousBindGroups {
+        images: [
+            image_bind_group(&a.texture_view, &b.texture_view),
+            image_bind_group(&b.texture_view, &a.texture_view),
+        ],
+        seed: bind_group(
+            &pipeline.seed_bind_group_layout,
+            &[BindGroupEntry {
+                binding: 1,
+                resource: seed_binding,
+            }],
+        ),
+        seed_version: seed.version,
+        colour: bind_group(
+            &pipeline.colour_bind_group_layout,
+            &[BindGroupEntry {
+                binding: 3,
+                resource: BindingResource::TextureView(&display.texture_view),
+            }],
+        ),
+        paint,
+    });
+}
+
+#[derive(Resource)]
+pub struct ContinuousPipeline {
+    texture_bind_group_layout: BindGroupLayout,
+    seed_bind_group_layout: BindGroupLayout,
+    paint_bind_group_layout: BindGroupLayout,
+    colour_bind_group_layout: BindGroupLayout,
+    init_pipeline: CachedComputePipelineId,
+    paint_pipeline: CachedComputePipelineId,
+    update_pipeline: CachedComputePipelineId,
+    colour_pipeline: CachedComputePipelineId,
+}
+
+impl FromWorld for ContinuousPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+        let storage_texture = |binding: u32, format: TextureFormat| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::COMPUTE,
+            ty: BindingType::StorageTexture {
+                access: StorageTextureAccess::WriteOnly,
+                format,
+                view_dimension: TextureViewDimension::D2,
+            },
+            count: None,
+        };
+        let buffer = |binding: u32, ty: BufferBindingType, min_size| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::COMPUTE,
+            ty: BindingType::Buffer {
+                ty,
+                has_dynamic_offset: false,
+                min_binding_size: Some(min_size),
+            },
+            count: None,
+        };
+        let layout = |entries: &[BindGroupLayoutEntry]| {
+            render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: None,
+                entries,
+            })
+        };
+
+        let texture_bind_group_layout = layout(&[
+            BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::COMPUTE,
+                ty: BindingType::Texture {
+                    sample_type: TextureSampleType::Float { filterable: false },
+                    view_dimension: TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            storage_texture(1, TextureFormat::Rgba32Float),
+            buffer(
+                2,
+                BufferBindingType::Uniform,
+                ContinuousAutomatonUniform::min_size(),
+            ),
+            buffer(
+                3,
+                BufferBindingType::Uniform,
+                GameOfLifeGridUniform::min_size(),
+            ),
+            buffer(
+                4,
+                BufferBindingType::Storage { read_only: true },
+                ContinuousKernelStorage::min_size(),
+            ),
+        ]);
+        let seed_bind_group_layout = layout(&[buffer(
+            1,
+            BufferBindingType::Uniform,
+            GameOfLifeSeedUniform::min_size(),
+        )]);
+        let paint_bind_group_layout = layout(&[buffer(
+            2,
+            BufferBindingType::Storage { read_only: true },
+            GameOfLifePaintStorage::min_size(),
+        )]);
+        let colour_bind_group_layout = layout(&[storage_texture(3, TextureFormat::Rgba8Unorm)]);
+
+        let shader = world
+            .resource::<AssetServer>()
+            .load("shaders/compute/continuous.wgsl");
+        let mut pipeline_cache = world.resource_mut::<PipelineCache>();
+        let mut queue = |layout: &[&BindGroupLayout], entry_point: &'static str| {
+            pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
+                label: None,
+                layout: Some(layout.iter().map(|&layout| layout.clone()).collect()),
+                shader: shader.clone(),
+                shader_defs: vec![],
+                entry_point: Cow::from(entry_point),
+            })
+        };
+        let texture = &texture_bind_group_layout;
+        let init_pipeline = queue(&[texture, &seed_bind_group_layout], "init");
+        let paint_pipeline = queue(&[texture, &paint_bind_group_layout], "paint");
+        let update_pipeline = queue(&[texture], "update");
+        let colour_pipeline = queue(&[texture, &colour_bind_group_layout], "colour");
+
+        ContinuousPipeline {
+            texture_bind_group_layout,
+            seed_bind_group_layout,
+            paint_bind_group_layout,
+            colour_bind_group_layout,
+            init_pipeline,
+            paint_pipeline,
+            update_pipeline,
+            colour_pipeline,
+        }
+    }
+}
+
+struct ContinuousNode {
+    state: GameOfLifeState,
+    /// The seed last initialized from.
+    seed_version: u32,
+}
+
+impl Default for ContinuousNode {
+    fn default() -> Self {
+        Self {
+            state: GameOfLifeState::Loading,
+            seed_version: 0,
+        }
+    }
+}
+
+impl render_graph::Node for ContinuousNode {
+    fn update(&mut self, world: &mut World) {
+        let pipeline = world.resource::<ContinuousPipeline>();
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let seed_version = world
+            .get_resource::<ContinuousBindGroups>()
+            .map(|bind_groups| bind_groups.seed_version);
+        let loaded = |id| {
+            matches!(
+                pipeline_cache.get_compute_pipeline_state(id),
+                CachedPipelineState::Ok(_)
+            )
+        };
+
+        match self.state {
+            GameOfLifeState::Loading if loaded(pipeline.init_pipeline) => {
+                self.state = GameOfLifeState::Init;
+            }
+            GameOfLifeState::Init if loaded(pipeline.update_pipeline) => {
+                self.state = GameOfLifeState::Update;
+            }
+            GameOfLifeState::Update
+                if seed_version.is_some_and(|version| version != self.seed_version) =>
+            {
+                self.state = GameOfLifeState::Init;
+            }
+            _ => {}
+        }
+        if let (GameOfLifeState::Init, Some(version)) = (&self.state, seed_version) {
+            self.seed_version = version;
+        }
+    }
+
+    fn run(
+        &self,
+        _graph: &mut render_graph::RenderGraphContext,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), render_graph::NodeRunError> {
+        let Some(bind_groups) = world.get_resource::<ContinuousBindGroups>() else {
+            return Ok(());
+        };
+        let game_of_life_image = world.resource::<GameOfLifeImage>();
+        let workgroups = (world.resource::<GameOfLifeGrid>().texture_size() + WORKGROUP_SIZE - 1)
+            / WORKGROUP_SIZE;
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let pipeline = world.resource::<ContinuousPipeline>();
+
+        let update_pipeline = match self.state {
+            GameOfLifeState::Loading => return Ok(()),
+            _ => pipeline_cache.get_compute_pipeline(pipeline.update_pipeline),
+        };
+        let generations = match update_pipeline {
+            Some(_) => game_of_life_image.generations,
+            None => 0,
+        };
+        // As in the Game of Life, passes alternate so the last one writes `current`.
+        let input = |pass: u32| game_of_life_image.current ^ ((generations - pass) & 1) as usize;
+
+        let mut pass = render_context
+            .command_encoder
+            .begin_compute_pass(&ComputePassDescriptor::default());
+
+        if let GameOfLifeState::Init = self.state {
+            let init_pipeline = pipeline_cache
+                .get_compute_pipeline(pipeline.init_pipeline)
+                .unwrap();
+            pass.set_pipeline(init_pipeline);
+            pass.set_bind_group(0, &bind_groups.images[1 - input(0)], &[]);
+            pass.set_bind_group(1, &bind_groups.seed, &[]);
+            pass.dispatch_workgroups(workgroups.x, workgroups.y, 1);
+        }
+
+        let paint_pipeline = pipeline_cache.get_compute_pipeline(pipeline.paint_pipeline);
+        if let (Some(paint_pipeline), Some((paint, paint_workgroups))) =
+            (paint_pipeline, &bind_groups.paint)
+        {
+            pass.set_pipeline(paint_pipeline);
+            pass.set_bind_group(0, &bind_groups.images[1 - input(0)], &[]);
+            pass.set_bind_group(1, paint, &[]);
+            pass.dispatch_workgroups(paint_workgroups.x, paint_workgroups.y, 1);
+        }
+
+        if let Some(update_pipeline) = update_pipeline {
+            pass.set_pipeline(update_pipeline);
+            for generation in 0..generations {
+                pass.set_bind_group(0, &bind_groups.images[input(generation)], &[]);
+                pass.dispatch_workgroups(workgroups.x, workgroups.y, 1);
+            }
+        }
+
+        if let Some(colour_pipeline) = pipeline_cache.get_compute_pipeline(pipeline.colour_pipeline)
+        {
+            pass.set_pipeline(colour_pipeline);
+            pass.set_bind_group(0, &bind_groups.images[game_of_life_image.current], &[]);
+            pass.set_bind_group(1, &bind_groups.colour, &[]);
+            pass.dispatch_workgroups(workgroups.x, workgroups.y, 1);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lenia_kernels_sum_to_one() {
+        let too_wide = Lenia {
+            radius: MAX_KERNEL_RADIUS + 10,
+            ..Lenia::orbium()
+        };
+        for lenia in [Lenia::orbium(), Lenia::hydrogeminium(), too_wide] {
+            let automaton = ContinuousAutomaton::Lenia(lenia);
+            let kernel = automaton.kernel();
+            let side = 2 * automaton.radius() as usize + 1;
+
+            assert_eq!(kernel.len(), side * side);
+            assert!((kernel.iter().sum::<f32>() - 1.0).abs() < 1e-5);
+            assert!(kernel.iter().all(|&weight| weight >= 0.0));
+        }
+    }
+
+    #[test]
+    fn lenia_grows_fastest_at_mu() {
+        let lenia = Lenia::orbium();
+        assert_eq!(lenia.growth(lenia.mu), 1.0);
+        assert!(lenia.growth(lenia.mu + lenia.sigma) < 1.0);
+        assert!((lenia.growth(1.0) + 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn gray_scott_laplacian_sums_to_zero() {
+        let kernel = ContinuousAutomaton::GrayScott(GrayScott::mitosis()).kernel();
+        assert!(kernel.iter().sum::<f32>().abs() < 1e-6);
+    }
+}
@@ -0,0 +1,204 @@
+//! Pausing, stepping, speed, reset and clear for the compute simulation.
+//!
+//! Space pauses, `.` steps one generation, `+` and `-` change speed, R resets with the next seed
+//! and C clears. Everything can also be edited on the `GameOfLifeControls` resource in the debug
+//! UI, where the action flags act as buttons.
+
+use bevy::prelude::*;
+
+use super::{GameOfLifeImage, GameOfLifeSeed};
+
+const PAUSE_KEY: KeyCode = KeyCode::Space;
+const STEP_KEY: KeyCode = KeyCode::Period;
+const FASTER_KEYS: [KeyCode; 2] = [KeyCode::Equals, KeyCode::NumpadAdd];
+const SLOWER_KEYS: [KeyCode; 2] = [KeyCode::Minus, KeyCode::NumpadSubtract];
+const RESET_KEY: KeyCode = KeyCode::R;
+const CLEAR_KEY: KeyCode = KeyCode::C;
+
+/// Generations are capped per frame so a high tick rate after a long frame can't stall the GPU.
+const MAX_GENERATIONS_PER_FRAME: u32 = 64;
+
+#[derive(Resource, Reflect, Debug, Clone)]
+#[reflect(Resource)]
+pub struct GameOfLifeControls {
+    pub running: bool,
+    pub speed: SimulationSpeed,
+    /// Runs one generation, even while paused.
+    pub step: bool,
+    /// Restarts from the initialization with `seed`, restamping any
+    /// [`GameOfLifeStamp`](super::GameOfLifeStamp).
+    pub reset: bool,
+    /// Kills every cell.
+    pub clear: bool,
+    /// Seeds the random initialization on reset.
+    pub seed: u32,
+}
+
+impl Default for GameOfLifeControls {
+    fn default() -> Self {
+        Self {
+            running: true,
+            speed: SimulationSpeed::default(),
+            step: false,
+            reset: false,
+            clear: false,
+            seed: 0,
+        }
+    }
+}
+
+#[derive(Reflect, FromReflect, Debug, Clone, Copy, PartialEq)]
+pub enum SimulationSpeed {
+    /// Generations run every frame. `0`, which can be set from the debug UI, runs none; pausing is
+    /// [`GameOfLifeControls::running`].
+    StepsPerFrame(u32),
+    /// Generations per second independent of the frame rate.
+    TicksPerSecond(f32),
+}
+
+impl Default for SimulationSpeed {
+    fn default() -> Self {
+        SimulationSpeed::StepsPerFrame(1)
+    }
+}
+
+impl SimulationSpeed {
+    /// Doubles the speed, starting from one step per frame if it was zero.
+    fn faster(self) -> Self {
+        match self {
+            SimulationSpeed::StepsPerFrame(steps) => SimulationSpeed::StepsPerFrame(
+                steps.saturating_mul(2).clamp(1, MAX_GENERATIONS_PER_FRAME),
+            ),
+            SimulationSpeed::TicksPerSecond(rate) => SimulationSpeed::TicksPerSecond(rate * 2.0),
+        }
+    }
+
+    /// Below one step per frame, switches to a fixed tick rate.
+    fn slower(self) -> Self {
+        match self {
+            SimulationSpeed::StepsPerFrame(steps) if steps > 1 => {
+                SimulationSpeed::StepsPerFrame(steps / 2)
+            }
+            SimulationSpeed::StepsPerFrame(_) => SimulationSpeed::TicksPerSecond(30.0),
+            SimulationSpeed::TicksPerSecond(rate) => {
+                SimulationSpeed::TicksPerSecond((rate / 2.0).max(0.5))
+            }
+        }
+    }
+}
+
+pub(super) fn game_of_life_keyboard(
+    keys: Res<Input<KeyCode>>,
+    mut controls: ResMut<GameOfLifeControls>,
+) {
+    if keys.just_pressed(PAUSE_KEY) {
+        controls.running = !controls.running;
+    }
+    if keys.just_pressed(STEP_KEY) {
+        controls.running = false;
+        controls.step = true;
+    }
+    if keys.any_just_pressed(FASTER_KEYS) {
+        controls.speed = controls.speed.faster();
+    }
+    if keys.any_just_pressed(SLOWER_KEYS) {
+        controls.speed = controls.speed.slower();
+    }
+    if keys.just_pressed(RESET_KEY) {
+        controls.seed = controls.seed.wrapping_add(1);
+        controls.reset = true;
+    }
+    if keys.just_pressed(CLEAR_KEY) {
+        controls.clear = true;
+    }
+}
+
+/// Decides how many generations the compute pass runs this frame, and which texture the last of
+/// them is written to.
+pub(super) fn advance_game_of_life(
+    time: Res<Time>,
+    mut controls: ResMut<GameOfLifeControls>,
+    mut game_of_life_image: ResMut<GameOfLifeImage>,
+    mut seed: ResMut<GameOfLifeSeed>,
+    mut ticks: Local<f32>,
+) {
+    if controls.clear {
+        controls.clear = false;
+        seed.clear = true;
+        seed.version += 1;
+    }
+    if controls.reset {
+        controls.reset = false;
+        seed.clear = false;
+        seed.seed = controls.seed;
+        seed.version += 1;
+    }
+
+    let mut generations = 0;
+    if controls.running {
+        generations = match controls.speed {
+            SimulationSpeed::StepsPerFrame(steps) => steps,
+            SimulationSpeed::TicksPerSecond(rate) => {
+                *ticks += time.delta_seconds() * rate.max(0.0);
+                let whole = ticks.floor();
+                *ticks -= whole;
+                whole as u32
+            }
+        };
+    } else {
+        *ticks = 0.0;
+    }
+    if controls.step {
+        controls.step = false;
+        generations += 1;
+    }
+
+    let generations = generations.min(MAX_GENERATIONS_PER_FRAME);
+    game_of_life_image.generations = generations;
+    game_of_life_image.current ^= generations as usize & 1;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn faster_doubles_the_steps_per_frame() {
+        assert_eq!(
+            SimulationSpeed::StepsPerFrame(2).faster(),
+            SimulationSpeed::StepsPerFrame(4)
+        );
+        assert_eq!(
+            SimulationSpeed::StepsPerFrame(MAX_GENERATIONS_PER_FRAME).faster(),
+            SimulationSpeed::StepsPerFrame(MAX_GENERATIONS_PER_FRAME)
+        );
+        assert_eq!(
+            SimulationSpeed::StepsPerFrame(u32::MAX).faster(),
+            SimulationSpeed::StepsPerFrame(MAX_GENERATIONS_PER_FRAME)
+        );
+    }
+
+    #[test]
+    fn faster_starts_from_zero_steps() {
+        assert_eq!(
+            SimulationSpeed::StepsPerFrame(0).faster(),
+            SimulationSpeed::StepsPerFrame(1)
+        );
+    }
+
+    #[test]
+    fn slower_switches_to_ticks_below_one_step() {
+        assert_eq!(
+            SimulationSpeed::StepsPerFrame(4).slower(),
+            SimulationSpeed::StepsPerFrame(2)
+        );
+        assert_eq!(
+            SimulationSpeed::StepsPerFrame(1).slower(),
+            SimulationSpeed::TicksPerSecond(30.0)
+        );
+        assert_eq!(
+            SimulationSpeed::TicksPerSecond(0.5).slower(),
+            SimulationSpeed::TicksPerSecond(0.5)
+        );
+    }
+}
@@ -0,0 +1,686 @@
+//! A CPU reference for the Game of Life compute shader, for running the automaton headless and
+//! checking the GPU's output against.
+//!
+//! [`LifeGrid`] runs Life-like rules with rows packed 64 cells to a word, where each generation
+//! counts the neighbours of a whole word at once with bit-sliced adders. [`AutomatonGrid`] runs
+//! any [`CellularAutomaton`] a cell at a time, and [`ContinuousGrid`] any
+//! [`ContinuousAutomaton`].
+
+use bevy::prelude::{IVec2, Vec2};
+
+use super::{Boundary, CellularAutomaton, ContinuousAutomaton, GameOfLifeRules, WORKGROUP_SIZE};
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// A grid of cells stepped the way `game_of_life.wgsl` steps its texture under
+/// [`CellularAutomaton::life`]: neighbours beyond the edges follow the grid's [`Boundary`], and
+/// [`LifeGrid::seeded`] reproduces the shader's `init`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LifeGrid {
+    width: usize,
+    height: usize,
+    words_per_row: usize,
+    boundary: Boundary,
+    /// Bit `i` of word `w` in a row is the cell at `x = w * 64 + i`. Bits past `width` stay clear.
+    words: Vec<u64>,
+}
+
+impl LifeGrid {
+    /// A grid with every cell dead, and a dead boundary.
+    pub fn new(width: usize, height: usize) -> Self {
+        let words_per_row = width.div_ceil(WORD_BITS);
+        Self {
+            width,
+            height,
+            words_per_row,
+            boundary: Boundary::default(),
+            words: vec![0; words_per_row * height],
+        }
+    }
+
+    /// The generation the shader's `init` writes for `seed`: a cell is alive when
+    /// [`random_float`] of its index is above `0.9`. As in the shader, the index's row stride is
+    /// the number of workgroups along x rather than the width.
+    pub fn seeded(width: usize, height: usize, seed: u32) -> Self {
+        let mut grid = Self::new(width, height);
+        let stride = (width as u32).div_ceil(WORKGROUP_SIZE);
+        let offset = seed.wrapping_mul(2654435769);
+        for y in 0..height {
+            for x in 0..width {
+                let index = (y as u32)
+                    .wrapping_mul(stride)
+                    .wrapping_add(x as u32)
+                    .wrapping_add(offset);
+                grid.set(x, y, random_float(index) > 0.9);
+            }
+        }
+        grid
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn boundary(&self) -> Boundary {
+        self.boundary
+    }
+
+    pub fn with_boundary(mut self, boundary: Boundary) -> Self {
+        self.boundary = boundary;
+        self
+    }
+
+    /// Whether the cell is alive. Cells outside the grid are dead.
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        if x >= self.width || y >= self.height {
+            return false;
+        }
+        self.words[y * self.words_per_row + x / WORD_BITS] >> (x % WORD_BITS) & 1 == 1
+    }
+
+    /// Sets a cell, ignoring cells outside the grid.
+    pub fn set(&mut self, x: usize, y: usize, alive: bool) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        let word = &mut self.words[y * self.words_per_row + x / WORD_BITS];
+        let bit = 1 << (x % WORD_BITS);
+        if alive {
+            *word |= bit;
+        } else {
+            *word &= !bit;
+        }
+    }
+
+    /// The number of live cells.
+    pub fn population(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    /// Advances one generation under `rules`.
+    pub fn step(&mut self, rules: &GameOfLifeRules) {
+        if self.width == 0 || self.height == 0 {
+            return;
+        }
+        let born = count_masks(rules.birth);
+        let survives = count_masks(rules.survival);
+        let row = |y: i64| match self.boundary.resolve(y, self.height as i64) {
+            Some(y) => {
+                let y = y as usize;
+                &self.words[y * self.words_per_row..(y + 1) * self.words_per_row]
+            }
+            None => &[][..],
+        };
+
+        let mut next = vec![0; self.words.len()];
+        for y in 0..self.height {
+            let (above, current, below) = (row(y as i64 - 1), row(y as i64), row(y as i64 + 1));
+            for w in 0..self.words_per_row {
+                let [a_west, a, a_east] = self.neighbours(above, w);
+                let [west, cell, east] = self.neighbours(current, w);
+                let [b_west, b, b_east] = self.neighbours(below, w);
+                let count = neighbour_count([a_west, a, a_east, west, east, b_west, b, b_east]);
+
+                next[y * self.words_per_row + w] =
+                    (cell & select(&count, &survives)) | (!cell & select(&count, &born));
+            }
+            if self.words_per_row > 0 {
+                next[(y + 1) * self.words_per_row - 1] &= self.last_word_mask();
+            }
+        }
+        self.words = next;
+    }
+
+    /// Advances `generations` generations under `rules`.
+    pub fn run(&mut self, rules: &GameOfLifeRules, generations: usize) {
+        for _ in 0..generations {
+            self.step(rules);
+        }
+    }
+
+    /// Reads cells from `Rgba8Unorm` texels as the shader does: alive when the red channel holds
+    /// state `1`.
+    pub fn from_rgba8(width: usize, height: usize, data: &[u8]) -> Self {
+        let mut grid = Self::new(width, height);
+        for (i, texel) in data.chunks_exact(4).take(width * height).enumerate() {
+            grid.set(i % width, i / width, texel[0] == 1);
+        }
+        grid
+    }
+
+    /// The `Rgba8Unorm` texels the shader writes for this generation.
+    pub fn to_rgba8(&self) -> Vec<u8> {
+        (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| (x, y)))
+            .flat_map(|(x, y)| [self.get(x, y) as u8, 0, 0, 0])
+            .collect()
+    }
+
+    /// [`shifted`], with the cells past either edge of the grid filled in from the boundary.
+    fn neighbours(&self, row: &[u64], w: usize) -> [u64; 3] {
+        let [mut west, cell, mut east] = shifted(row, w);
+        let boundary_cell = |x: i64| {
+            self.boundary
+                .resolve(x, self.width as i64)
+                .and_then(|x| row.get(x as usize / WORD_BITS).map(|word| (word, x)))
+                .map_or(0, |(word, x)| word >> (x as usize % WORD_BITS) & 1)
+        };
+        if w == 0 {
+            west |= boundary_cell(-1);
+        }
+        if w == self.words_per_row - 1 {
+            east |= boundary_cell(self.width as i64) << ((self.width - 1) % WORD_BITS);
+        }
+        [west, cell, east]
+    }
+
+    fn last_word_mask(&self) -> u64 {
+        match self.width % WORD_BITS {
+            0 => !0,
+            bits => (1 << bits) - 1,
+        }
+    }
+}
+
+/// A grid of cells in any state, stepped the way `game_of_life.wgsl` steps its texture under a
+/// [`CellularAutomaton`]. Each cell counts its neighbours one by one, so large neighbourhoods are
+/// slow.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AutomatonGrid {
+    width: usize,
+    height: usize,
+    boundary: Boundary,
+    states: Vec<u8>,
+}
+
+impl AutomatonGrid {
+    /// A grid with every cell in state `0`, and a dead boundary.
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            boundary: Boundary::default(),
+            states: vec![0; width * height],
+        }
+    }
+
+    /// The generation the shader's `init` writes for `seed`, as [`LifeGrid::seeded`] with live
+    /// cells in state `1`.
+    pub fn seeded(width: usize, height: usize, seed: u32) -> Self {
+        Self::from(&LifeGrid::seeded(width, height, seed))
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn boundary(&self) -> Boundary {
+        self.boundary
+    }
+
+    pub fn with_boundary(mut self, boundary: Boundary) -> Self {
+        self.boundary = boundary;
+        self
+    }
+
+    /// The cell's state. Cells outside the grid are in state `0`.
+    pub fn get(&self, x: usize, y: usize) -> u8 {
+        if x >= self.width || y >= self.height {
+            return 0;
+        }
+        self.states[y * self.width + x]
+    }
+
+    /// Sets a cell's state, ignoring cells outside the grid.
+    pub fn set(&mut self, x: usize, y: usize, state: u8) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        self.states[y * self.width + x] = state;
+    }
+
+    /// The number of cells in a state other than `0`.
+    pub fn population(&self) -> usize {
+        self.states.iter().filter(|&&state| state != 0).count()
+    }
+
+    /// Advances one generation under `automaton`.
+    pub fn step(&mut self, automaton: &CellularAutomaton) {
+        let offsets = automaton.counted_offsets();
+        let alive = |x: usize, y: usize, offset: IVec2| {
+            let x = self
+                .boundary
+                .resolve(x as i64 + offset.x as i64, self.width as i64);
+            let y = self
+                .boundary
+                .resolve(y as i64 + offset.y as i64, self.height as i64);
+            match (x, y) {
+                (Some(x), Some(y)) => self.get(x as usize, y as usize) == 1,
+                _ => false,
+            }
+        };
+
+        let mut next = vec![0; self.states.len()];
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let count = offsets
+                    .iter()
+                    .filter(|&&offset| alive(x, y, offset))
+                    .count();
+                let state = automaton.next(self.get(x, y) as u32, count as u32);
+                next[y * self.width + x] = state as u8;
+            }
+        }
+        self.states = next;
+    }
+
+    /// Advances `generations` generations under `automaton`.
+    pub fn run(&mut self, automaton: &CellularAutomaton, generations: usize) {
+        for _ in 0..generations {
+            self.step(automaton);
+        }
+    }
+
+    /// Reads states from the red channel of `Rgba8Unorm` texels, as the shader does.
+    pub fn from_rgba8(width: usize, height: usize, data: &[u8]) -> Self {
+        let mut grid = Self::new(width, height);
+        for (i, texel) in data.chunks_exact(4).take(width * height).enumerate() {
+            grid.set(i % width, i / width, texel[0]);
+        }
+        grid
+    }
+
+    /// The `Rgba8Unorm` texels the shader writes for this generation.
+    pub fn to_rgba8(&self) -> Vec<u8> {
+        self.states
+            .iter()
+            .flat_map(|&state| [state, 0, 0, 0])
+            .collect()
+    }
+
+    /// The `Rgba8Unorm` texels the `colour` entry point displays for this generation.
+    pub fn to_display_rgba8(&self, automaton: &CellularAutomaton) -> Vec<u8> {
+        self.states
+            .iter()
+            .flat_map(|&state| {
+                automaton
+                    .colour(state as u32)
+                    .as_linear_rgba_f32()
+                    .map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8)
+            })
+            .collect()
+    }
+}
+
+impl From<&LifeGrid> for AutomatonGrid {
+    fn from(life: &LifeGrid) -> Self {
+        let mut grid = Self::new(life.width, life.height).with_boundary(life.boundary);
+        for y in 0..life.height {
+            for x in 0..life.width {
+                grid.set(x, y, life.get(x, y) as u8);
+            }
+        }
+        grid
+    }
+}
+
+/// A grid of real-valued cells, stepped the way `continuous.wgsl` steps its texture under a
+/// [`ContinuousAutomaton`], to within floating-point rounding. Each cell convolves its
+/// neighbourhood one weight at a time, so Lenia's large kernels are slow.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContinuousGrid {
+    width: usize,
+    height: usize,
+    boundary: Boundary,
+    cells: Vec<Vec2>,
+}
+
+impl ContinuousGrid {
+    /// A grid of [`ContinuousAutomaton::empty`] cells, and a dead boundary.
+    pub fn new(width: usize, height: usize, automaton: &ContinuousAutomaton) -> Self {
+        Self {
+            width,
+            height,
+            boundary: Boundary::default(),
+            cells: vec![automaton.empty(); width * height],
+        }
+    }
+
+    /// The generation the shader's `init` writes for `seed`.
+    pub fn seeded(width: usize, height: usize, seed: u32, automaton: &ContinuousAutomaton) -> Self {
+        let mut grid = Self::new(width, height, automaton);
+        for y in 0..height {
+            for x in 0..width {
+                let cell = automaton.seeded_cell(x as u32, y as u32, width as u32, seed);
+                grid.set(x, y, cell);
+            }
+        }
+        grid
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn boundary(&self) -> Boundary {
+        self.boundary
+    }
+
+    pub fn with_boundary(mut self, boundary: Boundary) -> Self {
+        self.boundary = boundary;
+        self
+    }
+
+    /// The cell's value, or zero outside the grid.
+    pub fn get(&self, x: usize, y: usize) -> Vec2 {
+        if x >= self.width || y >= self.height {
+            return Vec2::ZERO;
+        }
+        self.cells[y * self.width + x]
+    }
+
+    /// Sets a cell's value, ignoring cells outside the grid.
+    pub fn set(&mut self, x: usize, y: usize, cell: Vec2) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        self.cells[y * self.width + x] = cell;
+    }
+
+    /// The sum of every cell, Lenia's mass or Gray-Scott's total `u` and `v`.
+    pub fn total(&self) -> Vec2 {
+        self.cells.iter().sum()
+    }
+
+    /// Advances one generation under `automaton`. Dead cells past the edge are empty.
+    pub fn step(&mut self, automaton: &ContinuousAutomaton) {
+        let radius = automaton.radius() as i32;
+        let side = 2 * radius as usize + 1;
+        let weights: Vec<_> = automaton
+            .kernel()
+            .into_iter()
+            .enumerate()
+            .filter(|&(_, weight)| weight != 0.0)
+            .map(|(i, weight)| {
+                let offset = IVec2::new((i % side) as i32, (i / side) as i32) - radius;
+                (offset, weight)
+            })
+            .collect();
+        let neighbour = |x: usize, y: usize, offset: IVec2| {
+            let x = self
+                .boundary
+                .resolve(x as i64 + offset.x as i64, self.width as i64);
+            let y = self
+                .boundary
+                .resolve(y as i64 + offset.y as i64, self.height as i64);
+            match (x, y) {
+                (Some(x), Some(y)) => self.get(x as usize, y as usize),
+                _ => automaton.empty(),
+            }
+        };
+
+        let mut next = vec![Vec2::ZERO; self.cells.len()];
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let convolution = weights.iter().fold(Vec2::ZERO, |sum, &(offset, weight)| {
+                    sum + weight * neighbour(x, y, offset)
+                });
+                next[y * self.width + x] = automaton.next(self.get(x, y), convolution);
+            }
+        }
+        self.cells = next;
+    }
+
+    /// Advances `generations` generations under `automaton`.
+    pub fn run(&mut self, automaton: &ContinuousAutomaton, generations: usize) {
+        for _ in 0..generations {
+            self.step(automaton);
+        }
+    }
+
+    /// Reads cells from the red and green channels of `Rgba32Float` texels, as the shader does.
+    pub fn from_rgba32f(
+        width: usize,
+        height: usize,
+        data: &[f32],
+        automaton: &ContinuousAutomaton,
+    ) -> Self {
+        let mut grid = Self::new(width, height, automaton);
+        for (i, texel) in data.chunks_exact(4).take(width * height).enumerate() {
+            grid.set(i % width, i / width, Vec2::new(texel[0], texel[1]));
+        }
+        grid
+    }
+
+    /// The `Rgba32Float` texels the shader writes for this generation.
+    pub fn to_rgba32f(&self) -> Vec<f32> {
+        self.cells
+            .iter()
+            .flat_map(|cell| [cell.x, cell.y, 0.0, 0.0])
+            .collect()
+    }
+}
+
+/// The shader's `hash`.
+pub fn hash(value: u32) -> u32 {
+    let mut state = value;
+    state ^= 2747636419;
+    state = state.wrapping_mul(2654435769);
+    state ^= state >> 16;
+    state = state.wrapping_mul(2654435769);
+    state ^= state >> 16;
+    state = state.wrapping_mul(2654435769);
+    state
+}
+
+/// The shader's `randomFloat`, in `0.0..=1.0`.
+pub fn random_float(value: u32) -> f32 {
+    hash(value) as f32 / 4294967295.0
+}
+
+/// Word `w` of `row`, and the same word shifted so each bit holds its west and east neighbour.
+/// Missing rows and words are dead.
+fn shifted(row: &[u64], w: usize) -> [u64; 3] {
+    let word = |w: Option<usize>| w.and_then(|w| row.get(w)).copied().unwrap_or(0);
+    let (previous, current, next) = (word(w.checked_sub(1)), word(Some(w)), word(Some(w + 1)));
+    [
+        current << 1 | previous >> (WORD_BITS - 1),
+        current,
+        current >> 1 | next << (WORD_BITS - 1),
+    ]
+}
+
+/// Adds eight one-bit-per-cell words into a four bit count per cell, least significant bit first.
+fn neighbour_count(n: [u64; 8]) -> [u64; 4] {
+    let full_add = |a: u64, b: u64, c: u64| (a ^ b ^ c, (a & b) | (c & (a ^ b)));
+
+    let (ones_a, twos_a) = full_add(n[0], n[1], n[2]);
+    let (ones_b, twos_b) = full_add(n[3], n[4], n[5]);
+    let (ones_c, twos_c) = (n[6] ^ n[7], n[6] & n[7]);
+    let (bit0, twos_d) = full_add(ones_a, ones_b, ones_c);
+
+    let (twos, fours_a) = full_add(twos_a, twos_b, twos_c);
+    let (bit1, fours_b) = (twos ^ twos_d, twos & twos_d);
+
+    [bit0, bit1, fours_a ^ fours_b, fours_a & fours_b]
+}
+
+/// The neighbour counts whose bit is set in a birth or survival mask.
+fn count_masks(mask: u16) -> Vec<u32> {
+    (0..=8).filter(|n| mask >> n & 1 == 1).collect()
+}
+
+/// Bits set where the per-cell `count` is one of `counts`.
+fn select(count: &[u64; 4], counts: &[u32]) -> u64 {
+    counts.iter().fold(0, |selected, &n| {
+        selected
+            | (0..4).fold(!0, |matches, bit| {
+                matches
+                    & if n >> bit & 1 == 1 {
+                        count[bit]
+                    } else {
+                        !count[bit]
+                    }
+            })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::{GrayScott, Lenia},
+        *,
+    };
+
+    const GLIDER: [(usize, usize); 5] = [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)];
+
+    fn with_cells(
+        width: usize,
+        height: usize,
+        cells: &[(usize, usize)],
+        at: (usize, usize),
+    ) -> LifeGrid {
+        let mut grid = LifeGrid::new(width, height);
+        for &(x, y) in cells {
+            grid.set(at.0 + x, at.1 + y, true);
+        }
+        grid
+    }
+
+    #[test]
+    fn blinker_has_period_two() {
+        let horizontal = with_cells(5, 5, &[(0, 0), (1, 0), (2, 0)], (1, 2));
+        let vertical = with_cells(5, 5, &[(0, 0), (0, 1), (0, 2)], (2, 1));
+
+        let mut grid = horizontal.clone();
+        grid.step(&GameOfLifeRules::CONWAY);
+        assert_eq!(grid, vertical);
+        grid.step(&GameOfLifeRules::CONWAY);
+        assert_eq!(grid, horizontal);
+    }
+
+    #[test]
+    fn block_is_still() {
+        let block = with_cells(4, 4, &[(0, 0), (1, 0), (0, 1), (1, 1)], (1, 1));
+
+        let mut grid = block.clone();
+        grid.run(&GameOfLifeRules::CONWAY, 5);
+        assert_eq!(grid, block);
+    }
+
+    #[test]
+    fn gliders_cross_word_boundaries() {
+        // Starting just west of the first word boundary, at widths that aren't multiples of 64.
+        for width in [70, 100, 130] {
+            let mut grid = with_cells(width, 24, &GLIDER, (58, 2));
+            for moved in 1..=8 {
+                grid.run(&GameOfLifeRules::CONWAY, 4);
+                let expected = with_cells(width, 24, &GLIDER, (58 + moved, 2 + moved));
+                assert_eq!(grid, expected, "width {} after {} moves", width, moved);
+            }
+        }
+    }
+
+    #[test]
+    fn life_grid_matches_automaton_grid() {
+        let boundaries = [Boundary::Dead, Boundary::Toroidal, Boundary::Mirrored];
+        let rules = [
+            GameOfLifeRules::CONWAY,
+            GameOfLifeRules::HIGHLIFE,
+            GameOfLifeRules::DAY_AND_NIGHT,
+        ];
+        for boundary in boundaries {
+            for rules in rules {
+                let automaton = CellularAutomaton::life(rules);
+                let mut life = LifeGrid::seeded(70, 20, 1).with_boundary(boundary);
+                let mut cells = AutomatonGrid::from(&life);
+                for generation in 0..10 {
+                    life.step(&rules);
+                    cells.step(&automaton);
+                    assert_eq!(
+                        AutomatonGrid::from(&life),
+                        cells,
+                        "{:?} {} generation {}",
+                        boundary,
+                        rules,
+                        generation
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn seeding_is_deterministic() {
+        let grid = LifeGrid::seeded(70, 40, 7);
+        assert_eq!(grid, LifeGrid::seeded(70, 40, 7));
+        assert_ne!(grid, LifeGrid::seeded(70, 40, 8));
+
+        // About a tenth of the cells start alive.
+        let population = grid.population() as f32 / (70.0 * 40.0);
+        assert!((0.05..0.15).contains(&population), "{}", population);
+    }
+
+    #[test]
+    fn empty_continuous_grids_stay_empty() {
+        let automata = [
+            ContinuousAutomaton::Lenia(Lenia::orbium()),
+            ContinuousAutomaton::GrayScott(GrayScott::mitosis()),
+        ];
+        for automaton in &automata {
+            for boundary in [Boundary::Dead, Boundary::Toroidal, Boundary::Mirrored] {
+                let empty = ContinuousGrid::new(8, 8, automaton).with_boundary(boundary);
+                let mut grid = empty.clone();
+                grid.run(automaton, 10);
+                assert_eq!(grid, empty, "{:?}", boundary);
+            }
+        }
+    }
+
+    #[test]
+    fn continuous_grids_follow_their_boundary() {
+        let automaton = ContinuousAutomaton::GrayScott(GrayScott::mitosis());
+        // A single row with `v` only in its first cell, after one generation.
+        let v = |boundary: Boundary| {
+            let mut grid = ContinuousGrid::new(4, 1, &automaton).with_boundary(boundary);
+            grid.set(0, 0, automaton.filled(1.0));
+            grid.step(&automaton);
+            (0..4).map(|x| grid.get(x, 0).y).collect::<Vec<_>>()
+        };
+        let (dead, toroidal, mirrored) = (
+            v(Boundary::Dead),
+            v(Boundary::Toroidal),
+            v(Boundary::Mirrored),
+        );
+
+        // Only the wrapped grid carries `v` from the first cell to the last.
+        assert_eq!(dead[3], 0.0);
+        assert_eq!(mirrored[3], 0.0);
+        assert!(toroidal[3] > 0.0);
+        assert_eq!(toroidal[1], toroidal[3]);
+        // The mirrored cell neighbours itself, where the dead edge is empty.
+        assert!(mirrored[0] > dead[0]);
+        assert!(mirrored[0] > toroidal[0]);
+    }
+
+    #[test]
+    fn texels_round_trip() {
+        let grid = LifeGrid::seeded(70, 5, 2);
+        assert_eq!(LifeGrid::from_rgba8(70, 5, &grid.to_rgba8()), grid);
+    }
+}
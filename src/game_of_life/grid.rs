@@ -0,0 +1,154 @@
+//! The size of the simulated grid and what lies beyond its edges.
+
+use bevy::{
+    prelude::*,
+    render::{
+        extract_resource::ExtractResource,
+        render_resource::{Extent3d, ShaderType},
+    },
+};
+
+use super::{GameOfLifeImage, GameOfLifeSeed, GameOfLifeSprite};
+
+/// Textures larger than this along either axis aren't guaranteed by wgpu's default limits.
+pub(super) const MAX_SIZE: u32 = 8192;
+
+/// Changing `size` resizes the textures and restarts the simulation; changing `boundary` takes
+/// effect from the next generation.
+#[derive(Resource, Reflect, ExtractResource, Debug, Clone)]
+#[reflect(Resource)]
+pub struct GameOfLifeGrid {
+    /// In cells, which are also texels and sprite pixels.
+    pub size: UVec2,
+    pub boundary: Boundary,
+}
+
+impl Default for GameOfLifeGrid {
+    fn default() -> Self {
+        Self {
+            size: UVec2::new(1280, 720),
+            boundary: Boundary::default(),
+        }
+    }
+}
+
+impl GameOfLifeGrid {
+    /// `size` clamped to what a texture can hold.
+    pub fn texture_size(&self) -> UVec2 {
+        self.size.max(UVec2::ONE).min(UVec2::splat(MAX_SIZE))
+    }
+
+    pub(super) fn extent(&self) -> Extent3d {
+        let size = self.texture_size();
+        Extent3d {
+            width: size.x,
+            height: size.y,
+            depth_or_array_layers: 1,
+        }
+    }
+}
+
+/// What neighbours past the edge of the grid are.
+#[derive(Reflect, FromReflect, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Boundary {
+    /// Always dead.
+    #[default]
+    Dead,
+    /// The cells on the opposite edge, so the grid wraps around.
+    Toroidal,
+    /// The cells reflected across the edge, so each edge cell neighbours itself.
+    Mirrored,
+}
+
+impl Boundary {
+    /// The cell standing in for `coordinate` along an axis of `size` cells, or `None` if it is
+    /// dead.
+    pub fn resolve(self, coordinate: i64, size: i64) -> Option<i64> {
+        match self {
+            _ if (0..size).contains(&coordinate) => Some(coordinate),
+            Boundary::Dead => None,
+            Boundary::Toroidal => Some(coordinate.rem_euclid(size)),
+            Boundary::Mirrored if coordinate < 0 => Some((-coordinate - 1).min(size - 1)),
+            Boundary::Mirrored => Some((2 * size - coordinate - 1).max(0)),
+        }
+    }
+}
+
+/// [`GameOfLifeGrid`] as laid out in `game_of_life.wgsl`.
+#[derive(Clone, Default, ShaderType)]
+pub(super) struct GameOfLifeGridUniform {
+    pub boundary: u32,
+}
+
+impl From<&GameOfLifeGrid> for GameOfLifeGridUniform {
+    fn from(grid: &GameOfLifeGrid) -> Self {
+        Self {
+            boundary: grid.boundary as u32,
+        }
+    }
+}
+
+/// Resizes the textures and the sprite when the grid's size changes, restarting the simulation
+/// from its initialization.
+pub(super) fn resize_game_of_life(
+    grid: Res<GameOfLifeGrid>,
+    game_of_life_image: Res<GameOfLifeImage>,
+    mut images: ResMut<Assets<Image>>,
+    mut seed: ResMut<GameOfLifeSeed>,
+    mut sprites: Query<&mut Sprite, With<GameOfLifeSprite>>,
+) {
+    if !grid.is_changed() {
+        return;
+    }
+    let extent = grid.extent();
+    let mut resized = false;
+    let textures = game_of_life_image.textures.iter();
+    for texture in textures.chain([&game_of_life_image.display]) {
+        // Only borrow mutably when resizing, as that reuploads the texture.
+        let stale = images
+            .get(texture)
+            .is_some_and(|image| image.texture_descriptor.size != extent);
+        if stale {
+            images.get_mut(texture).unwrap().resize(extent);
+            resized = true;
+        }
+    }
+    if !resized {
+        return;
+    }
+
+    for mut sprite in &mut sprites {
+        sprite.custom_size = Some(grid.texture_size().as_vec2());
+    }
+    seed.version += 1;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_coordinates_past_each_edge() {
+        let coordinates = [-2, -1, 0, 2, 3, 4];
+        let resolved = |boundary: Boundary, size: i64| {
+            coordinates.map(|coordinate| boundary.resolve(coordinate, size))
+        };
+
+        assert_eq!(
+            resolved(Boundary::Dead, 3),
+            [None, None, Some(0), Some(2), None, None]
+        );
+        assert_eq!(
+            resolved(Boundary::Toroidal, 3),
+            [1, 2, 0, 2, 0, 1].map(Some)
+        );
+        assert_eq!(
+            resolved(Boundary::Mirrored, 3),
+            [1, 0, 0, 2, 2, 1].map(Some)
+        );
+        // A single cell is its own neighbour on every side, however far out.
+        for boundary in [Boundary::Toroidal, Boundary::Mirrored] {
+            assert_eq!(resolved(boundary, 1), [Some(0); 6]);
+        }
+    }
+}
@@ -0,0 +1,577 @@
+//! Hashlife: the Game of Life on an unbounded quadtree, with every distinct subtree stored once
+//! and its future memoized, so patterns with regular structure can be advanced by billions of
+//! generations.
+//!
+//! [`HashlifePlugin`] steps a [`Hashlife`] resource each frame and draws a [`HashlifeView`] of it
+//! into the image of the sprite from [`game_of_life_setup`](super::game_of_life_setup). It takes
+//! the place of the GPU compute plugin rather than running alongside it.
+
+use std::{error::Error, fmt};
+
+use bevy::{prelude::*, utils::HashMap};
+
+use super::{GameOfLifeGrid, GameOfLifeRules, GameOfLifeSprite, LifePattern};
+
+type NodeId = u32;
+
+const DEAD: NodeId = 0;
+const ALIVE: NodeId = 1;
+
+/// The quadtree is rebuilt from the live cells when it grows past this many nodes, dropping
+/// memoized results that are no longer reachable.
+const COLLECT_GARBAGE_AT: usize = 1 << 22;
+
+/// Coordinates past `2^MAX_LEVEL` would overflow `i64`.
+const MAX_LEVEL: u8 = 62;
+
+#[derive(Clone, Copy)]
+struct Node {
+    /// North-west, north-east, south-west and south-east quadrants, one level down.
+    children: [NodeId; 4],
+    /// The node covers `2^level` by `2^level` cells. Leaves are level `0`.
+    level: u8,
+    population: u64,
+}
+
+/// An unbounded Life-like universe. The root node is centered on the origin, covering
+/// `-2^(level - 1)..2^(level - 1)` on both axes, and grows as the pattern does.
+#[derive(Resource)]
+pub struct Hashlife {
+    rules: GameOfLifeRules,
+    nodes: Vec<Node>,
+    lookup: HashMap<[NodeId; 4], NodeId>,
+    /// The centered half of a node advanced `2^j` generations, keyed by the node and `j`.
+    successors: HashMap<(NodeId, u8), NodeId>,
+    /// The empty node at each level.
+    empty: Vec<NodeId>,
+    root: NodeId,
+    generation: u64,
+}
+
+/// Rules [`Hashlife`] can't run: those that give birth with no neighbours, which would fill the
+/// unbounded universe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FillsEmptySpace(pub GameOfLifeRules);
+
+impl fmt::Display for FillsEmptySpace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Hashlife can't run {}, which fills empty space", self.0)
+    }
+}
+
+impl Error for FillsEmptySpace {}
+
+impl Hashlife {
+    /// An empty universe, unless `rules` give birth with no neighbours.
+    pub fn new(rules: GameOfLifeRules) -> Result<Self, FillsEmptySpace> {
+        if rules.born(0) {
+            return Err(FillsEmptySpace(rules));
+        }
+        Ok(Self::empty_universe(rules))
+    }
+
+    fn empty_universe(rules: GameOfLifeRules) -> Self {
+        let leaf = |population| Node {
+            children: [DEAD; 4],
+            level: 0,
+            population,
+        };
+        let mut hashlife = Self {
+            rules,
+            nodes: vec![leaf(0), leaf(1)],
+            lookup: HashMap::default(),
+            successors: HashMap::default(),
+            empty: vec![DEAD],
+            root: DEAD,
+            generation: 0,
+        };
+        hashlife.root = hashlife.empty(3);
+        hashlife
+    }
+
+    /// A universe holding `pattern` with its top-left corner at `offset`, under the pattern's own
+    /// rules if it declares any.
+    pub fn from_pattern(
+        pattern: &LifePattern,
+        offset: (i64, i64),
+    ) -> Result<Self, FillsEmptySpace> {
+        let mut hashlife = Self::new(pattern.rules.unwrap_or_default())?;
+        for cell in &pattern.cells {
+            hashlife.set(offset.0 + cell.x as i64, offset.1 + cell.y as i64, true);
+        }
+        Ok(hashlife)
+    }
+
+    pub fn rules(&self) -> GameOfLifeRules {
+        self.rules
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn population(&self) -> u64 {
+        self.nodes[self.root as usize].population
+    }
+
+    /// Whether the cell at `(x, y)` is alive, y increasing downwards as in the GPU texture.
+    pub fn get(&self, x: i64, y: i64) -> bool {
+        let mut node = self.root;
+        let mut half = self.half_size();
+        if x < -half || x >= half || y < -half || y >= half {
+            return false;
+        }
+        // Coordinates relative to the current node's top-left corner.
+        let (mut x, mut y) = (x + half, y + half);
+        while self.node(node).level > 0 {
+            half = 1 << (self.node(node).level - 1);
+            let quadrant = (x >= half) as usize + 2 * (y >= half) as usize;
+            node = self.node(node).children[quadrant];
+            x %= half;
+            y %= half;
+        }
+        node == ALIVE
+    }
+
+    pub fn set(&mut self, x: i64, y: i64, alive: bool) {
+        while x < -self.half_size()
+            || x >= self.half_size()
+            || y < -self.half_size()
+            || y >= self.half_size()
+        {
+            self.expand();
+        }
+        let half = self.half_size();
+        self.root = self.set_in(self.root, x + half, y + half, alive);
+    }
+
+    /// Advances `2^log2_generations` generations.
+    pub fn step(&mut self, log2_generations: u8) {
+        if self.population() == 0 {
+            self.generation += 1 << log2_generations;
+            return;
+        }
+        // The result is the centered half of the root, and the pattern spreads at most one cell
+        // per generation, so pad until it sits inside the centered quarter.
+        while self.node(self.root).level < log2_generations + 2 || !self.is_padded() {
+            self.expand();
+        }
+        self.expand();
+        self.root = self.successor(self.root, log2_generations);
+        self.generation += 1 << log2_generations;
+
+        if self.nodes.len() > COLLECT_GARBAGE_AT {
+            self.collect_garbage();
+        }
+    }
+
+    /// Advances any number of generations, one power of two at a time.
+    pub fn run(&mut self, generations: u64) {
+        for j in 0..u64::BITS as u8 {
+            if generations >> j & 1 == 1 {
+                self.step(j);
+            }
+        }
+    }
+
+    /// Draws the cells `view` covers into an `Rgba8Unorm` image, live pixels white and the rest
+    /// transparent black, as the compute shader displays Life-like rules. Zoomed out, a pixel is
+    /// alive when any cell it covers is.
+    pub fn render(&self, view: &HashlifeView, image: &mut Image) {
+        let size = image.texture_descriptor.size;
+        let (width, height) = (size.width as i64, size.height as i64);
+        image.data.fill(0);
+
+        let half = self.half_size();
+        let mut stack = vec![(self.root, -half, -half)];
+        while let Some((node, x, y)) = stack.pop() {
+            let Node {
+                children,
+                level,
+                population,
+            } = *self.node(node);
+            if population == 0 {
+                continue;
+            }
+
+            let (px, py) = (
+                view.to_pixel(x - view.origin.0),
+                view.to_pixel(y - view.origin.1),
+            );
+            let pixels = 1i64 << (level as i32 - view.zoom as i32).clamp(0, MAX_LEVEL as i32);
+            if px >= width || py >= height || px + pixels <= 0 || py + pixels <= 0 {
+                continue;
+            }
+
+            if level as i32 <= (view.zoom as i32).max(0) {
+                for row in py.max(0)..(py + pixels).min(height) {
+                    let start = (row * width + px.max(0)) as usize * 4;
+                    let end = (row * width + (px + pixels).min(width)) as usize * 4;
+                    image.data[start..end].fill(255);
+                }
+            } else {
+                let half = 1 << (level - 1);
+                stack.extend([
+                    (children[0], x, y),
+                    (children[1], x + half, y),
+                    (children[2], x, y + half),
+                    (children[3], x + half, y + half),
+                ]);
+            }
+        }
+    }
+
+    fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id as usize]
+    }
+
+    fn half_size(&self) -> i64 {
+        1 << (self.node(self.root).level - 1)
+    }
+
+    fn join(&mut self, children: [NodeId; 4]) -> NodeId {
+        if let Some(&id) = self.lookup.get(&children) {
+            return id;
+        }
+        let id = self.nodes.len() as NodeId;
+        self.nodes.push(Node {
+            children,
+            level: self.node(children[0]).level + 1,
+            population: children
+                .iter()
+                .map(|&child| self.node(child).population)
+                .sum(),
+        });
+        self.lookup.insert(children, id);
+        id
+    }
+
+    fn empty(&mut self, level: u8) -> NodeId {
+        while self.empty.len() <= level as usize {
+            let below = *self.empty.last().unwrap();
+            let empty = self.join([below; 4]);
+            self.empty.push(empty);
+        }
+        self.empty[level as usize]
+    }
+
+    /// Doubles the root's size, keeping it centered.
+    fn expand(&mut self) {
+        let Node {
+            children: [nw, ne, sw, se],
+            level,
+            ..
+        } = *self.node(self.root);
+        assert!(
+            level < MAX_LEVEL,
+            "Hashlife universe outgrew i64 coordinates"
+        );
+        let e = self.empty(level - 1);
+        let children = [
+            self.join([e, e, e, nw]),
+            self.join([e, e, ne, e]),
+            self.join([e, sw, e, e]),
+            self.join([se, e, e, e]),
+        ];
+        self.root = self.join(children);
+    }
+
+    /// Whether every live cell is inside the root's centered half.
+    fn is_padded(&self) -> bool {
+        let grandchildren = |quadrant: usize| self.node(self.node(self.root).children[quadrant]);
+        // The grandchildren nearest the center, by quadrant.
+        let inner = [3, 2, 1, 0];
+        (0..4).all(|quadrant| {
+            let node = grandchildren(quadrant);
+            node.population == self.node(node.children[inner[quadrant]]).population
+        })
+    }
+
+    fn set_in(&mut self, node: NodeId, x: i64, y: i64, alive: bool) -> NodeId {
+        let Node {
+            mut children,
+            level,
+            ..
+        } = *self.node(node);
+        if level == 0 {
+            return if alive { ALIVE } else { DEAD };
+        }
+        let half = 1 << (level - 1);
+        let quadrant = (x >= half) as usize + 2 * (y >= half) as usize;
+        children[quadrant] = self.set_in(children[quadrant], x % half, y % half, alive);
+        self.join(children)
+    }
+
+    /// The centered half of `node`, `2^j` generations on. `j` may be at most `level - 2`.
+    fn successor(&mut self, node: NodeId, j: u8) -> NodeId {
+        let Node {
+            children: [nw, ne, sw, se],
+            level,
+            population,
+        } = *self.node(node);
+        if population == 0 {
+            return self.empty(level - 1);
+        }
+        if let Some(&result) = self.successors.get(&(node, j)) {
+            return result;
+        }
+
+        let result = if level == 2 {
+            self.step_4x4(node)
+        } else {
+            // The 4x4 grandchildren, row by row.
+            let mut grid = [[DEAD; 4]; 4];
+            for (quadrant, child) in [nw, ne, sw, se].into_iter().enumerate() {
+                for (i, grandchild) in self.node(child).children.into_iter().enumerate() {
+                    grid[quadrant / 2 * 2 + i / 2][quadrant % 2 * 2 + i % 2] = grandchild;
+                }
+            }
+            // The nine overlapping sub-squares, advanced `2^(j - 1)` generations when both passes
+            // step, or only centered when the second pass does all the stepping.
+            let both_passes = j == level - 2;
+            let mut nine = [[DEAD; 3]; 3];
+            for (y, row) in nine.iter_mut().enumerate() {
+                for (x, sub_result) in row.iter_mut().enumerate() {
+                    let sub_square = self.join(square(&grid, x, y));
+                    *sub_result = if both_passes {
+                        self.successor(sub_square, j - 1)
+                    } else {
+                        self.centered(sub_square)
+                    };
+                }
+            }
+
+            let mut result = [DEAD; 4];
+            for (i, result) in result.iter_mut().enumerate() {
+                let quadrant = self.join(square(&nine, i % 2, i / 2));
+                *result = self.successor(quadrant, if both_passes { j - 1 } else { j });
+            }
+            self.join(result)
+        };
+
+        self.successors.insert((node, j), result);
+        result
+    }
+
+    /// The centered half of `node`, unchanged.
+    fn centered(&mut self, node: NodeId) -> NodeId {
+        let [nw, ne, sw, se] = self.node(node).children;
+        let children = [
+            self.node(nw).children[3],
+            self.node(ne).children[2],
+            self.node(sw).children[1],
+            self.node(se).children[0],
+        ];
+        self.join(children)
+    }
+
+    /// The centered 2x2 cells of a 4x4 node, one generation on.
+    fn step_4x4(&mut self, node: NodeId) -> NodeId {
+        let cell = |x: usize, y: usize| {
+            let quadrant = self.node(node).children[x / 2 + 2 * (y / 2)];
+            self.node(quadrant).children[x % 2 + 2 * (y % 2)] == ALIVE
+        };
+        let next = |x: usize, y: usize| {
+            let neighbours = (y - 1..=y + 1)
+                .flat_map(|ny| (x - 1..=x + 1).map(move |nx| (nx, ny)))
+                .filter(|&(nx, ny)| (nx, ny) != (x, y) && cell(nx, ny))
+                .count();
+            if self.rules.next(cell(x, y), neighbours as u32) {
+                ALIVE
+            } else {
+                DEAD
+            }
+        };
+        let children = [next(1, 1), next(2, 1), next(1, 2), next(2, 2)];
+        self.join(children)
+    }
+
+    /// Rebuilds the node store from the root alone, forgetting unreachable nodes and results.
+    fn collect_garbage(&mut self) {
+        let rules = self.rules;
+        let old = std::mem::replace(self, Self::empty_universe(rules));
+        let mut copied = HashMap::default();
+        copied.insert(DEAD, DEAD);
+        copied.insert(ALIVE, ALIVE);
+        self.root = self.copy_from(&old, old.root, &mut copied);
+        self.generation = old.generation;
+    }
+
+    fn copy_from(
+        &mut self,
+        old: &Hashlife,
+        node: NodeId,
+        copied: &mut HashMap<NodeId, NodeId>,
+    ) -> NodeId {
+        if let Some(&id) = copied.get(&node) {
+            return id;
+        }
+        let children = old
+            .node(node)
+            .children
+            .map(|child| self.copy_from(old, child, copied));
+        let id = self.join(children);
+        copied.insert(node, id);
+        id
+    }
+}
+
+/// The 2x2 block of `cells` with its top-left corner at `(x, y)`, in quadrant order.
+fn square<const N: usize>(cells: &[[NodeId; N]; N], x: usize, y: usize) -> [NodeId; 4] {
+    [
+        cells[y][x],
+        cells[y][x + 1],
+        cells[y + 1][x],
+        cells[y + 1][x + 1],
+    ]
+}
+
+/// The part of a [`Hashlife`] universe [`HashlifePlugin`] draws, and how fast it runs.
+#[derive(Resource, Clone, Debug)]
+pub struct HashlifeView {
+    /// The cell drawn at the image's top-left pixel.
+    pub origin: (i64, i64),
+    /// Each pixel covers `2^zoom` cells along each axis, or each cell `2^-zoom` pixels when
+    /// negative.
+    pub zoom: i8,
+    /// Generations advanced per frame, as a power of two.
+    pub log2_step: u8,
+}
+
+impl HashlifeView {
+    fn to_pixel(&self, cell: i64) -> i64 {
+        if self.zoom >= 0 {
+            cell >> self.zoom
+        } else {
+            cell << -self.zoom
+        }
+    }
+}
+
+impl Default for HashlifeView {
+    /// Centered on the origin at one pixel per cell, one generation per frame.
+    fn default() -> Self {
+        let size = GameOfLifeGrid::default().size;
+        Self {
+            origin: (-(size.x as i64) / 2, -(size.y as i64) / 2),
+            zoom: 0,
+            log2_step: 0,
+        }
+    }
+}
+
+pub struct HashlifePlugin;
+
+impl Plugin for HashlifePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HashlifeView>()
+            .add_system(step_hashlife)
+            .add_system(render_hashlife.after(step_hashlife));
+    }
+}
+
+fn step_hashlife(hashlife: Option<ResMut<Hashlife>>, view: Res<HashlifeView>) {
+    if let Some(mut hashlife) = hashlife {
+        hashlife.step(view.log2_step);
+    }
+}
+
+fn render_hashlife(
+    hashlife: Option<Res<Hashlife>>,
+    view: Res<HashlifeView>,
+    sprites: Query<&Handle<Image>, With<GameOfLifeSprite>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let Some(hashlife) = hashlife else {
+        return;
+    };
+    for texture in &sprites {
+        if let Some(image) = images.get_mut(texture) {
+            hashlife.render(&view, image);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::cpu::LifeGrid, *};
+
+    const R_PENTOMINO: [(i64, i64); 5] = [(1, 0), (2, 0), (0, 1), (1, 1), (1, 2)];
+
+    fn with_cells(cells: &[(i64, i64)], offset: i64) -> Hashlife {
+        let mut hashlife = Hashlife::new(GameOfLifeRules::CONWAY).unwrap();
+        for &(x, y) in cells {
+            hashlife.set(x + offset, y + offset, true);
+        }
+        hashlife
+    }
+
+    /// Whether the two universes agree on every cell within `radius` of the origin, and have the
+    /// same population.
+    fn same_cells(a: &Hashlife, b: &Hashlife, radius: i64) -> bool {
+        a.population() == b.population()
+            && (-radius..radius).all(|y| (-radius..radius).all(|x| a.get(x, y) == b.get(x, y)))
+    }
+
+    #[test]
+    fn run_matches_the_cpu_grid() {
+        let glider = [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)];
+        // Large enough that neither pattern reaches the grid's dead edges.
+        let (size, offset) = (256, 128);
+        for cells in [&glider, &R_PENTOMINO] {
+            for generations in [0, 1, 5, 37, 128, 300] {
+                let mut hashlife = with_cells(cells, 0);
+                hashlife.run(generations);
+
+                let mut grid = LifeGrid::new(size, size);
+                for &(x, y) in cells {
+                    grid.set((x + offset) as usize, (y + offset) as usize, true);
+                }
+                grid.run(&GameOfLifeRules::CONWAY, generations as usize);
+
+                assert_eq!(hashlife.generation(), generations);
+                assert_eq!(hashlife.population() as usize, grid.population());
+                for y in 0..size {
+                    for x in 0..size {
+                        let cell = (x as i64 - offset, y as i64 - offset);
+                        assert_eq!(
+                            hashlife.get(cell.0, cell.1),
+                            grid.get(x, y),
+                            "{:?} after {} generations",
+                            cell,
+                            generations
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn steps_match_single_generations() {
+        for j in 0..8 {
+            let mut leap = with_cells(&R_PENTOMINO, -1);
+            leap.step(j);
+
+            let mut single = with_cells(&R_PENTOMINO, -1);
+            for _ in 0..1 << j {
+                single.step(0);
+            }
+
+            assert_eq!(leap.generation(), 1 << j);
+            assert!(same_cells(&leap, &single, 160), "2^{} generations", j);
+        }
+    }
+
+    #[test]
+    fn rejects_rules_that_fill_empty_space() {
+        let rules = "B03/S23".parse().unwrap();
+        assert_eq!(Hashlife::new(rules).err(), Some(FillsEmptySpace(rules)));
+
+        let pattern = LifePattern {
+            rules: Some(rules),
+            ..default()
+        };
+        assert!(Hashlife::from_pattern(&pattern, (0, 0)).is_err());
+    }
+}
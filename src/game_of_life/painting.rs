@@ -0,0 +1,129 @@
+//! Drawing cells with the mouse: the left button draws, the right button erases, and `[` and `]`
+//! change the brush size, which can also be set on `GameOfLifeBrush` in the debug UI along with the
+//! state drawn.
+
+use bevy::{
+    prelude::*,
+    render::{extract_resource::ExtractResource, render_resource::ShaderType},
+};
+
+use super::{GameOfLifeGrid, GameOfLifeSprite, MAX_STATES};
+
+const DRAW_BUTTON: MouseButton = MouseButton::Left;
+const ERASE_BUTTON: MouseButton = MouseButton::Right;
+const SMALLER_KEY: KeyCode = KeyCode::LBracket;
+const LARGER_KEY: KeyCode = KeyCode::RBracket;
+
+#[derive(Resource, Reflect, Debug, Clone)]
+#[reflect(Resource)]
+pub struct GameOfLifeBrush {
+    /// In cells. Half a cell paints single cells.
+    pub radius: f32,
+    /// The [`CellularAutomaton`](super::CellularAutomaton) state drawn, such as Wireworld's
+    /// conductors. Erasing sets state `0`.
+    pub state: u32,
+}
+
+impl Default for GameOfLifeBrush {
+    fn default() -> Self {
+        Self {
+            radius: 0.5,
+            state: 1,
+        }
+    }
+}
+
+/// A capsule of cells to set, in texel coordinates.
+#[derive(Clone, Copy, Debug, ShaderType)]
+pub(super) struct PaintStroke {
+    pub start: Vec2,
+    pub end: Vec2,
+    pub radius: f32,
+    pub state: u32,
+}
+
+/// The strokes painted this frame, applied by the `paint` entry point before the frame's
+/// generations run.
+#[derive(Resource, Clone, Default, ExtractResource)]
+pub(super) struct GameOfLifePaint {
+    pub strokes: Vec<PaintStroke>,
+    /// The texels the strokes cover, clamped to the grid.
+    pub min: UVec2,
+    pub max: UVec2,
+}
+
+pub(super) fn resize_brush(
+    keys: Res<Input<KeyCode>>,
+    grid: Res<GameOfLifeGrid>,
+    mut brush: ResMut<GameOfLifeBrush>,
+) {
+    if keys.just_pressed(SMALLER_KEY) {
+        brush.radius = (brush.radius / 2.0).max(0.5);
+    }
+    if keys.just_pressed(LARGER_KEY) {
+        brush.radius = (brush.radius * 2.0).min(grid.texture_size().max_element() as f32);
+    }
+}
+
+/// Maps the cursor through the 2D camera onto the sprite's texels, joining each frame's position
+/// to the last so fast strokes have no gaps.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn paint_cells(
+    windows: Res<Windows>,
+    buttons: Res<Input<MouseButton>>,
+    brush: Res<GameOfLifeBrush>,
+    grid: Res<GameOfLifeGrid>,
+    cameras: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+    sprites: Query<(&Sprite, &GlobalTransform), With<GameOfLifeSprite>>,
+    mut paint: ResMut<GameOfLifePaint>,
+    mut last_texel: Local<Option<Vec2>>,
+) {
+    paint.strokes.clear();
+
+    let state = if buttons.pressed(DRAW_BUTTON) {
+        brush.state.min(MAX_STATES - 1)
+    } else if buttons.pressed(ERASE_BUTTON) {
+        0
+    } else {
+        *last_texel = None;
+        return;
+    };
+    let (Some(cursor), Ok((camera, camera_transform)), Ok((sprite, sprite_transform))) = (
+        windows.get_primary().and_then(Window::cursor_position),
+        cameras.get_single(),
+        sprites.get_single(),
+    ) else {
+        return;
+    };
+    let Some(ray) = camera.viewport_to_world(camera_transform, cursor) else {
+        return;
+    };
+
+    let size = grid.texture_size().as_vec2();
+    let local = sprite_transform
+        .affine()
+        .inverse()
+        .transform_point3(ray.origin)
+        .truncate();
+    // Sprites are centered, y up, while texel rows run downwards.
+    let texel = (local / sprite.custom_size.unwrap_or(size) * Vec2::new(1.0, -1.0) + 0.5) * size;
+
+    let start = last_texel.unwrap_or(texel);
+    *last_texel = Some(texel);
+    let stroke = PaintStroke {
+        start,
+        end: texel,
+        radius: brush.radius,
+        state,
+    };
+
+    let reach = Vec2::splat(brush.radius);
+    let min = (start.min(texel) - reach).floor().max(Vec2::ZERO);
+    let max = (start.max(texel) + reach).ceil().min(size);
+    if min.cmpge(max).any() {
+        return;
+    }
+    paint.strokes.push(stroke);
+    paint.min = min.as_uvec2();
+    paint.max = max.as_uvec2();
+}
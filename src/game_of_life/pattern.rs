@@ -0,0 +1,414 @@
+//! Life patterns loaded from the standard RLE (`.rle`), plaintext (`.cells`) and Life 1.06
+//! (`.lif`, `.life`) formats.
+
+use std::{error::Error, fmt};
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+    utils::BoxedFuture,
+};
+
+use super::{cpu::LifeGrid, grid::MAX_SIZE, GameOfLifeRules, RuleParseError};
+
+#[derive(TypeUuid, Debug, Clone, Default, PartialEq, Eq)]
+#[uuid = "c5774eb3-d3e7-4672-a83d-aabbe1592f48"]
+pub struct LifePattern {
+    pub name: Option<String>,
+    pub width: u32,
+    pub height: u32,
+    /// Live cells relative to the pattern's top-left corner, with y increasing downwards.
+    pub cells: Vec<UVec2>,
+    /// The rules the file declares, if any.
+    pub rules: Option<GameOfLifeRules>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatternFormat {
+    Rle,
+    Plaintext,
+    Life106,
+}
+
+impl PatternFormat {
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "rle" => Some(PatternFormat::Rle),
+            "cells" => Some(PatternFormat::Plaintext),
+            "lif" | "life" => Some(PatternFormat::Life106),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum PatternParseError {
+    /// A missing or malformed header line, such as RLE's `x = 3, y = 3`.
+    Header(String),
+    Rules(RuleParseError),
+    UnexpectedCharacter {
+        line: usize,
+        character: char,
+    },
+    /// A Life 1.06 line that is not a pair of integer coordinates.
+    Coordinates {
+        line: usize,
+    },
+    /// A pattern wider or taller than the largest grid, `MAX_SIZE` cells.
+    TooLarge,
+}
+
+impl fmt::Display for PatternParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatternParseError::Header(header) => write!(f, "invalid header `{}`", header),
+            PatternParseError::Rules(error) => write!(f, "invalid rules: {}", error),
+            PatternParseError::UnexpectedCharacter { line, character } => {
+                write!(f, "unexpected `{}` on line {}", character, line)
+            }
+            PatternParseError::Coordinates { line } => {
+                write!(f, "expected `x y` coordinates on line {}", line)
+            }
+            PatternParseError::TooLarge => {
+                write!(f, "pattern is larger than {0}x{0} cells", MAX_SIZE)
+            }
+        }
+    }
+}
+
+impl Error for PatternParseError {}
+
+impl From<RuleParseError> for PatternParseError {
+    fn from(error: RuleParseError) -> Self {
+        PatternParseError::Rules(error)
+    }
+}
+
+impl LifePattern {
+    pub fn parse(text: &str, format: PatternFormat) -> Result<Self, PatternParseError> {
+        match format {
+            PatternFormat::Rle => Self::parse_rle(text),
+            PatternFormat::Plaintext => Self::parse_plaintext(text),
+            PatternFormat::Life106 => Self::parse_life_106(text),
+        }
+    }
+
+    /// Parses run-length encoded cells after an `x = 3, y = 3, rule = B3/S23` header. Any state
+    /// other than `b` or `.` counts as alive, and cells past the header's size grow the pattern.
+    pub fn parse_rle(text: &str) -> Result<Self, PatternParseError> {
+        let mut pattern = LifePattern::default();
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim()));
+
+        for (_, line) in lines.by_ref() {
+            if let Some(comment) = line.strip_prefix('#') {
+                match comment.split_at(comment.chars().next().map_or(0, char::len_utf8)) {
+                    ("N", name) => pattern.name = Some(name.trim().to_string()),
+                    ("r", rules) => pattern.rules = Some(rules.trim().parse()?),
+                    _ => {}
+                }
+                continue;
+            }
+            if line.is_empty() {
+                continue;
+            }
+
+            let header = || PatternParseError::Header(line.to_string());
+            // The rule comes last and may contain commas, as in `B3/S23:T100,100`.
+            let (sizes, rule) = match line.split_once("rule") {
+                Some((sizes, rule)) => (sizes, Some(rule)),
+                None => (line, None),
+            };
+            for field in sizes.split(',').filter(|field| !field.trim().is_empty()) {
+                let (key, value) = field.split_once('=').ok_or_else(header)?;
+                let size = || -> Result<u32, PatternParseError> {
+                    match value.trim().parse().map_err(|_| header())? {
+                        size if size > MAX_SIZE => Err(PatternParseError::TooLarge),
+                        size => Ok(size),
+                    }
+                };
+                match key.trim() {
+                    "x" => pattern.width = size()?,
+                    "y" => pattern.height = size()?,
+                    _ => {}
+                }
+            }
+            if let Some(rule) = rule {
+                let rule = rule.trim_start().strip_prefix('=').ok_or_else(header)?;
+                // Ignore bounded grid suffixes.
+                pattern.rules = Some(rule.split(':').next().unwrap().parse()?);
+            }
+            break;
+        }
+
+        let advance = |coordinate: u32, count: u32| {
+            coordinate
+                .checked_add(count)
+                .filter(|&coordinate| coordinate <= MAX_SIZE)
+                .ok_or(PatternParseError::TooLarge)
+        };
+        let mut position = UVec2::ZERO;
+        let mut run: u32 = 0;
+        'lines: for (number, line) in lines {
+            for character in line.chars() {
+                let count = run.max(1);
+                match character {
+                    '0'..='9' => {
+                        run = run
+                            .checked_mul(10)
+                            .and_then(|run| run.checked_add(character.to_digit(10).unwrap()))
+                            .ok_or(PatternParseError::TooLarge)?;
+                        continue;
+                    }
+                    'b' | '.' => position.x = advance(position.x, count)?,
+                    '$' => position = UVec2::new(0, advance(position.y, count)?),
+                    '!' => break 'lines,
+                    character if character.is_ascii_alphabetic() => {
+                        if advance(position.x, count)? > MAX_SIZE || position.y >= MAX_SIZE {
+                            return Err(PatternParseError::TooLarge);
+                        }
+                        for _ in 0..count {
+                            pattern.cells.push(position);
+                            position.x += 1;
+                        }
+                    }
+                    character if character.is_whitespace() => continue,
+                    character => {
+                        return Err(PatternParseError::UnexpectedCharacter {
+                            line: number,
+                            character,
+                        })
+                    }
+                }
+                run = 0;
+            }
+        }
+
+        pattern.fit_cells();
+        Ok(pattern)
+    }
+
+    /// Parses rows of `.` and `O` after `!` comment lines, the first of which may be `!Name:`.
+    pub fn parse_plaintext(text: &str) -> Result<Self, PatternParseError> {
+        let mut pattern = LifePattern::default();
+        let mut y = 0;
+
+        for (number, line) in text.lines().enumerate().map(|(i, line)| (i + 1, line)) {
+            if let Some(comment) = line.strip_prefix('!') {
+                if let Some(name) = comment.strip_prefix("Name:") {
+                    pattern.name = Some(name.trim().to_string());
+                }
+                continue;
+            }
+            if y >= MAX_SIZE || line.trim_end().len() > MAX_SIZE as usize {
+                return Err(PatternParseError::TooLarge);
+            }
+            for (x, character) in line.trim_end().chars().enumerate() {
+                match character {
+                    '.' => {}
+                    'O' | '*' => pattern.cells.push(UVec2::new(x as u32, y)),
+                    character => {
+                        return Err(PatternParseError::UnexpectedCharacter {
+                            line: number,
+                            character,
+                        })
+                    }
+                }
+            }
+            pattern.width = pattern.width.max(line.trim_end().len() as u32);
+            y += 1;
+        }
+
+        pattern.height = y;
+        Ok(pattern)
+    }
+
+    /// Parses the `#Life 1.06` header followed by one `x y` pair per live cell. Coordinates may be
+    /// negative, and are moved so the pattern's top-left corner is at the origin.
+    pub fn parse_life_106(text: &str) -> Result<Self, PatternParseError> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim()));
+        match lines.next() {
+            Some((_, "#Life 1.06")) => {}
+            header => {
+                let header = header.map_or("", |(_, line)| line);
+                return Err(PatternParseError::Header(header.to_string()));
+            }
+        }
+
+        let mut cells = Vec::new();
+        for (number, line) in lines {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let coordinates = line
+                .split_whitespace()
+                .map(str::parse)
+                .collect::<Result<Vec<i32>, _>>();
+            match coordinates.as_deref() {
+                Ok(&[x, y]) => cells.push(IVec2::new(x, y)),
+                _ => return Err(PatternParseError::Coordinates { line: number }),
+            }
+        }
+
+        let min = cells
+            .iter()
+            .copied()
+            .reduce(IVec2::min)
+            .unwrap_or(IVec2::ZERO);
+        let max = cells.iter().copied().reduce(IVec2::max).unwrap_or(min);
+        // Widened, since the coordinates may span the whole of `i32`.
+        let span = |min: i32, max: i32| max as i64 - min as i64 + 1;
+        if span(min.x, max.x) > MAX_SIZE as i64 || span(min.y, max.y) > MAX_SIZE as i64 {
+            return Err(PatternParseError::TooLarge);
+        }
+        let mut pattern = LifePattern {
+            cells: cells
+                .into_iter()
+                .map(|cell| (cell - min).as_uvec2())
+                .collect(),
+            ..default()
+        };
+        pattern.fit_cells();
+        Ok(pattern)
+    }
+
+    /// Grows the size to cover every live cell.
+    fn fit_cells(&mut self) {
+        for cell in &self.cells {
+            self.width = self.width.max(cell.x + 1);
+            self.height = self.height.max(cell.y + 1);
+        }
+    }
+
+    /// The pattern as an `Rgba8Unorm` image, live cells white and dead ones transparent black, as
+    /// the shader's `init` reads them.
+    pub fn to_image(&self) -> Image {
+        let (width, height) = (self.width.max(1), self.height.max(1));
+        let (columns, rows) = (width as usize, height as usize);
+        let mut data = vec![0; columns * rows * 4];
+        for cell in &self.cells {
+            let texel = (cell.y as usize * columns + cell.x as usize) * 4;
+            data[texel..texel + 4].fill(255);
+        }
+        Image::new(
+            Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            data,
+            TextureFormat::Rgba8Unorm,
+        )
+    }
+
+    /// Sets the pattern's live cells in `grid` with its top-left corner at `offset`, clipping
+    /// cells that fall outside. Dead cells of the pattern are left as they were when `combine`
+    /// is set, and cleared otherwise.
+    pub fn stamp(&self, grid: &mut LifeGrid, offset: IVec2, combine: bool) {
+        let cell = |position: IVec2| (position.x as usize, position.y as usize);
+        if !combine {
+            for y in 0..self.height as i32 {
+                for x in 0..self.width as i32 {
+                    let position = offset + IVec2::new(x, y);
+                    if position.cmpge(IVec2::ZERO).all() {
+                        let (x, y) = cell(position);
+                        grid.set(x, y, false);
+                    }
+                }
+            }
+        }
+        for &live in &self.cells {
+            let position = offset + live.as_ivec2();
+            if position.cmpge(IVec2::ZERO).all() {
+                let (x, y) = cell(position);
+                grid.set(x, y, true);
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct LifePatternLoader;
+
+impl AssetLoader for LifePatternLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let path = load_context.path().display().to_string();
+            let format = load_context
+                .path()
+                .extension()
+                .and_then(|extension| PatternFormat::from_extension(&extension.to_string_lossy()))
+                .ok_or_else(|| bevy::asset::Error::msg(format!("{}: unknown format", path)))?;
+
+            let text = std::str::from_utf8(bytes)
+                .map_err(|error| bevy::asset::Error::msg(format!("{}: {}", path, error)))?;
+            let pattern = LifePattern::parse(text, format)
+                .map_err(|error| bevy::asset::Error::msg(format!("{}: {}", path, error)))?;
+
+            load_context.set_default_asset(LoadedAsset::new(pattern));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["rle", "cells", "lif", "life"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cells(pattern: &LifePattern) -> Vec<UVec2> {
+        let mut cells = pattern.cells.clone();
+        cells.sort_by_key(|cell| (cell.y, cell.x));
+        cells
+    }
+
+    #[test]
+    fn parses_a_glider_in_every_format() {
+        let rle = LifePattern::parse_rle("#N Glider\nx = 3, y = 3, rule = B3/S23\nbo$2bo$3o!");
+        let plaintext = LifePattern::parse_plaintext("!Name: Glider\n.O.\n..O\nOOO");
+        let life_106 = LifePattern::parse_life_106("#Life 1.06\n0 -1\n1 0\n-1 1\n0 1\n1 1");
+        let glider = [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)].map(|(x, y)| UVec2::new(x, y));
+
+        for pattern in [rle.unwrap(), plaintext.unwrap(), life_106.unwrap()] {
+            assert_eq!((pattern.width, pattern.height), (3, 3));
+            assert_eq!(cells(&pattern), glider);
+        }
+    }
+
+    #[test]
+    fn rejects_patterns_larger_than_the_grid() {
+        let too_large = |result: Result<LifePattern, PatternParseError>| {
+            matches!(result, Err(PatternParseError::TooLarge))
+        };
+
+        assert!(too_large(LifePattern::parse_rle("x = 9000, y = 1\no!")));
+        assert!(too_large(LifePattern::parse_rle(
+            "x = 1, y = 1\n99999999999o!"
+        )));
+        assert!(too_large(LifePattern::parse_rle(
+            "x = 1, y = 1\n8000b8000b8000bo!"
+        )));
+        assert!(too_large(LifePattern::parse_rle(
+            "x = 1, y = 1\n8000$8000$o!"
+        )));
+        assert!(too_large(LifePattern::parse_plaintext(&"O".repeat(9000))));
+        assert!(too_large(LifePattern::parse_life_106(
+            "#Life 1.06\n-2147483648 0\n2147483647 0"
+        )));
+    }
+}
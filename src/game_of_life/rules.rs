@@ -0,0 +1,182 @@
+//! Life-like birth/survival rules in B/S notation.
+
+use std::{error::Error, fmt, str::FromStr};
+
+/// The birth and survival counts of a Life-like automaton. Bit `n` of each mask is set when a cell
+/// with `n` live neighbours is born or survives.
+///
+/// Parses from B/S strings such as `"B36/S23"`, and from the older S/B form `"23/36"`. The compute
+/// shader runs them as [`CellularAutomaton::life`](super::CellularAutomaton::life).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GameOfLifeRules {
+    pub birth: u16,
+    pub survival: u16,
+}
+
+impl GameOfLifeRules {
+    /// B3/S23
+    pub const CONWAY: Self = Self::new(&[3], &[2, 3]);
+    /// B36/S23
+    pub const HIGHLIFE: Self = Self::new(&[3, 6], &[2, 3]);
+    /// B3678/S34678
+    pub const DAY_AND_NIGHT: Self = Self::new(&[3, 6, 7, 8], &[3, 4, 6, 7, 8]);
+    /// B2/S
+    pub const SEEDS: Self = Self::new(&[2], &[]);
+
+    pub const fn new(birth: &[u8], survival: &[u8]) -> Self {
+        Self {
+            birth: mask(birth),
+            survival: mask(survival),
+        }
+    }
+
+    pub fn born(&self, neighbours: u32) -> bool {
+        self.birth >> neighbours & 1 == 1
+    }
+
+    pub fn survives(&self, neighbours: u32) -> bool {
+        self.survival >> neighbours & 1 == 1
+    }
+
+    /// Whether a cell is alive in the next generation.
+    pub fn next(&self, alive: bool, neighbours: u32) -> bool {
+        if alive {
+            self.survives(neighbours)
+        } else {
+            self.born(neighbours)
+        }
+    }
+}
+
+const fn mask(counts: &[u8]) -> u16 {
+    let mut mask = 0;
+    let mut i = 0;
+    while i < counts.len() {
+        mask |= 1 << counts[i];
+        i += 1;
+    }
+    mask
+}
+
+impl Default for GameOfLifeRules {
+    fn default() -> Self {
+        Self::CONWAY
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuleParseError {
+    /// The rule is not two `/` separated parts, one `B` and one `S`.
+    Malformed(String),
+    /// A neighbour count outside `0..=8`.
+    InvalidCount(char),
+}
+
+impl fmt::Display for RuleParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuleParseError::Malformed(rule) => {
+                write!(f, "`{}` is not a B/S rule such as `B3/S23`", rule)
+            }
+            RuleParseError::InvalidCount(count) => {
+                write!(f, "`{}` is not a neighbour count between 0 and 8", count)
+            }
+        }
+    }
+}
+
+impl Error for RuleParseError {}
+
+impl FromStr for GameOfLifeRules {
+    type Err = RuleParseError;
+
+    fn from_str(rule: &str) -> Result<Self, Self::Err> {
+        let malformed = || RuleParseError::Malformed(rule.to_string());
+        let (first, second) = rule.trim().split_once('/').ok_or_else(malformed)?;
+
+        let mut birth = None;
+        let mut survival = None;
+        for (index, part) in [first, second].into_iter().enumerate() {
+            let part = part.trim();
+            let (target, counts) = match part.chars().next() {
+                Some('B' | 'b') => (&mut birth, &part[1..]),
+                Some('S' | 's') => (&mut survival, &part[1..]),
+                // Without letters the rule is in S/B order.
+                _ if index == 0 => (&mut survival, part),
+                _ => (&mut birth, part),
+            };
+            if target.is_some() {
+                return Err(malformed());
+            }
+            *target = Some(parse_counts(counts)?);
+        }
+
+        Ok(Self {
+            birth: birth.ok_or_else(malformed)?,
+            survival: survival.ok_or_else(malformed)?,
+        })
+    }
+}
+
+fn parse_counts(counts: &str) -> Result<u16, RuleParseError> {
+    counts
+        .chars()
+        .try_fold(0, |mask, count| match count.to_digit(10) {
+            Some(n) if n <= 8 => Ok(mask | 1 << n),
+            _ => Err(RuleParseError::InvalidCount(count)),
+        })
+}
+
+impl fmt::Display for GameOfLifeRules {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let counts = |mask: u16| {
+            (0..=8u16)
+                .filter(|&n| mask >> n & 1 == 1)
+                .map(|n| char::from(b'0' + n as u8))
+                .collect::<String>()
+        };
+        write!(f, "B{}/S{}", counts(self.birth), counts(self.survival))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_bs_notation() {
+        assert_eq!("B36/S23".parse(), Ok(GameOfLifeRules::HIGHLIFE));
+        assert_eq!("B3678/S34678".parse(), Ok(GameOfLifeRules::DAY_AND_NIGHT));
+        assert_eq!("b2/s".parse(), Ok(GameOfLifeRules::SEEDS));
+        assert_eq!(" s23 / b36 ".parse(), Ok(GameOfLifeRules::HIGHLIFE));
+    }
+
+    #[test]
+    fn parses_the_older_sb_order() {
+        assert_eq!("23/3".parse(), Ok(GameOfLifeRules::CONWAY));
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        let malformed = |rule: &str| RuleParseError::Malformed(rule.to_string());
+
+        assert_eq!("B3/B3".parse::<GameOfLifeRules>(), Err(malformed("B3/B3")));
+        assert_eq!("B3".parse::<GameOfLifeRules>(), Err(malformed("B3")));
+        assert_eq!(
+            "B9/S23".parse::<GameOfLifeRules>(),
+            Err(RuleParseError::InvalidCount('9'))
+        );
+    }
+
+    #[test]
+    fn displays_in_bs_notation() {
+        for rules in [
+            GameOfLifeRules::CONWAY,
+            GameOfLifeRules::DAY_AND_NIGHT,
+            GameOfLifeRules::SEEDS,
+        ] {
+            assert_eq!(rules.to_string().parse(), Ok(rules));
+        }
+        assert_eq!(GameOfLifeRules::HIGHLIFE.to_string(), "B36/S23");
+    }
+}
@@ -0,0 +1,2 @@
+pub mod custom_material;
+pub mod game_of_life;
@@ -0,0 +1,423 @@
+//! Chunked marching-cubes terrain, streamed in around the camera and persisted to disk.
+//!
+//! The left mouse button digs into the terrain in front of the camera and the right one builds it
+//! up. Edited chunks are saved with F5 or when they unload, and take precedence over generated
+//! ones so they survive config reloads that keep the chunk and cell size.
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use bevy_flycam::FlyCam;
+use marching_cubes::{
+    ambient_occlusion::AmbientOcclusion,
+    cpu::MarchingCubes,
+    grid::DensityGrid,
+    mesh::build_mesh,
+    storage::{Quantization, WorldSave},
+};
+use noise::{NoiseFn, SuperSimplex};
+
+use crate::{config::WorldConfig, terrain_material::TerrainMaterial};
+
+const SAVE_KEY: KeyCode = KeyCode::F5;
+const DIG_BUTTON: MouseButton = MouseButton::Left;
+const BUILD_BUTTON: MouseButton = MouseButton::Right;
+/// Distance from the camera to the centre of the brush, in cells.
+const BRUSH_DISTANCE: f32 = 6.0;
+/// Brush radius, in cells.
+const BRUSH_RADIUS: f32 = 3.0;
+/// Density added per second at the centre of the brush, fading to nothing at its edge.
+const BRUSH_STRENGTH: f32 = 4.0;
+/// Saved densities are quantized within this distance of the iso level. Sculpting clamps to the
+/// same band, so digging out more than is saved doesn't take longer to fill back in.
+const SAVED_BAND: f32 = 2.0;
+
+pub struct TerrainPlugin;
+
+impl Plugin for TerrainPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_to_stage(CoreStage::PreUpdate, configure_terrain)
+            .add_system(unload_distant_chunks.before(load_chunks_near_camera))
+            .add_system(load_chunks_near_camera)
+            .add_system(sculpt_terrain)
+            .add_system(save_terrain);
+    }
+}
+
+#[derive(Resource)]
+struct Terrain {
+    config: WorldConfig,
+    save: WorldSave,
+    noise: SuperSimplex,
+    loaded: HashSet<IVec3>,
+    /// Saved chunks around `prefetched` that are yet to be loaded.
+    saved: HashMap<IVec3, DensityGrid>,
+    /// The chunk the camera was in when `saved` was read.
+    prefetched: Option<IVec3>,
+    material: Handle<TerrainMaterial>,
+}
+
+/// A loaded chunk and the density it was meshed from.
+#[derive(Component)]
+pub struct TerrainChunk {
+    pub coord: IVec3,
+    grid: DensityGrid,
+    /// Set when `grid` is borrowed mutably, and cleared once the chunk is saved.
+    edited: bool,
+}
+
+impl TerrainChunk {
+    pub fn grid(&self) -> &DensityGrid {
+        &self.grid
+    }
+
+    /// Marks the chunk as edited, so the next save writes it.
+    pub fn grid_mut(&mut self) -> &mut DensityGrid {
+        self.edited = true;
+        &mut self.grid
+    }
+
+    pub fn is_edited(&self) -> bool {
+        self.edited
+    }
+}
+
+impl Terrain {
+    fn chunk_extent(&self) -> f32 {
+        self.config.chunk_size as f32 * self.config.cell_size
+    }
+
+    /// Chunk `(0, 0, 0)` is centred on the world origin.
+    fn chunk_origin(&self, coord: IVec3) -> Vec3 {
+        coord.as_vec3() * self.chunk_extent() - Vec3::splat(self.chunk_extent() / 2.0)
+    }
+
+    fn chunk_containing(&self, position: Vec3) -> IVec3 {
+        ((position + Vec3::splat(self.chunk_extent() / 2.0)) / self.chunk_extent())
+            .floor()
+            .as_ivec3()
+    }
+
+    fn value_from_noise(&self, translation: Vec3) -> f32 {
+        let scale = self.config.noise_scale;
+
+        1.0 - (self.noise.get([
+            translation.x as f64 / scale,
+            translation.y as f64 / scale,
+            translation.z as f64 / scale,
+        ]) * 2.0) as f32
+    }
+
+    fn generate_chunk(&self, coord: IVec3) -> DensityGrid {
+        DensityGrid::from_fn(
+            UVec3::splat(self.config.chunk_size + 1),
+            self.chunk_origin(coord),
+            self.config.cell_size,
+            |position| self.value_from_noise(position),
+        )
+    }
+
+    fn mesh_chunk(&self, grid: &DensityGrid) -> Mesh {
+        let triangles = MarchingCubes::new(self.config.iso_level).polygonize_grid(grid);
+        let mut mesh = build_mesh(&triangles);
+        if self.config.ambient_occlusion {
+            // Sample the noise rather than the chunk's grid, so rays can leave the chunk. This
+            // means the occlusion ignores edits.
+            AmbientOcclusion::new(self.config.iso_level, self.config.cell_size * 4.0)
+                .bake(&|position: Vec3| self.value_from_noise(position), &mut mesh);
+        }
+        mesh
+    }
+}
+
+/// Rebuilds the terrain from scratch whenever the [`WorldConfig`] resource changes, including
+/// when it is first loaded.
+fn configure_terrain(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<TerrainMaterial>>,
+    config: Option<Res<WorldConfig>>,
+    terrain: Option<Res<Terrain>>,
+    chunks: Query<Entity, With<TerrainChunk>>,
+) {
+    let Some(config) = config else {
+        return;
+    };
+    if !config.is_changed() {
+        return;
+    }
+
+    // Keep a band around the iso level; everything further out meshes the same.
+    let save_directory = save_directory(&config);
+    let save = match WorldSave::open(
+        &save_directory,
+        Quantization {
+            min: config.iso_level - SAVED_BAND,
+            max: config.iso_level + SAVED_BAND,
+        },
+    ) {
+        Ok(save) => save,
+        Err(error) => {
+            error!(
+                "failed to open the world save in {}: {}",
+                save_directory.display(),
+                error
+            );
+            return;
+        }
+    };
+
+    for chunk in &chunks {
+        commands.entity(chunk).despawn();
+    }
+
+    let material = match terrain {
+        Some(terrain) => terrain.material.clone(),
+        None => materials.add(TerrainMaterial::default()),
+    };
+
+    commands.insert_resource(Terrain {
+        config: config.clone(),
+        save,
+        noise: SuperSimplex::new(config.seed),
+        loaded: HashSet::new(),
+        saved: HashMap::new(),
+        prefetched: None,
+        material,
+    });
+}
+
+/// Chunks of one size don't fit a world of another, so each chunk and cell size is saved in its
+/// own subdirectory of the configured one.
+fn save_directory(config: &WorldConfig) -> PathBuf {
+    Path::new(&config.save_directory).join(format!(
+        "chunk_{}_cell_{}",
+        config.chunk_size, config.cell_size
+    ))
+}
+
+/// Loads the missing chunk closest to the camera, at most one per frame. Saved chunks are read
+/// from their region files whenever the camera enters another chunk; chunks that were never
+/// saved are generated from noise.
+fn load_chunks_near_camera(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    terrain: Option<ResMut<Terrain>>,
+    camera: Query<&Transform, With<FlyCam>>,
+) {
+    let (Some(mut terrain), Ok(camera)) = (terrain, camera.get_single()) else {
+        return;
+    };
+    let center = terrain.chunk_containing(camera.translation);
+    let radius = terrain.config.load_radius;
+
+    if terrain.prefetched != Some(center) {
+        let terrain = &mut *terrain;
+        terrain.saved = match terrain.save.load_chunks_near(center, radius) {
+            Ok(chunks) => chunks
+                .into_iter()
+                .filter(|(coord, _)| !terrain.loaded.contains(coord))
+                .collect(),
+            Err(error) => {
+                error!(
+                    "failed to load saved terrain near chunk {}: {}",
+                    center, error
+                );
+                HashMap::new()
+            }
+        };
+        terrain.prefetched = Some(center);
+    }
+
+    let mut missing = Vec::new();
+    for z in -radius..=radius {
+        for y in -radius..=radius {
+            for x in -radius..=radius {
+                let coord = center + IVec3::new(x, y, z);
+                if !terrain.loaded.contains(&coord) {
+                    missing.push(coord);
+                }
+            }
+        }
+    }
+
+    let Some(coord) = missing
+        .into_iter()
+        .min_by_key(|coord| (*coord - center).abs().max_element())
+    else {
+        return;
+    };
+
+    let grid = terrain
+        .saved
+        .remove(&coord)
+        .unwrap_or_else(|| terrain.generate_chunk(coord));
+
+    commands.spawn((
+        MaterialMeshBundle {
+            mesh: meshes.add(terrain.mesh_chunk(&grid)),
+            material: terrain.material.clone(),
+            ..default()
+        },
+        TerrainChunk {
+            coord,
+            grid,
+            edited: false,
+        },
+    ));
+    terrain.loaded.insert(coord);
+}
+
+/// Despawns chunks further than the load radius from the camera. Edited chunks are saved first,
+/// and stay loaded if that fails so their edits aren't lost.
+fn unload_distant_chunks(
+    mut commands: Commands,
+    terrain: Option<ResMut<Terrain>>,
+    camera: Query<&Transform, With<FlyCam>>,
+    chunks: Query<(Entity, &TerrainChunk)>,
+) {
+    let (Some(mut terrain), Ok(camera)) = (terrain, camera.get_single()) else {
+        return;
+    };
+    let center = terrain.chunk_containing(camera.translation);
+    let radius = terrain.config.load_radius;
+
+    let distant: Vec<_> = chunks
+        .iter()
+        .filter(|(_, chunk)| (chunk.coord - center).abs().max_element() > radius)
+        .collect();
+    if distant.is_empty() {
+        return;
+    }
+
+    let edited = distant
+        .iter()
+        .filter(|(_, chunk)| chunk.is_edited())
+        .map(|(_, chunk)| (chunk.coord, chunk.grid()));
+    let saved = match terrain.save.save_chunks(edited) {
+        Ok(()) => true,
+        Err(error) => {
+            error!(
+                "failed to save terrain chunks before unloading them: {}",
+                error
+            );
+            false
+        }
+    };
+
+    for (entity, chunk) in distant {
+        if saved || !chunk.is_edited() {
+            commands.entity(entity).despawn();
+            terrain.loaded.remove(&chunk.coord);
+        }
+    }
+}
+
+/// Adds density to the samples within the brush in front of the camera while a sculpting button
+/// is held, then remeshes the chunks it touched.
+fn sculpt_terrain(
+    buttons: Res<Input<MouseButton>>,
+    time: Res<Time>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    terrain: Option<Res<Terrain>>,
+    camera: Query<&Transform, With<FlyCam>>,
+    mut chunks: Query<(&mut TerrainChunk, &Handle<Mesh>)>,
+) {
+    let (Some(terrain), Ok(camera)) = (terrain, camera.get_single()) else {
+        return;
+    };
+    // Densities above the iso level are open air.
+    let direction = match (buttons.pressed(DIG_BUTTON), buttons.pressed(BUILD_BUTTON)) {
+        (true, false) => 1.0,
+        (false, true) => -1.0,
+        _ => return,
+    };
+
+    let cell_size = terrain.config.cell_size;
+    let center = camera.translation + camera.forward() * BRUSH_DISTANCE * cell_size;
+    let radius = BRUSH_RADIUS * cell_size;
+    let amount = direction * BRUSH_STRENGTH * time.delta_seconds();
+    let (min_value, max_value) = (
+        terrain.config.iso_level - SAVED_BAND,
+        terrain.config.iso_level + SAVED_BAND,
+    );
+
+    for (mut chunk, mesh) in &mut chunks {
+        let Some((min, max)) = samples_near(chunk.grid(), center, radius) else {
+            continue;
+        };
+
+        let grid = chunk.grid_mut();
+        for z in min.z..=max.z {
+            for y in min.y..=max.y {
+                for x in min.x..=max.x {
+                    let distance = grid.position(x, y, z).distance(center);
+                    if distance < radius {
+                        let index = grid.index(x, y, z);
+                        grid.values[index] = (grid.values[index]
+                            + amount * (1.0 - distance / radius))
+                            .clamp(min_value, max_value);
+                    }
+                }
+            }
+        }
+
+        if let Some(mesh) = meshes.get_mut(mesh) {
+            *mesh = terrain.mesh_chunk(chunk.grid());
+        }
+    }
+}
+
+/// The smallest and largest sample indices of the box around a sphere, or `None` if the sphere
+/// misses the grid.
+fn samples_near(grid: &DensityGrid, center: Vec3, radius: f32) -> Option<(UVec3, UVec3)> {
+    let last = grid.dimensions.as_vec3() - Vec3::ONE;
+    let min = ((center - radius - grid.origin) / grid.cell_size)
+        .ceil()
+        .max(Vec3::ZERO);
+    let max = ((center + radius - grid.origin) / grid.cell_size)
+        .floor()
+        .min(last);
+
+    min.cmple(max)
+        .all()
+        .then(|| (min.as_uvec3(), max.as_uvec3()))
+}
+
+/// Writes the chunks edited since they were loaded or last saved.
+fn save_terrain(
+    keys: Res<Input<KeyCode>>,
+    terrain: Option<Res<Terrain>>,
+    mut chunks: Query<&mut TerrainChunk>,
+) {
+    let Some(terrain) = terrain else {
+        return;
+    };
+    if !keys.just_pressed(SAVE_KEY) {
+        return;
+    }
+
+    let edited = chunks
+        .iter()
+        .filter(|chunk| chunk.is_edited())
+        .map(|chunk| (chunk.coord, chunk.grid()));
+    if let Err(error) = terrain.save.save_chunks(edited) {
+        error!("failed to save terrain: {}", error);
+        return;
+    }
+
+    let mut saved = 0;
+    for mut chunk in &mut chunks {
+        if chunk.edited {
+            chunk.edited = false;
+            saved += 1;
+        }
+    }
+    info!(
+        "saved {} edited terrain chunks to {}",
+        saved,
+        terrain.save.root().display()
+    );
+}
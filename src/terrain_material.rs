@@ -0,0 +1,230 @@
+//! A splatted terrain material. Up to four texture layers are projected triplanarly and blended
+//! by world height, slope and the optional per-vertex [`ATTRIBUTE_MATERIAL_WEIGHTS`]. Baked
+//! [`ATTRIBUTE_AMBIENT_OCCLUSION`] darkens ambient light when present.
+
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::{
+    pbr::{MaterialPipeline, MaterialPipelineKey},
+    prelude::*,
+    reflect::TypeUuid,
+    render::{
+        mesh::MeshVertexBufferLayout,
+        render_asset::RenderAssets,
+        render_resource::{
+            AsBindGroup, AsBindGroupShaderType, RenderPipelineDescriptor, ShaderRef, ShaderType,
+            SpecializedMeshPipelineError,
+        },
+    },
+};
+use marching_cubes::mesh::{ATTRIBUTE_AMBIENT_OCCLUSION, ATTRIBUTE_MATERIAL_WEIGHTS};
+
+/// Number of blended layers; matches the four channels of [`ATTRIBUTE_MATERIAL_WEIGHTS`].
+pub const TERRAIN_LAYERS: usize = 4;
+
+/// Where a layer shows up and how it is tinted.
+#[derive(Debug, Clone, Copy)]
+pub struct TerrainLayer {
+    /// Multiplied with the layer's albedo texture, or used as is when there is none.
+    pub tint: Color,
+    /// World units covered by one repeat of the layer's textures.
+    pub texture_scale: f32,
+    /// World heights between which the layer is fully visible.
+    pub height_range: Vec2,
+    /// Slopes, in radians from horizontal ground, between which the layer is fully visible.
+    pub slope_range: Vec2,
+}
+
+/// Splatted terrain material. Textures are sampled triplanarly from world position, so meshes
+/// need no UVs. They should use a repeating sampler; the sampler of `albedo_0` is shared by
+/// every layer.
+#[derive(AsBindGroup, Debug, Clone, TypeUuid)]
+#[uuid = "a6a4c733-db06-41b1-a32f-4d15052fa81a"]
+#[uniform(0, TerrainMaterialUniform)]
+pub struct TerrainMaterial {
+    pub layers: [TerrainLayer; TERRAIN_LAYERS],
+    /// Distance in world units over which neighbouring height bands fade into each other.
+    pub height_blend: f32,
+    /// Angle in radians over which neighbouring slope bands fade into each other.
+    pub slope_blend: f32,
+    pub perceptual_roughness: f32,
+    #[texture(1)]
+    #[sampler(9)]
+    pub albedo_0: Option<Handle<Image>>,
+    #[texture(2)]
+    pub albedo_1: Option<Handle<Image>>,
+    #[texture(3)]
+    pub albedo_2: Option<Handle<Image>>,
+    #[texture(4)]
+    pub albedo_3: Option<Handle<Image>>,
+    #[texture(5)]
+    pub normal_0: Option<Handle<Image>>,
+    #[texture(6)]
+    pub normal_1: Option<Handle<Image>>,
+    #[texture(7)]
+    pub normal_2: Option<Handle<Image>>,
+    #[texture(8)]
+    pub normal_3: Option<Handle<Image>>,
+}
+
+impl TerrainMaterial {
+    fn albedo_textures(&self) -> [&Option<Handle<Image>>; TERRAIN_LAYERS] {
+        [
+            &self.albedo_0,
+            &self.albedo_1,
+            &self.albedo_2,
+            &self.albedo_3,
+        ]
+    }
+
+    fn normal_maps(&self) -> [&Option<Handle<Image>>; TERRAIN_LAYERS] {
+        [
+            &self.normal_0,
+            &self.normal_1,
+            &self.normal_2,
+            &self.normal_3,
+        ]
+    }
+}
+
+/// Stands in for an unbounded height. Kept finite so the band fades in the shader stay
+/// well-defined.
+const FAR: f32 = 1.0e6;
+
+impl Default for TerrainMaterial {
+    /// Sand at the bottom, grass above it and snow on the peaks, with rock on steep slopes.
+    fn default() -> Self {
+        let flat = Vec2::new(0.0, 0.6);
+        Self {
+            layers: [
+                TerrainLayer {
+                    tint: Color::rgb(0.76, 0.70, 0.50),
+                    texture_scale: 4.0,
+                    height_range: Vec2::new(-FAR, -8.0),
+                    slope_range: flat,
+                },
+                TerrainLayer {
+                    tint: Color::rgb(0.30, 0.50, 0.20),
+                    texture_scale: 4.0,
+                    height_range: Vec2::new(-8.0, 16.0),
+                    slope_range: flat,
+                },
+                TerrainLayer {
+                    tint: Color::rgb(0.45, 0.42, 0.40),
+                    texture_scale: 8.0,
+                    height_range: Vec2::new(-FAR, FAR),
+                    slope_range: Vec2::new(0.6, FRAC_PI_2),
+                },
+                TerrainLayer {
+                    tint: Color::rgb(0.95, 0.95, 0.97),
+                    texture_scale: 4.0,
+                    height_range: Vec2::new(16.0, FAR),
+                    slope_range: flat,
+                },
+            ],
+            height_blend: 2.0,
+            slope_blend: 0.1,
+            perceptual_roughness: 0.8,
+            albedo_0: None,
+            albedo_1: None,
+            albedo_2: None,
+            albedo_3: None,
+            normal_0: None,
+            normal_1: None,
+            normal_2: None,
+            normal_3: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, ShaderType)]
+pub struct TerrainLayerUniform {
+    pub tint: Vec4,
+    pub height_range: Vec2,
+    pub slope_range: Vec2,
+    pub texture_scale: f32,
+}
+
+/// The GPU representation of the uniform data of a [`TerrainMaterial`].
+#[derive(Clone, Default, ShaderType)]
+pub struct TerrainMaterialUniform {
+    pub layers: [TerrainLayerUniform; TERRAIN_LAYERS],
+    pub height_blend: f32,
+    pub slope_blend: f32,
+    pub perceptual_roughness: f32,
+    /// Bit `i` is set when layer `i` has an albedo texture, bit `i + 4` when it has a normal map.
+    pub flags: u32,
+}
+
+impl AsBindGroupShaderType<TerrainMaterialUniform> for TerrainMaterial {
+    fn as_bind_group_shader_type(&self, _images: &RenderAssets<Image>) -> TerrainMaterialUniform {
+        let mut flags = 0;
+        for (i, (albedo, normal)) in self
+            .albedo_textures()
+            .into_iter()
+            .zip(self.normal_maps())
+            .enumerate()
+        {
+            if albedo.is_some() {
+                flags |= 1 << i;
+            }
+            if normal.is_some() {
+                flags |= 1 << (i + TERRAIN_LAYERS);
+            }
+        }
+
+        TerrainMaterialUniform {
+            layers: self.layers.map(|layer| TerrainLayerUniform {
+                tint: layer.tint.as_linear_rgba_f32().into(),
+                height_range: layer.height_range,
+                slope_range: layer.slope_range,
+                texture_scale: layer.texture_scale,
+            }),
+            height_blend: self.height_blend,
+            slope_blend: self.slope_blend,
+            perceptual_roughness: self.perceptual_roughness,
+            flags,
+        }
+    }
+}
+
+impl Material for TerrainMaterial {
+    fn vertex_shader() -> ShaderRef {
+        "shaders/terrain_material.wgsl".into()
+    }
+    fn fragment_shader() -> ShaderRef {
+        "shaders/terrain_material.wgsl".into()
+    }
+
+    fn specialize(
+        _pipeline: &MaterialPipeline<Self>,
+        descriptor: &mut RenderPipelineDescriptor,
+        layout: &MeshVertexBufferLayout,
+        _key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        let mut attributes = vec![
+            Mesh::ATTRIBUTE_POSITION.at_shader_location(0),
+            Mesh::ATTRIBUTE_NORMAL.at_shader_location(1),
+        ];
+        let mut shader_defs = Vec::new();
+        // Meshes without weights blend purely by height and slope.
+        if layout.contains(ATTRIBUTE_MATERIAL_WEIGHTS) {
+            attributes.push(ATTRIBUTE_MATERIAL_WEIGHTS.at_shader_location(2));
+            shader_defs.push(String::from("TERRAIN_MATERIAL_WEIGHTS"));
+        }
+        if layout.contains(ATTRIBUTE_AMBIENT_OCCLUSION) {
+            attributes.push(ATTRIBUTE_AMBIENT_OCCLUSION.at_shader_location(3));
+            shader_defs.push(String::from("TERRAIN_AMBIENT_OCCLUSION"));
+        }
+        descriptor
+            .vertex
+            .shader_defs
+            .extend(shader_defs.iter().cloned());
+        if let Some(fragment) = descriptor.fragment.as_mut() {
+            fragment.shader_defs.extend(shader_defs);
+        }
+
+        descriptor.vertex.buffers = vec![layout.get_layout(&attributes)?];
+        Ok(())
+    }
+}